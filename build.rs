@@ -0,0 +1,27 @@
+// Shell completions and the man page, generated from the same definition the binary parses
+#![allow(dead_code)]
+
+use clap::CommandFactory;
+use clap_complete::Shell;
+use std::{env, fs, io, path::PathBuf};
+
+include!("src/cli.rs");
+
+fn main() -> io::Result<()> {
+    println!("cargo:rerun-if-changed=src/cli.rs");
+    println!("cargo:rerun-if-env-changed=ZERO_LOCK_ASSETS_DIR");
+    // Packagers pick a directory, otherwise they stay in the build dir
+    let dir = match env::var_os("ZERO_LOCK_ASSETS_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is set by cargo")),
+    };
+    fs::create_dir_all(&dir)?;
+
+    let mut command = Args::command();
+    for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+        clap_complete::generate_to(shell, &mut command, "zero-lock", &dir)?;
+    }
+    let mut man = Vec::new();
+    clap_mangen::Man::new(command).render(&mut man)?;
+    fs::write(dir.join("zero-lock.1"), man)
+}
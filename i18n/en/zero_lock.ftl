@@ -0,0 +1,80 @@
+## Lock screen
+
+away-for-less-than-a-minute = Away for less than a minute
+away-for-minutes = Away for {$minutes}m
+away-for-hours = Away for {$hours}h {$minutes}m
+failed-attempts = {$count ->
+    [one] 1 failed attempt since {$time}
+   *[other] {$count} failed attempts since {$time}
+}
+no-keyboard-input = The lock screen does not have keyboard input, not showing the prompt
+cover-hint = Press any key or swipe up to unlock
+last-login = Last login {$date}
+next-alarm = Alarm {$time}
+timer = Timer
+timer-running = {$label} {$remaining}
+timer-paused = {$label} {$remaining}, paused
+username-prompt = Username:
+password-prompt = Password:
+authentication-failed = Authentication failed
+prompt-step = Step {$step}
+password-expired = Your password has expired, choose a new one
+fingerprint-hint = Or touch the fingerprint reader
+fingerprint-no-match = Fingerprint not recognized
+fingerprint-password-expired = Password expired, unlock with the password to change it
+uptime-hours = Up {$hours}h {$minutes}m
+uptime-days = Up {$days ->
+    [one] 1 day
+   *[other] {$days} days
+} {$hours}h
+reboot-required = Restart required to finish updating
+other-users = {$count ->
+    [one] 1 other user logged in
+   *[other] {$count} other users logged in
+}
+
+## Warnings
+
+microphone-in-use = Microphone in use: {$apps}
+camera-in-use = Camera in use: {$apps}
+screen-shared = Screen shared with {$apps}
+suspend-countdown = Suspending in {$seconds}s — press any key to stay awake
+
+## Status
+
+vpn-connected = VPN: {$name}
+vpn-disconnected = VPN disconnected
+bluetooth-on = Bluetooth on
+bluetooth-off = Bluetooth off
+bluetooth-devices = {$count ->
+    [one] 1 device connected
+   *[other] {$count} devices connected
+}
+peripheral-battery = {$model} {$percentage}%
+ups-on-battery = On UPS battery, {$percentage}%
+ups-on-battery-minutes = On UPS battery, {$minutes} min left
+notifications = {$count ->
+    [one] 1 notification
+   *[other] {$count} notifications
+}
+updates-pending = {$count ->
+    [one] 1 update pending
+   *[other] {$count} updates pending
+}
+updates-reboot-required = Restart required to install updates
+unread = {$label}: {$count} unread
+do-not-disturb = Do not disturb
+notifications-on = Notifications on
+power-profile-performance = Performance
+power-profile-balanced = Balanced
+power-profile-power-saver = Power saver
+
+## Power menu
+
+suspend = Suspend
+hibernate = Hibernate
+restart = Restart
+shut-down = Shut down
+power-confirm = {$action}?
+cancel = Cancel
+switch-user = Switch User
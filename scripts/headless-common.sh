@@ -0,0 +1,67 @@
+# Shared by the headless scripts: a headless sway to run the locker against, and log checks.
+# Callers set LOCKER and source this file.
+
+TIMEOUT=10
+
+WORK="$(mktemp -d)"
+SWAY_PID=""
+
+stop_sway() {
+    if [ -n "$SWAY_PID" ]; then
+        kill "$SWAY_PID" 2>/dev/null || true
+        wait "$SWAY_PID" 2>/dev/null || true
+        SWAY_PID=""
+    fi
+}
+
+cleanup() {
+    stop_sway
+    rm -rf "$WORK"
+}
+trap cleanup EXIT
+
+fail() {
+    echo "FAIL: $*" >&2
+    echo "--- locker log ---" >&2
+    cat "$WORK/locker.log" >&2 || true
+    exit 1
+}
+
+# Waits until the locker log has at least COUNT lines matching PATTERN
+expect() {
+    local pattern="$1" count="${2:-1}"
+    for _ in $(seq $((TIMEOUT * 100))); do
+        if [ "$(grep -c -- "$pattern" "$WORK/locker.log" || true)" -ge "$count" ]; then
+            echo "ok: $pattern"
+            return 0
+        fi
+        sleep 0.01
+    done
+    fail "no \"$pattern\" in the log after ${TIMEOUT}s"
+}
+
+export XDG_RUNTIME_DIR="$WORK"
+export WLR_BACKENDS=headless
+export WLR_LIBINPUT_NO_DEVICES=1
+export WLR_RENDERER=pixman
+unset WAYLAND_DISPLAY DISPLAY
+
+start_sway() {
+    rm -f "$WORK"/wayland-* "$WORK"/sway-ipc.*
+    cat > "$WORK/sway.conf" <<CONF
+output HEADLESS-1 resolution 1920x1080
+CONF
+    sway --config "$WORK/sway.conf" > "$WORK/sway.log" 2>&1 &
+    SWAY_PID=$!
+
+    local socket=""
+    for _ in $(seq $((TIMEOUT * 10))); do
+        socket="$(find "$WORK" -maxdepth 1 -name 'wayland-*' ! -name '*.lock' -printf '%f\n' | head -n1)"
+        [ -n "$socket" ] && break
+        sleep 0.1
+    done
+    [ -n "$socket" ] || fail "sway did not start"
+    export WAYLAND_DISPLAY="$socket"
+    SWAYSOCK="$(find "$WORK" -maxdepth 1 -name 'sway-ipc.*' | head -n1)"
+    export SWAYSOCK
+}
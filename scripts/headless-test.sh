@@ -0,0 +1,37 @@
+#!/usr/bin/env bash
+# Runs the locker against a headless sway and checks the session lock state machine from its log:
+# locking, output hotplug and unplug, key input reaching the prompt, and relocking after a crash.
+#
+# Needs sway, swaymsg and wtype. Usage: scripts/headless-test.sh [path/to/zero-lock]
+
+set -euo pipefail
+
+LOCKER="$(realpath "${1:-target/debug/zero-lock}")"
+# shellcheck source=scripts/headless-common.sh
+. "$(dirname "$0")/headless-common.sh"
+
+start_sway
+
+echo "locking"
+RUST_LOG=info ZERO_LOCK_SOFTWARE_RENDERING=1 "$LOCKER" --supervised > "$WORK/locker.log" 2>&1 &
+expect "session locked"
+expect "focus surface"
+
+echo "output hotplug"
+swaymsg create_output > /dev/null
+expect "HEADLESS-2 at scale"
+swaymsg output HEADLESS-2 unplug > /dev/null
+expect ": removed"
+
+echo "key input"
+wtype "not the password"
+wtype -k Return
+expect "authentication failed"
+
+echo "relocking after a crash"
+# The supervised locker runs without arguments, the supervisor has --supervised
+pkill -KILL -f "^$LOCKER\$"
+expect "relaunching"
+expect "session locked" 2
+
+echo "all passed"
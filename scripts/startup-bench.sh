@@ -0,0 +1,30 @@
+#!/usr/bin/env bash
+# Measures how long the locker takes from launch until the session is locked, against a fresh
+# headless sway for every run.
+#
+# Needs sway. Usage: scripts/startup-bench.sh [path/to/zero-lock] [runs]
+
+set -euo pipefail
+
+LOCKER="$(realpath "${1:-target/release/zero-lock}")"
+RUNS="${2:-10}"
+
+# shellcheck source=scripts/headless-common.sh
+. "$(dirname "$0")/headless-common.sh"
+
+times=()
+for run in $(seq "$RUNS"); do
+    start_sway
+    start_ns="$(date +%s%N)"
+    RUST_LOG=info ZERO_LOCK_SOFTWARE_RENDERING=1 "$LOCKER" > "$WORK/locker.log" 2>&1 &
+    expect "session locked" > /dev/null
+    end_ns="$(date +%s%N)"
+    ms=$(((end_ns - start_ns) / 1000000))
+    times+=("$ms")
+    echo "run $run: locked after ${ms}ms, $(grep -o 'session locked [0-9]*ms' "$WORK/locker.log")"
+    # Killing the compositor takes the locked locker with it
+    stop_sway
+done
+
+sorted="$(printf '%s\n' "${times[@]}" | sort -n)"
+echo "min $(echo "$sorted" | head -n1)ms, median $(echo "$sorted" | sed -n "$(((RUNS + 1) / 2))p")ms, max $(echo "$sorted" | tail -n1)ms"
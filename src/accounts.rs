@@ -0,0 +1,94 @@
+//! Local users from AccountsService, for the user list in greeter mode
+
+use chrono::{DateTime, Local, TimeZone};
+use cosmic::iced::widget::image::Handle;
+use tokio::fs;
+use zbus::{dbus_proxy, zvariant::OwnedObjectPath, Connection, Result};
+
+#[dbus_proxy(
+    interface = "org.freedesktop.Accounts",
+    default_service = "org.freedesktop.Accounts",
+    default_path = "/org/freedesktop/Accounts"
+)]
+trait Accounts {
+    // Users that logged in before or are listed in the configuration, not hidden ones
+    fn list_cached_users(&self) -> Result<Vec<OwnedObjectPath>>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.Accounts.User",
+    default_service = "org.freedesktop.Accounts"
+)]
+trait User {
+    #[dbus_proxy(property)]
+    fn user_name(&self) -> Result<String>;
+
+    #[dbus_proxy(property)]
+    fn real_name(&self) -> Result<String>;
+
+    #[dbus_proxy(property)]
+    fn icon_file(&self) -> Result<String>;
+
+    #[dbus_proxy(property)]
+    fn login_time(&self) -> Result<i64>;
+
+    #[dbus_proxy(property)]
+    fn system_account(&self) -> Result<bool>;
+}
+
+#[derive(Clone, Debug)]
+pub struct UserInfo {
+    pub name: String,
+    // Full name, or the username if none is set
+    pub real_name: String,
+    pub icon_opt: Option<Handle>,
+    pub login_time_opt: Option<DateTime<Local>>,
+}
+
+async fn user_info(zbus: &Connection, path: OwnedObjectPath) -> Result<Option<UserInfo>> {
+    let user = UserProxy::builder(zbus).path(path)?.build().await?;
+    if user.system_account().await? {
+        return Ok(None);
+    }
+    let name = user.user_name().await?;
+    let real_name = user.real_name().await?;
+    let icon_file = user.icon_file().await?;
+    // Set even when the file doesn't exist
+    let icon_opt = match fs::read(&icon_file).await {
+        Ok(bytes) => Some(Handle::from_memory(bytes)),
+        Err(err) => {
+            log::debug!("no avatar for {} in {:?}: {}", name, icon_file, err);
+            None
+        }
+    };
+    let login_time_opt = match user.login_time().await? {
+        0 => None,
+        login_time => Local.timestamp_opt(login_time, 0).single(),
+    };
+    Ok(Some(UserInfo {
+        real_name: if real_name.is_empty() {
+            name.clone()
+        } else {
+            real_name
+        },
+        name,
+        icon_opt,
+        login_time_opt,
+    }))
+}
+
+/// Regular users sorted by full name
+pub async fn users() -> Result<Vec<UserInfo>> {
+    let zbus = Connection::system().await?;
+    let accounts = AccountsProxy::new(&zbus).await?;
+    let mut users = Vec::new();
+    for path in accounts.list_cached_users().await? {
+        match user_info(&zbus, path.clone()).await {
+            Ok(Some(info)) => users.push(info),
+            Ok(None) => {}
+            Err(err) => log::warn!("failed to read user {}: {}", path.as_str(), err),
+        }
+    }
+    users.sort_by(|a, b| a.real_name.cmp(&b.real_name));
+    Ok(users)
+}
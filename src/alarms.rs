@@ -0,0 +1,91 @@
+//! Alarms set in GNOME Clocks, for the next one under the clock
+//!
+//! Clocks has no D-Bus interface for them, they are only in its GSettings, read with `gsettings`
+//! like a shell would. COSMIC has no alarms of its own yet.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone};
+use std::io;
+use tokio::process::Command;
+
+const SCHEMA: &str = "org.gnome.clocks";
+const KEY: &str = "alarms";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Alarm {
+    time: NaiveTime,
+    // ISO weekdays, 1 for Monday, none for an alarm that rings once
+    days: Vec<u32>,
+}
+
+impl Alarm {
+    /// When it rings next after `now`
+    pub fn next(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        // A week ahead covers every repeating alarm, a one-off one rings within a day
+        (0..8).find_map(|days_ahead| {
+            let date = now.date_naive() + Duration::days(days_ahead);
+            let weekday = date.weekday().number_from_monday();
+            if !self.days.is_empty() && !self.days.contains(&weekday) {
+                return None;
+            }
+            let ring = Local
+                .from_local_datetime(&date.and_time(self.time))
+                .earliest()?;
+            (ring > now).then_some(ring)
+        })
+    }
+}
+
+/// The next of the alarms
+pub fn next(alarms: &[Alarm], now: DateTime<Local>) -> Option<DateTime<Local>> {
+    alarms.iter().filter_map(|alarm| alarm.next(now)).min()
+}
+
+/// The enabled alarms, empty without GNOME Clocks
+pub async fn load() -> io::Result<Vec<Alarm>> {
+    let output = Command::new("gsettings")
+        .arg("get")
+        .arg(SCHEMA)
+        .arg(KEY)
+        .output()
+        .await?;
+    // No schema without Clocks installed
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+    Ok(parse(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// GVariant text of an aa{sv}, "[{'name': <'Work'>, 'hour': <7>, 'minute': <30>, 'days': <[1, 2]>}]"
+fn parse(data: &str) -> Vec<Alarm> {
+    data.split('{')
+        .skip(1)
+        .filter_map(|dict| {
+            if value(dict, "active") == Some("false") {
+                return None;
+            }
+            let hour = value(dict, "hour")?.parse().ok()?;
+            let minute = value(dict, "minute")?.parse().ok()?;
+            let days = value(dict, "days")
+                .map(|days| {
+                    days.trim_matches(|c| c == '[' || c == ']')
+                        .split(',')
+                        .filter_map(|day| day.trim().parse().ok())
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some(Alarm {
+                time: NaiveTime::from_hms_opt(hour, minute, 0)?,
+                days,
+            })
+        })
+        .collect()
+}
+
+// What is inside the <> after 'key':, numbers and lists print without a type annotation
+fn value<'a>(dict: &'a str, key: &str) -> Option<&'a str> {
+    let start = dict.find(&format!("'{}': <", key))? + key.len() + 5;
+    let end = start + dict[start..].find('>')?;
+    let value = dict[start..end].trim();
+    // "int32 7" when GVariant can't tell the type from the value
+    Some(value.rsplit(' ').next().unwrap_or(value))
+}
@@ -0,0 +1,105 @@
+//! Ambient light from iio-sensor-proxy, so the lock screen isn't blinding in a dark room and stays
+//! readable in sunlight
+//!
+//! Sensors that only report vendor units can't be compared to these levels and are left alone.
+//! The sensor is released when the subscription's connection closes.
+
+use cosmic::iced::{
+    futures::{channel::mpsc, SinkExt, StreamExt},
+    subscription, Subscription,
+};
+use std::{any::TypeId, time::Duration};
+use tokio::time;
+use zbus::{dbus_proxy, Connection, Result};
+
+// Fully dimmed by MAX_DIM in the dark, brightening up to no dimming at DARK_ROOM
+const DARK_ROOM: f64 = 50.0;
+const MAX_DIM: f32 = 0.5;
+// Direct sunlight is 30000 lux and up, an overcast day outside around 1000
+const SUNLIGHT: f64 = 10000.0;
+// Smaller changes are sensor noise, and every reading redraws
+const MIN_CHANGE: f64 = 0.1;
+
+#[dbus_proxy(
+    interface = "net.hadess.SensorProxy",
+    default_service = "net.hadess.SensorProxy",
+    default_path = "/net/hadess/SensorProxy"
+)]
+trait Sensor {
+    fn claim_light(&self) -> Result<()>;
+
+    #[dbus_proxy(property)]
+    fn has_ambient_light(&self) -> Result<bool>;
+
+    // "lux", or "vendor" for arbitrary units
+    #[dbus_proxy(property)]
+    fn light_level_unit(&self) -> Result<String>;
+
+    #[dbus_proxy(property)]
+    fn light_level(&self) -> Result<f64>;
+}
+
+/// Dimming on top of the lock screen for this light level, none in a lit room
+pub fn dim(lux: f64) -> f32 {
+    if lux >= DARK_ROOM {
+        return 0.0;
+    }
+    MAX_DIM * (1.0 - (lux.max(0.0) / DARK_ROOM) as f32)
+}
+
+/// Bright enough to wash out text on the wallpaper
+pub fn sunlight(lux: f64) -> bool {
+    lux >= SUNLIGHT
+}
+
+/// Light levels in lux as they change
+pub fn subscription() -> Subscription<f64> {
+    struct AmbientLightSubscription;
+
+    subscription::channel(
+        TypeId::of::<AmbientLightSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            if let Err(err) = handler(&mut msg_tx).await {
+                log::info!("no ambient light: {}", err);
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+async fn handler(msg_tx: &mut mpsc::Sender<f64>) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let sensor = SensorProxy::new(&zbus).await?;
+    if !sensor.has_ambient_light().await? {
+        log::info!("no ambient light sensor");
+        return Ok(());
+    }
+    let unit = sensor.light_level_unit().await?;
+    if unit != "lux" {
+        log::info!("ambient light sensor reports {} units, ignoring it", unit);
+        return Ok(());
+    }
+    sensor.claim_light().await?;
+    let mut changes = sensor.receive_light_level_changed().await;
+    let mut last_opt: Option<f64> = None;
+    loop {
+        let lux = sensor.light_level().await?;
+        let changed = last_opt.map_or(true, |last| {
+            (lux - last).abs() > (last * MIN_CHANGE).max(1.0)
+        });
+        if changed {
+            last_opt = Some(lux);
+            if msg_tx.send(lux).await.is_err() {
+                // The locker stopped listening
+                return Ok(());
+            }
+        }
+        if changes.next().await.is_none() {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use cosmic::iced::{time, Subscription};
+use std::time::{Duration, Instant};
+
+// Roughly 60 frames per second while something animates
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+#[derive(Clone, Copy, Debug)]
+pub struct Timeline {
+    start: Instant,
+    duration: Duration,
+}
+
+impl Timeline {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            start: Instant::now(),
+            duration,
+        }
+    }
+
+    /// Linear progress from 0.0 to 1.0
+    pub fn linear(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        (self.start.elapsed().as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+
+    /// Eased progress from 0.0 to 1.0, fast at first and settling at the end
+    pub fn progress(&self) -> f32 {
+        let t = self.linear();
+        1.0 - (1.0 - t).powi(3)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.start.elapsed() >= self.duration
+    }
+}
+
+pub fn frames() -> Subscription<Instant> {
+    time::every(FRAME_INTERVAL)
+}
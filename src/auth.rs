@@ -0,0 +1,347 @@
+use cosmic::iced::{
+    futures::{channel::mpsc as futures_mpsc, executor, SinkExt},
+    subscription, Subscription,
+};
+use pam_client::{ConversationHandler, ErrorCode};
+use std::{
+    any::TypeId,
+    ffi::{CStr, CString},
+    process::Stdio,
+    thread,
+    time::{Duration, Instant},
+};
+use tokio::{process::Command, sync::mpsc, task, time};
+
+use crate::fl;
+
+// Every failure is answered this long after the last answer, so how fast it comes back doesn't
+// tell an unknown user from a wrong password or which module refused. Above pam_unix's delay of
+// two seconds with its random spread, slower failures are left as they are
+const FAILURE_RESPONSE_TIME: Duration = Duration::from_secs(3);
+
+/// How much longer to hold back a failure after the last prompt was answered at `answered_at`
+pub fn failure_padding(answered_at: Instant) -> Duration {
+    FAILURE_RESPONSE_TIME.saturating_sub(answered_at.elapsed())
+}
+
+// Needs a matching /etc/pam.d/zero-lock, see the README
+#[derive(Clone, Debug)]
+pub enum AuthEvent {
+    // Answers to prompts are sent through this channel
+    Channel(mpsc::Sender<String>),
+    // Greeter mode only, the conversation is now for this user
+    Username(String),
+    Prompt(String, bool),
+    Info(String),
+    Error(String),
+    // The attempt failed, with the reason to show
+    Failure(String),
+    Success,
+}
+
+/// Who may unlock, the session's user always can
+#[derive(Clone, Debug)]
+pub struct Unlockers {
+    pub owner: String,
+    pub users: Vec<String>,
+    pub groups: Vec<String>,
+}
+
+impl Unlockers {
+    // Anyone else may unlock, so the username is asked for first
+    fn shared(&self) -> bool {
+        !self.users.is_empty() || !self.groups.is_empty()
+    }
+
+    async fn allows(&self, user: &str) -> bool {
+        if user == self.owner || self.users.iter().any(|allowed| allowed == user) {
+            return true;
+        }
+        if self.groups.is_empty() {
+            return false;
+        }
+        // Through NSS, so LDAP and systemd-homed groups count
+        let output = match Command::new("id")
+            .arg("-nG")
+            .arg(user)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .await
+        {
+            Ok(output) if output.status.success() => output,
+            Ok(output) => {
+                log::warn!("failed to look up groups of {}: {}", user, output.status);
+                return false;
+            }
+            Err(err) => {
+                log::warn!("failed to look up groups of {}: {}", user, err);
+                return false;
+            }
+        };
+        String::from_utf8_lossy(&output.stdout)
+            .split_whitespace()
+            .any(|group| self.groups.iter().any(|allowed| allowed == group))
+    }
+}
+
+struct Conversation {
+    event_tx: futures_mpsc::Sender<AuthEvent>,
+    value_rx: mpsc::Receiver<String>,
+    answered_at_opt: Option<Instant>,
+}
+
+impl Conversation {
+    fn send(&mut self, event: AuthEvent) -> Result<(), ErrorCode> {
+        executor::block_on(self.event_tx.send(event)).map_err(|err| {
+            log::error!("failed to send auth event: {:?}", err);
+            ErrorCode::CONV_ERR
+        })
+    }
+
+    fn prompt_value(&mut self, prompt_c: &CStr, secret: bool) -> Result<CString, ErrorCode> {
+        let prompt = prompt_c.to_str().map_err(|err| {
+            log::error!("failed to convert prompt to UTF-8: {:?}", err);
+            ErrorCode::CONV_ERR
+        })?;
+
+        self.send(AuthEvent::Prompt(prompt.to_string(), secret))?;
+
+        let value = self.value_rx.blocking_recv().ok_or_else(|| {
+            log::error!("failed to receive prompt value: channel closed");
+            ErrorCode::CONV_ERR
+        })?;
+        self.answered_at_opt = Some(Instant::now());
+
+        CString::new(value).map_err(|err| {
+            log::error!("failed to convert value to C string: {:?}", err);
+            ErrorCode::CONV_ERR
+        })
+    }
+}
+
+impl ConversationHandler for Conversation {
+    fn prompt_echo_on(&mut self, prompt_c: &CStr) -> Result<CString, ErrorCode> {
+        self.prompt_value(prompt_c, false)
+    }
+
+    fn prompt_echo_off(&mut self, prompt_c: &CStr) -> Result<CString, ErrorCode> {
+        self.prompt_value(prompt_c, true)
+    }
+
+    fn text_info(&mut self, msg_c: &CStr) {
+        let msg = msg_c.to_string_lossy().into_owned();
+        let _ = self.send(AuthEvent::Info(msg));
+    }
+
+    fn error_msg(&mut self, msg_c: &CStr) {
+        let msg = msg_c.to_string_lossy().into_owned();
+        let _ = self.send(AuthEvent::Error(msg));
+    }
+}
+
+// Account checks have nothing to answer prompts with
+struct NoConversation;
+
+impl ConversationHandler for NoConversation {
+    fn prompt_echo_on(&mut self, _prompt_c: &CStr) -> Result<CString, ErrorCode> {
+        Err(ErrorCode::CONV_ERR)
+    }
+
+    fn prompt_echo_off(&mut self, _prompt_c: &CStr) -> Result<CString, ErrorCode> {
+        Err(ErrorCode::CONV_ERR)
+    }
+
+    fn text_info(&mut self, msg_c: &CStr) {
+        log::info!("account check: {}", msg_c.to_string_lossy());
+    }
+
+    fn error_msg(&mut self, msg_c: &CStr) {
+        log::warn!("account check: {}", msg_c.to_string_lossy());
+    }
+}
+
+/// The account part of `service` for a user authenticated outside of PAM, by fprintd, so expired
+/// or locked accounts and access rules still keep them out. The reason to show if they do
+pub async fn account(service: String, username: String) -> Result<(), String> {
+    let res = task::spawn_blocking(move || {
+        let mut context = pam_client::Context::new(&service, Some(&username), NoConversation)?;
+        context.acct_mgmt(pam_client::Flag::NONE)
+    })
+    .await;
+    match res {
+        Ok(Ok(())) => Ok(()),
+        // Changing it needs the old password, which the password prompt asks for
+        Ok(Err(err)) if err.code() == ErrorCode::NEW_AUTHTOK_REQD => {
+            Err(fl!("fingerprint-password-expired"))
+        }
+        Ok(Err(err)) => Err(err.to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+// The user PAM authenticated, a module may have changed it from `username`, and when the last
+// prompt was answered
+fn authenticate(
+    service: &str,
+    username: &str,
+    conversation: Conversation,
+) -> Result<(String, Option<Instant>), pam_client::Error> {
+    let mut context = pam_client::Context::new(service, Some(username), conversation)?;
+    let res = verify(&mut context).and_then(|()| context.user());
+    let answered_at_opt = context.conversation().answered_at_opt;
+    if res.is_err() {
+        if let Some(answered_at) = answered_at_opt {
+            thread::sleep(failure_padding(answered_at));
+        }
+    }
+    res.map(|user| (user, answered_at_opt))
+}
+
+// What a wrong password and a user who may not unlock both get, PAM's own message for the first
+// would tell them apart
+fn refused(err: &pam_client::Error) -> bool {
+    matches!(err.code(), ErrorCode::AUTH_ERR | ErrorCode::USER_UNKNOWN)
+}
+
+fn verify(context: &mut pam_client::Context<Conversation>) -> Result<(), pam_client::Error> {
+    context.authenticate(pam_client::Flag::NONE)?;
+    match context.acct_mgmt(pam_client::Flag::NONE) {
+        // The old, new and retyped password are asked for through the same conversation
+        Err(err) if err.code() == ErrorCode::NEW_AUTHTOK_REQD => {
+            log::info!("password expired, changing it");
+            let _ = context
+                .conversation_mut()
+                .send(AuthEvent::Info(fl!("password-expired")));
+            context.chauthtok(pam_client::Flag::CHANGE_EXPIRED_AUTHTOK)?;
+            Ok(())
+        }
+        res => res,
+    }
+}
+
+pub fn subscription(service: String, unlockers: Unlockers) -> Subscription<AuthEvent> {
+    struct AuthSubscription;
+
+    subscription::channel(
+        TypeId::of::<AuthSubscription>(),
+        16,
+        |mut event_tx| async move {
+            let mut attempt = 0;
+            loop {
+                attempt += 1;
+                let (value_tx, mut value_rx) = mpsc::channel(16);
+                if event_tx.send(AuthEvent::Channel(value_tx)).await.is_err() {
+                    // The locker stopped listening
+                    break;
+                }
+
+                let username = if unlockers.shared() {
+                    if event_tx
+                        .send(AuthEvent::Prompt(fl!("username-prompt"), false))
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                    let Some(username) = value_rx.recv().await else {
+                        break;
+                    };
+                    // Nothing typed is the session's user
+                    let username = match username.trim() {
+                        "" => unlockers.owner.clone(),
+                        username => username.to_string(),
+                    };
+                    if event_tx
+                        .send(AuthEvent::Username(username.clone()))
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                    username
+                } else {
+                    unlockers.owner.clone()
+                };
+
+                // Before PAM sees the name, other accounts' passwords aren't tried or counted by
+                // pam_faillock, and the failure looks like a wrong password after the same time
+                if !unlockers.allows(&username).await {
+                    log::warn!("{} may not unlock", username);
+                    if event_tx
+                        .send(AuthEvent::Prompt(fl!("password-prompt"), true))
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                    if value_rx.recv().await.is_none() {
+                        break;
+                    }
+                    time::sleep(failure_padding(Instant::now())).await;
+                    if event_tx
+                        .send(AuthEvent::Failure(fl!("authentication-failed")))
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                    continue;
+                }
+
+                let conversation = Conversation {
+                    event_tx: event_tx.clone(),
+                    value_rx,
+                    answered_at_opt: None,
+                };
+                let service = service.clone();
+                let span = tracing::info_span!("auth", attempt);
+                let res = task::spawn_blocking(move || {
+                    let _entered = span.entered();
+                    authenticate(&service, &username, conversation)
+                })
+                .await;
+                // Again for the user PAM ended up with
+                let allowed = match &res {
+                    Ok(Ok((user, _))) => unlockers.allows(user).await,
+                    _ => false,
+                };
+
+                let event = match res {
+                    Ok(Ok((user, _))) if allowed => {
+                        log::info!("authentication succeeded as {}", user);
+                        AuthEvent::Success
+                    }
+                    Ok(Ok((user, answered_at_opt))) => {
+                        log::warn!("{} authenticated but may not unlock", user);
+                        if let Some(answered_at) = answered_at_opt {
+                            time::sleep(failure_padding(answered_at)).await;
+                        }
+                        AuthEvent::Failure(fl!("authentication-failed"))
+                    }
+                    Ok(Err(err)) if refused(&err) => {
+                        log::warn!("authentication failed: {}", err);
+                        AuthEvent::Failure(fl!("authentication-failed"))
+                    }
+                    Ok(Err(err)) => {
+                        log::warn!("authentication failed: {}", err);
+                        AuthEvent::Failure(err.to_string())
+                    }
+                    Err(err) => {
+                        log::error!("authentication task failed: {}", err);
+                        AuthEvent::Error(err.to_string())
+                    }
+                };
+                let success = matches!(event, AuthEvent::Success);
+                // Unlocked, or the locker stopped listening
+                if event_tx.send(event).await.is_err() || success {
+                    break;
+                }
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
@@ -0,0 +1,104 @@
+//! `background = "dir:~/Pictures/walls"`, a random image from the directory on every lock
+//!
+//! The last picks are kept in the state dir, so a one-shot locker doesn't repeat them either.
+
+use std::{
+    collections::hash_map::RandomState,
+    env, fs,
+    hash::{BuildHasher, Hasher},
+    path::{Path, PathBuf},
+};
+
+const PREFIX: &str = "dir:";
+const HISTORY_FILE_NAME: &str = "zero-lock/recent-backgrounds";
+const EXTENSIONS: [&str; 6] = ["jpg", "jpeg", "png", "webp", "bmp", "gif"];
+
+/// The directory of a `dir:` background, with a leading `~` expanded
+pub fn directory(background: &Path) -> Option<PathBuf> {
+    let dir = background.to_str()?.strip_prefix(PREFIX)?;
+    match dir.strip_prefix("~/") {
+        Some(rest) => Some(PathBuf::from(env::var_os("HOME")?).join(rest)),
+        None => Some(PathBuf::from(dir)),
+    }
+}
+
+/// A random image in `dir` whose file name isn't in `exclude` or among the last `history` picks,
+/// which only holds when there are enough images left
+pub fn pick(dir: &Path, exclude: &[String], history: usize) -> Option<PathBuf> {
+    let entries = fs::read_dir(dir)
+        .map_err(|err| log::warn!("failed to read backgrounds in {}: {}", dir.display(), err))
+        .ok()?;
+    let images: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| {
+            Path::new(name)
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| {
+                    EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+                })
+        })
+        .filter(|name| !exclude.contains(name))
+        .collect();
+    if images.is_empty() {
+        log::warn!("no backgrounds in {}", dir.display());
+        return None;
+    }
+
+    let mut recent = load_history();
+    let fresh: Vec<&String> = images
+        .iter()
+        .filter(|name| {
+            !recent
+                .iter()
+                .rev()
+                .take(history)
+                .any(|recent| recent == *name)
+        })
+        .collect();
+    let candidates: Vec<&String> = if fresh.is_empty() {
+        images.iter().collect()
+    } else {
+        fresh
+    };
+    // Hashing nothing with a fresh random state, there is no rand dependency for one number
+    let random = RandomState::new().build_hasher().finish();
+    let name = candidates[(random % candidates.len() as u64) as usize].clone();
+    log::info!("picked background {}", name);
+
+    recent.retain(|recent| recent != &name);
+    recent.push(name.clone());
+    let skip = recent.len().saturating_sub(history);
+    save_history(&recent[skip..]);
+    Some(dir.join(name))
+}
+
+fn history_path() -> Option<PathBuf> {
+    let state_home = match env::var_os("XDG_STATE_HOME") {
+        Some(state_home) => PathBuf::from(state_home),
+        None => PathBuf::from(env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(state_home.join(HISTORY_FILE_NAME))
+}
+
+// File names, oldest first
+fn load_history() -> Vec<String> {
+    history_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|data| data.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+fn save_history(recent: &[String]) {
+    let Some(path) = history_path() else {
+        return;
+    };
+    let res = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(&path, recent.join("\n")));
+    if let Err(err) = res {
+        log::warn!("failed to save recent backgrounds to {:?}: {}", path, err);
+    }
+}
@@ -0,0 +1,88 @@
+use std::{fs, path::PathBuf};
+
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+const LEDS_DIR: &str = "/sys/class/leds";
+// LED class devices of keyboard backlights are named "<device>::kbd_backlight"
+const KEYBOARD_SUFFIX: &str = "::kbd_backlight";
+
+#[derive(Clone, Debug)]
+pub struct Backlight {
+    pub name: String,
+    max_brightness: u32,
+}
+
+impl Backlight {
+    /// First backlight device, firmware interfaces are preferred over raw ones
+    pub fn load() -> Option<Self> {
+        let mut devices = Vec::new();
+        for entry_res in fs::read_dir(BACKLIGHT_DIR).ok()? {
+            let entry = entry_res.ok()?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let kind = fs::read_to_string(entry.path().join("type")).unwrap_or_default();
+            let priority = match kind.trim() {
+                "firmware" => 0,
+                "platform" => 1,
+                _ => 2,
+            };
+            devices.push((priority, name));
+        }
+        devices.sort();
+
+        let (_, name) = devices.into_iter().next()?;
+        let max_brightness = read_u32(
+            PathBuf::from(BACKLIGHT_DIR)
+                .join(&name)
+                .join("max_brightness"),
+        )?;
+        if max_brightness == 0 {
+            return None;
+        }
+        Some(Self {
+            name,
+            max_brightness,
+        })
+    }
+
+    /// Current brightness in percent
+    pub fn brightness(&self) -> Option<f32> {
+        let brightness = read_u32(
+            PathBuf::from(BACKLIGHT_DIR)
+                .join(&self.name)
+                .join("brightness"),
+        )?;
+        Some(brightness as f32 * 100.0 / self.max_brightness as f32)
+    }
+
+    pub fn raw_brightness(&self, percent: f32) -> u32 {
+        let raw = (percent.clamp(0.0, 100.0) * self.max_brightness as f32 / 100.0).round();
+        // Never go fully dark, some panels turn off at zero
+        (raw as u32).max(1)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct KeyboardBacklight {
+    pub name: String,
+}
+
+impl KeyboardBacklight {
+    pub fn load() -> Option<Self> {
+        let mut names: Vec<String> = fs::read_dir(LEDS_DIR)
+            .ok()?
+            .filter_map(|entry_res| Some(entry_res.ok()?.file_name().to_string_lossy().to_string()))
+            .filter(|name| name.ends_with(KEYBOARD_SUFFIX))
+            .collect();
+        names.sort();
+        let name = names.into_iter().next()?;
+        Some(Self { name })
+    }
+
+    /// Current brightness in the device's own steps
+    pub fn brightness(&self) -> Option<u32> {
+        read_u32(PathBuf::from(LEDS_DIR).join(&self.name).join("brightness"))
+    }
+}
+
+fn read_u32(path: PathBuf) -> Option<u32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
@@ -0,0 +1,133 @@
+use cosmic::iced::{
+    futures::{channel::mpsc, stream, SinkExt, StreamExt},
+    Subscription,
+};
+use std::{any::TypeId, collections::HashMap};
+use zbus::{
+    dbus_proxy,
+    fdo::ObjectManagerProxy,
+    zvariant::{OwnedObjectPath, OwnedValue},
+    Connection, MatchRule, MessageStream, MessageType, Result,
+};
+
+use crate::retry;
+
+const ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+
+#[dbus_proxy(interface = "org.bluez.Adapter1", default_service = "org.bluez")]
+trait Adapter {
+    #[dbus_proxy(property)]
+    fn powered(&self) -> Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn set_powered(&self, powered: bool) -> Result<()>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BluetoothInfo {
+    pub powered: bool,
+    pub connected: usize,
+}
+
+pub fn subscription() -> Subscription<Option<BluetoothInfo>> {
+    struct BluetoothSubscription;
+
+    retry::subscription(
+        TypeId::of::<BluetoothSubscription>(),
+        "bluetooth",
+        Some(None),
+        handler,
+    )
+}
+
+async fn handler(mut msg_tx: mpsc::Sender<Option<BluetoothInfo>>) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let manager = ObjectManagerProxy::builder(&zbus)
+        .destination("org.bluez")?
+        .path("/")?
+        .build()
+        .await?;
+    // Adapters and devices coming and going, powering on and off and connecting
+    let rule = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .sender("org.bluez")?
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .path_namespace("/org/bluez")?
+        .build();
+    let properties = MessageStream::for_match_rule(rule, &zbus, None)
+        .await?
+        .map(|_| ());
+    let added = manager.receive_interfaces_added().await?.map(|_| ());
+    let removed = manager.receive_interfaces_removed().await?.map(|_| ());
+    let mut changes = stream::select(properties, stream::select(added, removed));
+
+    let mut last_opt = None;
+    loop {
+        let objects = manager.get_managed_objects().await?;
+        let info_opt = info(&objects);
+        if info_opt != last_opt {
+            if msg_tx.send(info_opt.clone()).await.is_err() {
+                // The locker stopped listening
+                return Ok(());
+            }
+            last_opt = info_opt;
+        }
+
+        if changes.next().await.is_none() {
+            return Ok(());
+        }
+    }
+}
+
+type ManagedObjects = HashMap<OwnedObjectPath, HashMap<String, HashMap<String, OwnedValue>>>;
+
+fn info(objects: &ManagedObjects) -> Option<BluetoothInfo> {
+    let mut has_adapter = false;
+    let mut powered = false;
+    let mut connected = 0;
+    for interfaces in objects.values() {
+        if let Some(adapter) = interfaces.get(ADAPTER_INTERFACE) {
+            has_adapter = true;
+            powered |= property(adapter, "Powered");
+        }
+        if let Some(device) = interfaces.get(DEVICE_INTERFACE) {
+            if property(device, "Connected") {
+                connected += 1;
+            }
+        }
+    }
+
+    // Hide the indicator entirely on machines without Bluetooth
+    if has_adapter {
+        Some(BluetoothInfo { powered, connected })
+    } else {
+        None
+    }
+}
+
+fn property(properties: &HashMap<String, OwnedValue>, name: &str) -> bool {
+    properties
+        .get(name)
+        .and_then(|value| value.downcast_ref::<bool>())
+        .copied()
+        .unwrap_or(false)
+}
+
+pub async fn set_powered(powered: bool) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let manager = ObjectManagerProxy::builder(&zbus)
+        .destination("org.bluez")?
+        .path("/")?
+        .build()
+        .await?;
+
+    for (path, interfaces) in manager.get_managed_objects().await? {
+        if interfaces.contains_key(ADAPTER_INTERFACE) {
+            let adapter = AdapterProxy::builder(&zbus).path(path)?.build().await?;
+            adapter.set_powered(powered).await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,140 @@
+// Command line definition, also included by build.rs for the completions and man page
+
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+/// A barebone screen locker implemented with libcosmic
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    #[command(subcommand)]
+    action_opt: Option<Action>,
+    /// Status message shown to passers-by, "Back at 14:00"
+    #[arg(short, long = "message", value_name = "MESSAGE")]
+    message_opt: Option<String>,
+    /// Relaunch the locker if it crashes
+    #[arg(long)]
+    supervised: bool,
+    /// Show the lock screen in a window, without locking the session
+    #[arg(long, conflicts_with_all = ["daemon", "supervised"])]
+    preview: bool,
+    /// Keep running and lock when idle or asked to
+    #[arg(long)]
+    daemon: bool,
+    /// Lock right away in daemon mode
+    #[arg(long, requires = "daemon")]
+    lock_now: bool,
+    /// Return once the session is locked, leaving the locker running, like swaylock -f
+    #[arg(short = 'f', long, conflicts_with_all = ["daemon", "preview"])]
+    daemonize: bool,
+    /// Config file read instead of ~/.config/zero-lock/config.toml
+    #[arg(short = 'C', long = "config", value_name = "PATH", global = true)]
+    config_opt: Option<PathBuf>,
+    /// Lock with the keys of [profiles.NAME] from the config file
+    #[arg(long = "profile", value_name = "NAME", global = true)]
+    profile_opt: Option<String>,
+    /// Image drawn behind the lock screen
+    #[arg(short, long = "image", value_name = "PATH")]
+    image_opt: Option<PathBuf>,
+    /// Background color, around the image or instead of one
+    #[arg(short, long = "color", value_name = "RRGGBB", value_parser = parse_color)]
+    color_opt: Option<String>,
+    /// How the image is fitted to each output
+    #[arg(
+        short,
+        long = "scaling",
+        value_name = "MODE",
+        value_parser = ["fill", "fit", "stretch", "center", "tile", "solid_color"],
+    )]
+    scaling_opt: Option<String>,
+    /// Show nothing while typing, like swaylock without its unlock indicator
+    #[arg(short = 'u', long)]
+    no_unlock_indicator: bool,
+    /// Any key unlocks without a password for this long after locking
+    #[arg(long = "grace", value_name = "SECONDS")]
+    grace_opt: Option<u64>,
+}
+
+// The same check as config::Rgb, which the build script can't reach
+fn parse_color(value: &str) -> Result<String, String> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    if matches!(hex.len(), 6 | 8) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(value.to_string())
+    } else {
+        Err("expected a color like 1e1e2e".to_string())
+    }
+}
+
+// "2560x1440"
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or_else(|| "expected a size like 2560x1440".to_string())?;
+    match (width.parse(), height.parse()) {
+        (Ok(width), Ok(height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err("expected a size like 2560x1440".to_string()),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Subcommand)]
+enum Action {
+    /// Lock the session, or ask the running locker to
+    Lock,
+    /// Print whether the running locker has the session locked
+    Status,
+    /// Print how often the running locker locked, unlocked and saw a failed attempt
+    Stats,
+    /// Unlock without authenticating, root only
+    Unlock,
+    /// Draw the lock screen to a PNG file without locking, in a window that closes again
+    Render {
+        /// PNG file to write
+        #[arg(short, long = "output", value_name = "PATH")]
+        output: PathBuf,
+        /// Window size in logical pixels
+        #[arg(
+            long,
+            value_name = "WIDTHxHEIGHT",
+            default_value = "1920x1080",
+            value_parser = parse_size,
+        )]
+        size: (u32, u32),
+    },
+}
+
+#[cfg(test)]
+mod cli_tests {
+    use super::*;
+    use clap::CommandFactory;
+    use proptest::prelude::*;
+    use std::ffi::OsString;
+
+    fn arg() -> impl Strategy<Value = String> {
+        prop_oneof![
+            prop::sample::select(vec![
+                "lock", "status", "stats", "unlock", "render", "-o", "--size", "-m", "--preview",
+                "--daemon", "--lock-now", "-f", "--supervised", "-C", "--profile", "-i", "-c",
+                "-s", "-u", "--grace", "--ring-color", "--indicator-radius", "--", "-",
+            ])
+            .prop_map(str::to_string),
+            "[0-9]{1,24}x[0-9]{1,24}",
+            "#?[0-9a-fA-F]{0,9}",
+            any::<String>(),
+        ]
+    }
+
+    #[test]
+    fn command_is_consistent() {
+        Args::command().debug_assert();
+    }
+
+    proptest! {
+        #[test]
+        fn parsing_never_panics(args in prop::collection::vec(arg(), 0..8)) {
+            let args = std::iter::once("zero-lock".to_string())
+                .chain(args)
+                .map(OsString::from);
+            let _ = Args::try_parse_from(crate::swaylock::strip_args(args));
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Clears the clipboard and primary selection through wlr-data-control
+//!
+//! Like output power, the protocol isn't exposed by iced, so a short-lived connection of its own
+//! replaces both selections with nothing.
+
+use std::error::Error;
+use tokio::task;
+use wayland_client::{
+    event_created_child,
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::{wl_registry::WlRegistry, wl_seat::WlSeat},
+    Connection, Dispatch, Proxy, QueueHandle,
+};
+use wayland_protocols_wlr::data_control::v1::client::{
+    zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+    zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+    zwlr_data_control_offer_v1::ZwlrDataControlOfferV1,
+};
+
+// Primary selection support
+const PRIMARY_VERSION: u32 = 2;
+
+struct State;
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        _state: &mut Self,
+        _registry: &WlRegistry,
+        _event: <WlRegistry as Proxy>::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlSeat, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _seat: &WlSeat,
+        _event: <WlSeat as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _manager: &ZwlrDataControlManagerV1,
+        _event: <ZwlrDataControlManagerV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _device: &ZwlrDataControlDeviceV1,
+        _event: zwlr_data_control_device_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+
+    // The current selection is announced as an offer, which is never read
+    event_created_child!(State, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrDataControlOfferV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        offer: &ZwlrDataControlOfferV1,
+        _event: <ZwlrDataControlOfferV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        offer.destroy();
+    }
+}
+
+fn clear_blocking() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, mut queue) = registry_queue_init::<State>(&conn)?;
+    let qh = queue.handle();
+    let manager: ZwlrDataControlManagerV1 = globals.bind(&qh, 1..=PRIMARY_VERSION, ())?;
+    let seat: WlSeat = globals.bind(&qh, 1..=1, ())?;
+    let device = manager.get_data_device(&seat, &qh, ());
+    device.set_selection(None);
+    if manager.version() >= PRIMARY_VERSION {
+        device.set_primary_selection(None);
+    }
+    queue.roundtrip(&mut State)?;
+    device.destroy();
+    manager.destroy();
+    conn.flush()?;
+    Ok(())
+}
+
+/// Empties the clipboard and, where supported, the primary selection
+pub async fn clear() -> Result<(), String> {
+    task::spawn_blocking(|| clear_blocking().map_err(|err| err.to_string()))
+        .await
+        .map_err(|err| err.to_string())?
+}
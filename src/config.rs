@@ -0,0 +1,1264 @@
+use chrono::{
+    format::{Item, StrftimeItems},
+    NaiveTime,
+};
+use cosmic::{
+    cosmic_config::{self, ConfigGet, ConfigSet},
+    iced::{
+        futures::{channel::mpsc, SinkExt, StreamExt},
+        subscription, ContentFit, Subscription,
+    },
+};
+use inotify::{Inotify, WatchMask};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::time;
+
+use crate::{
+    gesture::{Corner, Edge},
+    keymap::{self, KeyAction},
+    layout::{Layout, WidgetKind},
+    logind::PowerAction,
+    swaylock,
+};
+
+const FILE_NAME: &str = "zero-lock/config.toml";
+const SYSTEM_PATH: &str = "/etc/zero-lock/config.toml";
+// Where COSMIC Settings keeps zero-lock's options, ~/.config/cosmic/zero-lock/v1
+const COSMIC_CONFIG_ID: &str = "zero-lock";
+const COSMIC_CONFIG_VERSION: u64 = 1;
+// Candle light to daylight, warmer than this is hardly readable
+const NIGHT_LIGHT_MIN: u32 = 1000;
+const NIGHT_LIGHT_MAX: u32 = 6500;
+// Longer timeouts overflow the instants they are added to, and panic the lock screen
+const MAX_SECONDS: u64 = 365 * 24 * 60 * 60;
+// Larger text is off every output anyway, and takes long to lay out
+const MAX_TEXT_SCALE: f32 = 8.0;
+// The blur runs on the CPU, wider ones take minutes
+const MAX_FROSTED_BLUR: f32 = 200.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationPrivacy {
+    Show,
+    HideBody,
+    Hide,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TypingFeedback {
+    // Password field with a dot per character
+    Dots,
+    // Flashing segments that don't reveal the password length
+    Segments,
+    // Nothing, like swaylock's --no-unlock-indicator
+    Hidden,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputContent {
+    // The layout with every widget
+    Full,
+    // Clock, away time and status message only
+    Clock,
+    // Nothing, for displays nobody sits at
+    Blank,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputMode {
+    // Same layout and sizes everywhere, per-output settings are ignored
+    Mirrored,
+    // Per-output settings, orientation and clock size
+    Independent,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockOnLid {
+    Always,
+    // Docked laptops are often closed on purpose
+    OnBattery,
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackgroundScaling {
+    // Covers the output, cropping what doesn't fit
+    Fill,
+    // Whole image, with the background color around it
+    Fit,
+    Stretch,
+    // Original size in the middle
+    Center,
+    // Original size repeated from the top left corner
+    Tile,
+}
+
+impl BackgroundScaling {
+    pub fn content_fit(self) -> ContentFit {
+        match self {
+            Self::Fill => ContentFit::Cover,
+            Self::Fit => ContentFit::Contain,
+            Self::Stretch => ContentFit::Fill,
+            Self::Center | Self::Tile => ContentFit::None,
+        }
+    }
+}
+
+/// A color written as "1e1e2e" or "#1e1e2e", alpha is accepted and ignored
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rgb(pub [f32; 3]);
+
+impl Rgb {
+    pub fn parse(hex: &str) -> Option<Self> {
+        let hex = hex.strip_prefix('#').unwrap_or(hex);
+        if !matches!(hex.len(), 6 | 8) || !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .ok()
+                .map(|value| f32::from(value) / 255.0)
+        };
+        Some(Self([channel(0)?, channel(2)?, channel(4)?]))
+    }
+}
+
+impl<'de> Deserialize<'de> for Rgb {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Self::parse(&hex)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid color {:?}", hex)))
+    }
+}
+
+/// What a colored indicator says, whichever palette draws it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    // Failed attempts, and a microphone or camera left recording
+    Error,
+    // A disconnected VPN or a pending restart
+    Warning,
+    Success,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusPalette {
+    Default,
+    // Red and green look alike, from the Okabe-Ito palette like protanopia and tritanopia
+    Deuteranopia,
+    // Also red and green, with reds looking dark, so errors are a brighter orange
+    Protanopia,
+    // Blue and yellow look alike
+    Tritanopia,
+}
+
+impl StatusPalette {
+    fn color(self, status: Status) -> Rgb {
+        Rgb(match (self, status) {
+            (Self::Default, Status::Error) => [0.93, 0.2, 0.2],
+            (Self::Default, Status::Warning) => [0.96, 0.66, 0.14],
+            (Self::Default, Status::Success) => [0.3, 0.75, 0.35],
+            (Self::Deuteranopia, Status::Error) => [0.84, 0.37, 0.0],
+            (Self::Deuteranopia, Status::Warning) => [0.94, 0.89, 0.26],
+            (Self::Deuteranopia, Status::Success) => [0.0, 0.45, 0.7],
+            (Self::Protanopia, Status::Error) => [0.9, 0.62, 0.0],
+            (Self::Protanopia, Status::Warning) => [0.94, 0.89, 0.26],
+            (Self::Protanopia, Status::Success) => [0.34, 0.71, 0.91],
+            (Self::Tritanopia, Status::Error) => [0.93, 0.2, 0.2],
+            (Self::Tritanopia, Status::Warning) => [0.8, 0.47, 0.65],
+            (Self::Tritanopia, Status::Success) => [0.0, 0.62, 0.45],
+        })
+    }
+}
+
+/// Colors replacing the palette's, each one optional
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatusColors {
+    #[serde(rename = "error")]
+    pub error_opt: Option<Rgb>,
+    #[serde(rename = "warning")]
+    pub warning_opt: Option<Rgb>,
+    #[serde(rename = "success")]
+    pub success_opt: Option<Rgb>,
+}
+
+/// A `[styles.<widget>]` table, a box drawn around the widget over the COSMIC theme
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WidgetStyle {
+    #[serde(rename = "background")]
+    pub background_opt: Option<Rgb>,
+    // Of the background, from 0 to 1
+    pub opacity: f32,
+    // Text without a color of its own, the status colors stay
+    #[serde(rename = "text")]
+    pub text_opt: Option<Rgb>,
+    pub radius: f32,
+    pub border_width: f32,
+    // The theme's accent color when unset
+    #[serde(rename = "border")]
+    pub border_opt: Option<Rgb>,
+    pub padding: u16,
+}
+
+impl Default for WidgetStyle {
+    fn default() -> Self {
+        Self {
+            background_opt: None,
+            opacity: 1.0,
+            text_opt: None,
+            radius: 0.0,
+            border_width: 0.0,
+            border_opt: None,
+            padding: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    pub content: OutputContent,
+    // Replaces the layout on this output
+    #[serde(rename = "layout")]
+    pub layout_opt: Option<Layout>,
+    // Multiplies the text size on this output
+    pub text_scale: f32,
+    // Replaces background_scaling here, for an ultrawide or portrait output
+    #[serde(rename = "background_scaling")]
+    pub background_scaling_opt: Option<BackgroundScaling>,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            content: OutputContent::Full,
+            layout_opt: None,
+            text_scale: 1.0,
+            background_scaling_opt: None,
+        }
+    }
+}
+
+// Where an unread counter gets its count
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnreadSource {
+    // Unseen messages in a maildir
+    Maildir(PathBuf),
+    // A shell command printing the count, and optionally a preview on the next line
+    Command(String),
+    // Notifications from this app received while locked
+    Notifications(String),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UnreadCounter {
+    pub label: String,
+    #[serde(default = "default_unread_icon")]
+    pub icon: String,
+    pub source: UnreadSource,
+    // Show the newest sender and subject, only the count otherwise
+    #[serde(default)]
+    pub preview: bool,
+}
+
+fn default_unread_icon() -> String {
+    "mail-unread-symbolic".to_string()
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    // Show a button to power Bluetooth on and off
+    pub bluetooth_toggle: bool,
+    // Show the current track and playback controls, disable for privacy
+    pub media_controls: bool,
+    // Show notification summaries received while locked
+    pub notification_previews: bool,
+    // Privacy for apps without an entry in notification_privacy
+    pub notification_privacy_default: NotificationPrivacy,
+    // Per-app privacy, keyed by the app name sent with the notification
+    pub notification_privacy: HashMap<String, NotificationPrivacy>,
+    // Counters for the unread widget, mail or messages
+    pub unread: Vec<UnreadCounter>,
+    // Shortcuts on the lock surface replacing the defaults, "Ctrl+U" = "clear_input"
+    pub key_bindings: HashMap<String, KeyAction>,
+    // Power actions left out of the power menu
+    pub hidden_power_actions: Vec<PowerAction>,
+    // Which widgets are shown and where
+    pub layout: Layout,
+    // Plugins to load, they run inside the locker so none are loaded by default
+    pub plugins: Vec<String>,
+    // Open the on-screen keyboard by default, for tablets
+    pub virtual_keyboard: bool,
+    // Hide the prompt until a key press, click or swipe up
+    pub cover_screen: bool,
+    // Seconds without input before the prompt is hidden again
+    pub cover_timeout: u64,
+    // How typing into the password prompt is shown
+    pub typing_feedback: TypingFeedback,
+    // Seconds without pointer movement before the cursor is hidden
+    pub cursor_timeout: u64,
+    // High contrast palette and larger buttons, also ZERO_LOCK_HIGH_CONTRAST=1
+    pub high_contrast: bool,
+    // No animations or transparency, also ZERO_LOCK_REDUCED_MOTION=1
+    pub reduced_motion: bool,
+    // Opaque cards behind the clock, the prompt and the banners, for busy wallpapers, also
+    // ZERO_LOCK_REDUCED_TRANSPARENCY=1
+    pub reduced_transparency: bool,
+    // Frosted glass behind the clock and the prompt for bright wallpapers, the wallpaper blurred
+    // by frosted_blur pixels with frosted_padding around the text
+    pub frosted_card: bool,
+    pub frosted_blur: f32,
+    pub frosted_padding: u16,
+    // A slow pan and zoom over the wallpaper, one cycle every wallpaper_motion_period seconds.
+    // Still with reduced_motion, on idle and on the power-saver profile
+    pub wallpaper_motion: bool,
+    pub wallpaper_motion_period: u64,
+    // Dim the lock screen in a dark room and back text with opaque cards in sunlight, from
+    // iio-sensor-proxy's ambient light sensor
+    pub ambient_light: bool,
+    // Colors of errors, warnings and success, "deuteranopia", "protanopia" or "tritanopia" for
+    // color blindness, with single colors replaced by [status_colors]
+    pub status_palette: StatusPalette,
+    pub status_colors: StatusColors,
+    // Boxes around widgets by name, like [styles.clock]
+    pub styles: HashMap<WidgetKind, WidgetStyle>,
+    // CPU rendering for broken GPU drivers, also ZERO_LOCK_SOFTWARE_RENDERING=1
+    pub software_rendering: bool,
+    // Surface, focus, timing and auth details on every output, also ZERO_LOCK_DEBUG_OVERLAY=1
+    pub debug_overlay: bool,
+    // Per-output settings, keyed by output name like "DP-1"
+    pub outputs: HashMap<String, OutputConfig>,
+    // Named sets of keys that replace the ones above when selected, [profiles.work]
+    pub profiles: HashMap<String, toml::Table>,
+    // Keys from /etc that neither the user's file, a profile nor cosmic-config can change
+    pub enforced: toml::Table,
+    // Picked from the number of outputs when unset
+    #[serde(rename = "output_mode")]
+    pub output_mode_opt: Option<OutputMode>,
+    // Multiplies the text size on every output
+    pub text_scale: f32,
+    // Larger text on top of text_scale, toggled on the lock screen with Ctrl++
+    pub large_text: bool,
+    // Seconds without input before polling and animations pause
+    pub idle_timeout: u64,
+    // Seconds without input before the displays are turned off, None keeps them on
+    #[serde(rename = "display_off_timeout", deserialize_with = "seconds_opt")]
+    pub display_off_timeout_opt: Option<u64>,
+    // Only a double tap on a touchscreen wakes dimmed or turned off displays, like on phones
+    pub double_tap_wake: bool,
+    // "top_left", "top_right", "bottom_left" or "bottom_right", resting the pointer there opens
+    // the power menu
+    #[serde(rename = "hot_corner")]
+    pub hot_corner_opt: Option<Corner>,
+    // "top", "bottom", "left" or "right", swiping in from there opens the power menu
+    #[serde(rename = "edge_swipe")]
+    pub edge_swipe_opt: Option<Edge>,
+    // Seconds locked without input before suspending, None never suspends
+    #[serde(rename = "suspend_timeout", deserialize_with = "seconds_opt")]
+    pub suspend_timeout_opt: Option<u64>,
+    // Also suspend on AC power, otherwise only on battery
+    pub suspend_on_ac: bool,
+    // "suspend", "hibernate" or "shut_down" while locked once a UPS on battery has less than
+    // ups_critical_minutes left
+    #[serde(rename = "ups_critical_action")]
+    pub ups_critical_action_opt: Option<PowerAction>,
+    pub ups_critical_minutes: u64,
+    // Shown above the prompt so a fake lock screen can be told apart, keep it to yourself
+    #[serde(rename = "secret_phrase")]
+    pub secret_phrase_opt: Option<String>,
+    // Image shown with the secret phrase
+    #[serde(rename = "secret_image")]
+    pub secret_image_opt: Option<PathBuf>,
+    // Drawn behind the lock screen, scaled to cover each output, or "dir:PATH" for a random
+    // image from a directory on every lock
+    #[serde(rename = "background")]
+    pub background_opt: Option<PathBuf>,
+    // File names in a background dir that are never picked
+    pub background_exclude: Vec<String>,
+    // How many of the last picks from a background dir aren't picked again
+    pub background_history: usize,
+    // How the background is fitted to each output
+    pub background_scaling: BackgroundScaling,
+    // Around a background that doesn't cover the output, or instead of one
+    #[serde(rename = "background_color")]
+    pub background_color_opt: Option<Rgb>,
+    // Without a background, draw the COSMIC desktop wallpaper of each output
+    pub desktop_wallpaper: bool,
+    // Seconds after locking in which a key, click or touch unlocks without a password, 0 never
+    pub grace_period: u64,
+    // Daemon mode locks after this many seconds without input
+    #[serde(rename = "lock_timeout", deserialize_with = "seconds_opt")]
+    pub lock_timeout_opt: Option<u64>,
+    // Lock when the lid closes, before any suspend logind does for it
+    pub lock_on_lid: LockOnLid,
+    // Also answer to org.gnome.ScreenSaver, which browsers and Electron apps check for the lock
+    pub gnome_screensaver: bool,
+    // Command greetd starts after logging in from greeter mode, if there are no session files
+    pub greeter_session: Vec<String>,
+    // strftime format of the clock
+    pub clock_format: String,
+    // Show the next GNOME Clocks alarm under the clock
+    pub next_alarm: bool,
+    // PAM service used to authenticate, one that prompts for a fingerprint works too
+    pub pam_service: String,
+    // Also accept an enrolled fingerprint from fprintd while the password prompt is up
+    pub fingerprint: bool,
+    // Others who may unlock with their own password, the lock screen asks for a username first
+    pub unlock_users: Vec<String>,
+    // Members of these groups may unlock too, e.g. "wheel" for admins
+    pub unlock_groups: Vec<String>,
+    // Pause every playing media player when locking
+    pub pause_media: bool,
+    // Resume the paused players after unlocking
+    pub resume_media: bool,
+    // Mute every microphone while locked, and unmute them after unlocking
+    pub mute_microphone: bool,
+    // Empty the clipboard and primary selection this many seconds after locking
+    pub clear_clipboard: bool,
+    pub clear_clipboard_delay: u64,
+    // Keep the counts of `zero-lock stats` across logins
+    pub persist_stats: bool,
+    // Turn the keyboard backlight off with the displays
+    pub keyboard_backlight_off: bool,
+    // Show pending updates from PackageKit, which is started for it if not running
+    pub pending_updates: bool,
+    // Warm the lock screen to this color temperature in Kelvin from night_light_start to
+    // night_light_end, "20:00" and "07:00", 0 for off
+    pub night_light: u32,
+    pub night_light_start: String,
+    pub night_light_end: String,
+    // Shell commands run after locking, after unlocking and after a failed attempt
+    #[serde(rename = "on_lock")]
+    pub on_lock_opt: Option<String>,
+    #[serde(rename = "on_unlock")]
+    pub on_unlock_opt: Option<String>,
+    #[serde(rename = "on_fail")]
+    pub on_fail_opt: Option<String>,
+}
+
+impl Config {
+    /// Reads /etc/zero-lock/config.toml with `$XDG_CONFIG_HOME/zero-lock/config.toml` over it, and
+    /// the cosmic-config settings that COSMIC Settings writes over both
+    ///
+    /// A broken config must not keep the session from locking, so the defaults are used instead
+    /// and the errors are returned to be shown on the lock screen.
+    pub fn load(path_opt: Option<&Path>, profile_opt: Option<&str>) -> (Self, Option<String>) {
+        let (mut config, mut errors) = Self::load_file(path_opt, profile_opt);
+        match cosmic_config::Config::new(COSMIC_CONFIG_ID, COSMIC_CONFIG_VERSION) {
+            Ok(cosmic_config) => config.apply_cosmic_config(&cosmic_config, &mut errors),
+            Err(err) => log::info!("failed to open cosmic-config settings: {:?}", err),
+        }
+        config.validate(&mut errors);
+        let error_opt =
+            (!errors.is_empty()).then(|| format!("config error: {}", errors.join(", ")));
+        (config, error_opt)
+    }
+
+    // The file in /etc with the user's on top, then the profile and the enforced keys. Without
+    // either file, swaylock's config is used
+    fn load_file(path_opt: Option<&Path>, profile_opt: Option<&str>) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let mut table = read_table(Path::new(SYSTEM_PATH), &mut errors);
+        let enforced = match table.as_mut().and_then(|table| table.remove("enforced")) {
+            Some(toml::Value::Table(enforced)) => enforced,
+            _ => toml::Table::new(),
+        };
+        let user_path_opt = path_opt.map(Path::to_path_buf).or_else(user_path);
+        if let Some(mut user_table) = user_path_opt
+            .as_deref()
+            .and_then(|path| read_table(path, &mut errors))
+        {
+            if user_table.remove("enforced").is_some() {
+                errors.push(format!("enforced is only read from {}", SYSTEM_PATH));
+            }
+            match &mut table {
+                Some(table) => merge_table(table, user_table),
+                None => table = Some(user_table),
+            }
+        }
+        let Some(mut table) = table else {
+            if path_opt.is_none() && errors.is_empty() {
+                if let Some(config) = swaylock::load() {
+                    return (config, errors);
+                }
+            }
+            return (Self::default(), errors);
+        };
+
+        if let Some(name) = profile_opt {
+            let profile_opt = table
+                .get("profiles")
+                .and_then(|profiles| profiles.get(name))
+                .and_then(toml::Value::as_table)
+                .cloned();
+            match profile_opt {
+                Some(profile) => {
+                    log::info!("using profile {}", name);
+                    merge_table(&mut table, profile);
+                }
+                None => {
+                    log::warn!("no profile {:?} in the config", name);
+                    errors.push(format!("unknown profile {:?}", name));
+                }
+            }
+        }
+        if !enforced.is_empty() {
+            let keys: Vec<&String> = enforced.keys().collect();
+            log::info!("enforced by {}: {:?}", SYSTEM_PATH, keys);
+        }
+        merge_table(&mut table, enforced.clone());
+        match toml::Value::Table(table).try_into::<Self>() {
+            Ok(mut config) => {
+                config.enforced = enforced;
+                (config, errors)
+            }
+            // Each file parsed on its own, so this is a profile or enforced key
+            Err(err) => {
+                log::error!("failed to apply the config, using defaults: {}", err);
+                errors.push(err.message().to_string());
+                (Self::default(), errors)
+            }
+        }
+    }
+
+    // Keys are the field names, with the values in RON like every cosmic-config entry
+    fn apply_cosmic_config(&mut self, config: &cosmic_config::Config, errors: &mut Vec<String>) {
+        let mut get = CosmicConfigGet {
+            config,
+            errors,
+            enforced: &self.enforced,
+        };
+        get.key("bluetooth_toggle", &mut self.bluetooth_toggle);
+        get.key("media_controls", &mut self.media_controls);
+        get.key("notification_previews", &mut self.notification_previews);
+        get.key(
+            "notification_privacy_default",
+            &mut self.notification_privacy_default,
+        );
+        get.key("notification_privacy", &mut self.notification_privacy);
+        get.key("unread", &mut self.unread);
+        get.key("key_bindings", &mut self.key_bindings);
+        get.key("large_text", &mut self.large_text);
+        get.key("hidden_power_actions", &mut self.hidden_power_actions);
+        get.key("layout", &mut self.layout);
+        get.key("plugins", &mut self.plugins);
+        get.key("virtual_keyboard", &mut self.virtual_keyboard);
+        get.key("cover_screen", &mut self.cover_screen);
+        get.key("cover_timeout", &mut self.cover_timeout);
+        get.key("typing_feedback", &mut self.typing_feedback);
+        get.key("cursor_timeout", &mut self.cursor_timeout);
+        get.key("high_contrast", &mut self.high_contrast);
+        get.key("reduced_motion", &mut self.reduced_motion);
+        get.key("reduced_transparency", &mut self.reduced_transparency);
+        get.key("frosted_card", &mut self.frosted_card);
+        get.key("frosted_blur", &mut self.frosted_blur);
+        get.key("frosted_padding", &mut self.frosted_padding);
+        get.key("wallpaper_motion", &mut self.wallpaper_motion);
+        get.key("wallpaper_motion_period", &mut self.wallpaper_motion_period);
+        get.key("ambient_light", &mut self.ambient_light);
+        get.key("status_palette", &mut self.status_palette);
+        get.key("status_colors", &mut self.status_colors);
+        get.key("styles", &mut self.styles);
+        get.key("software_rendering", &mut self.software_rendering);
+        get.key("debug_overlay", &mut self.debug_overlay);
+        get.key("outputs", &mut self.outputs);
+        get.key("output_mode", &mut self.output_mode_opt);
+        get.key("text_scale", &mut self.text_scale);
+        get.key("idle_timeout", &mut self.idle_timeout);
+        get.key("display_off_timeout", &mut self.display_off_timeout_opt);
+        get.key("double_tap_wake", &mut self.double_tap_wake);
+        get.key("hot_corner", &mut self.hot_corner_opt);
+        get.key("edge_swipe", &mut self.edge_swipe_opt);
+        get.key("suspend_timeout", &mut self.suspend_timeout_opt);
+        get.key("suspend_on_ac", &mut self.suspend_on_ac);
+        get.key("ups_critical_action", &mut self.ups_critical_action_opt);
+        get.key("ups_critical_minutes", &mut self.ups_critical_minutes);
+        get.key("secret_phrase", &mut self.secret_phrase_opt);
+        get.key("secret_image", &mut self.secret_image_opt);
+        get.key("background", &mut self.background_opt);
+        get.key("background_scaling", &mut self.background_scaling);
+        get.key("background_color", &mut self.background_color_opt);
+        get.key("background_exclude", &mut self.background_exclude);
+        get.key("background_history", &mut self.background_history);
+        get.key("desktop_wallpaper", &mut self.desktop_wallpaper);
+        get.key("grace_period", &mut self.grace_period);
+        get.key("lock_timeout", &mut self.lock_timeout_opt);
+        get.key("lock_on_lid", &mut self.lock_on_lid);
+        get.key("gnome_screensaver", &mut self.gnome_screensaver);
+        get.key("greeter_session", &mut self.greeter_session);
+        get.key("clock_format", &mut self.clock_format);
+        get.key("next_alarm", &mut self.next_alarm);
+        get.key("pam_service", &mut self.pam_service);
+        get.key("fingerprint", &mut self.fingerprint);
+        get.key("unlock_users", &mut self.unlock_users);
+        get.key("unlock_groups", &mut self.unlock_groups);
+        get.key("pause_media", &mut self.pause_media);
+        get.key("resume_media", &mut self.resume_media);
+        get.key("mute_microphone", &mut self.mute_microphone);
+        get.key("clear_clipboard", &mut self.clear_clipboard);
+        get.key("clear_clipboard_delay", &mut self.clear_clipboard_delay);
+        get.key("persist_stats", &mut self.persist_stats);
+        get.key("keyboard_backlight_off", &mut self.keyboard_backlight_off);
+        get.key("pending_updates", &mut self.pending_updates);
+        get.key("night_light", &mut self.night_light);
+        get.key("night_light_start", &mut self.night_light_start);
+        get.key("night_light_end", &mut self.night_light_end);
+        get.key("on_lock", &mut self.on_lock_opt);
+        get.key("on_unlock", &mut self.on_unlock_opt);
+        get.key("on_fail", &mut self.on_fail_opt);
+    }
+
+    // Values the types allow but the locker can't use are reset to their defaults
+    fn validate(&mut self, errors: &mut Vec<String>) {
+        let default = Self::default();
+        let start = errors.len();
+        // chrono panics while formatting with an invalid specifier
+        if StrftimeItems::new(&self.clock_format).any(|item| item == Item::Error) {
+            errors.push(format!("invalid clock_format {:?}", self.clock_format));
+            self.clock_format = default.clock_format;
+        }
+        if !(self.text_scale > 0.0 && self.text_scale <= MAX_TEXT_SCALE) {
+            errors.push(format!("invalid text_scale {}", self.text_scale));
+            self.text_scale = default.text_scale;
+        }
+        if !(0.0..=MAX_FROSTED_BLUR).contains(&self.frosted_blur) {
+            errors.push(format!("invalid frosted_blur {}", self.frosted_blur));
+            self.frosted_blur = default.frosted_blur;
+        }
+        if self.wallpaper_motion_period == 0 {
+            errors.push(format!(
+                "invalid wallpaper_motion_period {}",
+                self.wallpaper_motion_period
+            ));
+            self.wallpaper_motion_period = default.wallpaper_motion_period;
+        }
+        for (name, seconds, default) in [
+            (
+                "cover_timeout",
+                &mut self.cover_timeout,
+                default.cover_timeout,
+            ),
+            (
+                "cursor_timeout",
+                &mut self.cursor_timeout,
+                default.cursor_timeout,
+            ),
+            (
+                "wallpaper_motion_period",
+                &mut self.wallpaper_motion_period,
+                default.wallpaper_motion_period,
+            ),
+            ("idle_timeout", &mut self.idle_timeout, default.idle_timeout),
+            ("grace_period", &mut self.grace_period, default.grace_period),
+            (
+                "clear_clipboard_delay",
+                &mut self.clear_clipboard_delay,
+                default.clear_clipboard_delay,
+            ),
+        ] {
+            if *seconds > MAX_SECONDS {
+                errors.push(format!("invalid {} {}", name, seconds));
+                *seconds = default;
+            }
+        }
+        // A restart comes back up on the same battery
+        if self.ups_critical_action_opt == Some(PowerAction::Restart) {
+            errors.push("invalid ups_critical_action \"restart\"".to_string());
+            self.ups_critical_action_opt = default.ups_critical_action_opt;
+        }
+        for (name, seconds_opt, default_opt) in [
+            (
+                "display_off_timeout",
+                &mut self.display_off_timeout_opt,
+                default.display_off_timeout_opt,
+            ),
+            (
+                "suspend_timeout",
+                &mut self.suspend_timeout_opt,
+                default.suspend_timeout_opt,
+            ),
+            (
+                "lock_timeout",
+                &mut self.lock_timeout_opt,
+                default.lock_timeout_opt,
+            ),
+        ] {
+            if let Some(seconds) = seconds_opt.filter(|&seconds| seconds > MAX_SECONDS) {
+                errors.push(format!("invalid {} {}", name, seconds));
+                *seconds_opt = default_opt;
+            }
+        }
+        if self.night_light != 0 && !(NIGHT_LIGHT_MIN..=NIGHT_LIGHT_MAX).contains(&self.night_light)
+        {
+            errors.push(format!("invalid night_light {}", self.night_light));
+            self.night_light = default.night_light;
+        }
+        if parse_time(&self.night_light_start).is_none() {
+            errors.push(format!(
+                "invalid night_light_start {:?}",
+                self.night_light_start
+            ));
+            self.night_light_start = default.night_light_start;
+        }
+        if parse_time(&self.night_light_end).is_none() {
+            errors.push(format!(
+                "invalid night_light_end {:?}",
+                self.night_light_end
+            ));
+            self.night_light_end = default.night_light_end;
+        }
+        self.key_bindings.retain(|binding, _| {
+            let valid = keymap::is_valid(binding);
+            if !valid {
+                errors.push(format!("invalid key binding {:?}", binding));
+            }
+            valid
+        });
+        for (name, output) in self.outputs.iter_mut() {
+            if !(output.text_scale > 0.0 && output.text_scale <= MAX_TEXT_SCALE) {
+                errors.push(format!(
+                    "invalid text_scale {} for {}",
+                    output.text_scale, name
+                ));
+                output.text_scale = 1.0;
+            }
+        }
+        for (kind, style) in self.styles.iter_mut() {
+            if !(0.0..=1.0).contains(&style.opacity) {
+                errors.push(format!("invalid opacity {} for {:?}", style.opacity, kind));
+                style.opacity = 1.0;
+            }
+            if !(style.radius >= 0.0 && style.radius.is_finite()) {
+                errors.push(format!("invalid radius {} for {:?}", style.radius, kind));
+                style.radius = 0.0;
+            }
+            if !(style.border_width >= 0.0 && style.border_width.is_finite()) {
+                errors.push(format!(
+                    "invalid border_width {} for {:?}",
+                    style.border_width, kind
+                ));
+                style.border_width = 0.0;
+            }
+        }
+        for err in errors[start..].iter() {
+            log::warn!("{}, using the default", err);
+        }
+    }
+
+    /// The night light temperature at this time of day, None when off or outside its hours
+    pub fn night_light_at(&self, time: NaiveTime) -> Option<u32> {
+        if self.night_light == 0 {
+            return None;
+        }
+        let start = parse_time(&self.night_light_start)?;
+        let end = parse_time(&self.night_light_end)?;
+        // Usually over midnight, from the evening to the morning
+        let active = if start <= end {
+            start <= time && time < end
+        } else {
+            start <= time || time < end
+        };
+        active.then_some(self.night_light)
+    }
+
+    pub fn notification_privacy(&self, app_name: &str) -> NotificationPrivacy {
+        self.notification_privacy
+            .get(app_name)
+            .copied()
+            .unwrap_or(self.notification_privacy_default)
+    }
+
+    /// The palette's color for `status`, unless `status_colors` replaces it
+    pub fn status_color(&self, status: Status) -> Rgb {
+        let color_opt = match status {
+            Status::Error => self.status_colors.error_opt,
+            Status::Warning => self.status_colors.warning_opt,
+            Status::Success => self.status_colors.success_opt,
+        };
+        color_opt.unwrap_or_else(|| self.status_palette.color(status))
+    }
+
+    /// Overrides from the environment, so they work before any config is written
+    pub fn apply_env(&mut self) {
+        if let Some(high_contrast) = env_flag("ZERO_LOCK_HIGH_CONTRAST") {
+            self.high_contrast = high_contrast;
+        }
+        if let Some(reduced_motion) = env_flag("ZERO_LOCK_REDUCED_MOTION") {
+            self.reduced_motion = reduced_motion;
+        }
+        if let Some(reduced_transparency) = env_flag("ZERO_LOCK_REDUCED_TRANSPARENCY") {
+            self.reduced_transparency = reduced_transparency;
+        }
+        if let Some(software_rendering) = env_flag("ZERO_LOCK_SOFTWARE_RENDERING") {
+            self.software_rendering = software_rendering;
+        }
+        if let Some(debug_overlay) = env_flag("ZERO_LOCK_DEBUG_OVERLAY") {
+            self.debug_overlay = debug_overlay;
+        }
+    }
+}
+
+struct CosmicConfigGet<'a> {
+    config: &'a cosmic_config::Config,
+    errors: &'a mut Vec<String>,
+    enforced: &'a toml::Table,
+}
+
+impl CosmicConfigGet<'_> {
+    // Keys that were never set keep the value from the file
+    fn key<T: DeserializeOwned>(&mut self, key: &str, value: &mut T) {
+        if self.enforced.contains_key(key) {
+            return;
+        }
+        match self.config.get::<T>(key) {
+            Ok(setting) => *value = setting,
+            Err(cosmic_config::Error::GetKey(_, err)) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => {
+                log::warn!("failed to read cosmic-config {}: {:?}", key, err);
+                self.errors
+                    .push(format!("invalid {} in cosmic-config", key));
+            }
+        }
+    }
+}
+
+// "unknown field `wallpapper` line 12", without the list of every valid key
+fn parse_error(data: &str, err: &toml::de::Error) -> String {
+    let message = err.message();
+    let message = message.split(", expected").next().unwrap_or(message);
+    match err.span() {
+        Some(span) => {
+            // A span off a character boundary would panic the slice
+            let line_opt = data
+                .get(..span.start)
+                .map(|before| before.matches('\n').count() + 1);
+            match line_opt {
+                Some(line) => format!("{} line {}", message, line),
+                None => message.to_string(),
+            }
+        }
+        None => message.to_string(),
+    }
+}
+
+// One config file as a table to merge, None if missing or invalid
+fn read_table(path: &Path, errors: &mut Vec<String>) -> Option<toml::Table> {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+        Err(err) => {
+            log::warn!("failed to read {}: {}", path.display(), err);
+            errors.push(err.to_string());
+            return None;
+        }
+    };
+    // Parsed as a whole config first for the unknown keys and their lines
+    match toml::from_str::<Config>(&data).and_then(|_| data.parse::<toml::Table>()) {
+        Ok(table) => {
+            log::info!("loaded {}", path.display());
+            Some(table)
+        }
+        Err(err) => {
+            log::error!("failed to parse {}, ignoring it: {}", path.display(), err);
+            errors.push(parse_error(&data, &err));
+            None
+        }
+    }
+}
+
+fn merge_table(table: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match value {
+            toml::Value::Table(overrides)
+                if table.get(&key).map_or(false, toml::Value::is_table) =>
+            {
+                if let Some(toml::Value::Table(table)) = table.get_mut(&key) {
+                    merge_table(table, overrides);
+                }
+            }
+            value => {
+                table.insert(key, value);
+            }
+        }
+    }
+}
+
+fn user_path() -> Option<PathBuf> {
+    match env::var_os("XDG_CONFIG_HOME") {
+        Some(config_home) => Some(PathBuf::from(config_home).join(FILE_NAME)),
+        None => Some(
+            PathBuf::from(env::var_os("HOME")?)
+                .join(".config")
+                .join(FILE_NAME),
+        ),
+    }
+}
+
+fn config_paths(path_opt: Option<&Path>) -> Vec<PathBuf> {
+    let mut paths = Vec::with_capacity(2);
+    if let Some(path) = path_opt.map(Path::to_path_buf).or_else(user_path) {
+        paths.push(path);
+    }
+    paths.push(PathBuf::from(SYSTEM_PATH));
+    paths
+}
+
+/// Saves the large text toggle in cosmic-config, where it outlasts this lock over the files
+pub fn set_large_text(large_text: bool) {
+    match cosmic_config::Config::new(COSMIC_CONFIG_ID, COSMIC_CONFIG_VERSION)
+        .and_then(|config| config.set("large_text", large_text))
+    {
+        Ok(()) => log::info!("large text {}", if large_text { "on" } else { "off" }),
+        Err(err) => log::warn!("failed to save large_text: {:?}", err),
+    }
+}
+
+/// Sends an event whenever a config file that [`Config::load`] looks at is written or removed,
+/// or a cosmic-config setting changes
+pub fn subscription(path_opt: Option<PathBuf>) -> Subscription<()> {
+    struct ConfigSubscription;
+
+    subscription::channel(
+        TypeId::of::<ConfigSubscription>(),
+        16,
+        move |msg_tx| async move {
+            let mut change_tx = msg_tx.clone();
+            let _watcher_opt =
+                match cosmic_config::Config::new(COSMIC_CONFIG_ID, COSMIC_CONFIG_VERSION).and_then(
+                    |config| {
+                        config.watch(move |_config, _keys| {
+                            // A full channel already has a reload pending
+                            let _ = change_tx.try_send(());
+                        })
+                    },
+                ) {
+                    Ok(watcher) => Some(watcher),
+                    Err(err) => {
+                        log::info!("failed to watch cosmic-config settings: {:?}", err);
+                        None
+                    }
+                };
+
+            if let Err(err) = watch(path_opt.as_deref(), msg_tx).await {
+                log::warn!("not watching the config file: {}", err);
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+// Editors replace the file instead of writing it, so the directories are watched
+async fn watch(path_opt: Option<&Path>, mut msg_tx: mpsc::Sender<()>) -> io::Result<()> {
+    let inotify = Inotify::init()?;
+    let mut file_names = HashMap::new();
+    for path in config_paths(path_opt) {
+        let (Some(dir), Some(file_name)) = (path.parent(), path.file_name()) else {
+            continue;
+        };
+        let mask = WatchMask::CLOSE_WRITE | WatchMask::MOVED_TO | WatchMask::DELETE;
+        match inotify.watches().add(dir, mask) {
+            Ok(wd) => {
+                file_names.insert(wd, file_name.to_os_string());
+            }
+            // A directory created later is only read by the next locker
+            Err(err) => log::info!("not watching {}: {}", dir.display(), err),
+        }
+    }
+    if file_names.is_empty() {
+        return Ok(());
+    }
+
+    let mut events = inotify.into_event_stream([0; 1024])?;
+    while let Some(event) = events.next().await {
+        let event = event?;
+        if event.name.is_some() && event.name.as_ref() == file_names.get(&event.wd) {
+            let _ = msg_tx.send(()).await;
+        }
+    }
+    Ok(())
+}
+
+// TOML has no null, so 0 turns a timeout off
+fn seconds_opt<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    let seconds = u64::deserialize(deserializer)?;
+    Ok(Some(seconds).filter(|&seconds| seconds > 0))
+}
+
+fn parse_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time, "%H:%M").ok()
+}
+
+fn env_flag(name: &str) -> Option<bool> {
+    match env::var(name).ok()?.as_str() {
+        "1" | "true" | "yes" => Some(true),
+        "0" | "false" | "no" => Some(false),
+        value => {
+            log::warn!("ignoring {}={:?}, expected 1 or 0", name, value);
+            None
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            bluetooth_toggle: true,
+            media_controls: true,
+            notification_previews: false,
+            notification_privacy_default: NotificationPrivacy::HideBody,
+            notification_privacy: HashMap::new(),
+            unread: Vec::new(),
+            key_bindings: HashMap::new(),
+            hidden_power_actions: Vec::new(),
+            layout: Layout::default(),
+            plugins: Vec::new(),
+            virtual_keyboard: false,
+            cover_screen: true,
+            cover_timeout: 30,
+            typing_feedback: TypingFeedback::Dots,
+            cursor_timeout: 3,
+            high_contrast: false,
+            reduced_motion: false,
+            reduced_transparency: false,
+            frosted_card: false,
+            frosted_blur: 24.0,
+            frosted_padding: 24,
+            wallpaper_motion: false,
+            wallpaper_motion_period: 90,
+            ambient_light: false,
+            status_palette: StatusPalette::Default,
+            status_colors: StatusColors::default(),
+            styles: HashMap::new(),
+            software_rendering: false,
+            debug_overlay: false,
+            outputs: HashMap::new(),
+            profiles: HashMap::new(),
+            enforced: toml::Table::new(),
+            output_mode_opt: None,
+            text_scale: 1.0,
+            large_text: false,
+            idle_timeout: 60,
+            display_off_timeout_opt: Some(300),
+            double_tap_wake: true,
+            hot_corner_opt: None,
+            edge_swipe_opt: None,
+            suspend_timeout_opt: None,
+            suspend_on_ac: false,
+            ups_critical_action_opt: None,
+            ups_critical_minutes: 5,
+            secret_phrase_opt: None,
+            secret_image_opt: None,
+            background_opt: None,
+            background_scaling: BackgroundScaling::Fill,
+            background_color_opt: None,
+            background_exclude: Vec::new(),
+            background_history: 5,
+            desktop_wallpaper: true,
+            grace_period: 0,
+            lock_timeout_opt: Some(300),
+            lock_on_lid: LockOnLid::Always,
+            gnome_screensaver: true,
+            greeter_session: vec!["cosmic-session".to_string()],
+            clock_format: "%b %e %-I:%M %p".to_string(),
+            next_alarm: true,
+            pam_service: "zero-lock".to_string(),
+            fingerprint: false,
+            unlock_users: Vec::new(),
+            unlock_groups: Vec::new(),
+            pause_media: false,
+            resume_media: false,
+            mute_microphone: false,
+            clear_clipboard: false,
+            clear_clipboard_delay: 0,
+            persist_stats: false,
+            keyboard_backlight_off: true,
+            pending_updates: false,
+            night_light: 0,
+            night_light_start: "20:00".to_string(),
+            night_light_end: "07:00".to_string(),
+            on_lock_opt: None,
+            on_unlock_opt: None,
+            on_fail_opt: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // Keys the config knows, one it doesn't, and ones whose spans end inside multi-byte text
+    fn toml_key() -> impl Strategy<Value = String> {
+        prop_oneof![
+            Just("clock_format".to_string()),
+            Just("text_scale".to_string()),
+            Just("frosted_blur".to_string()),
+            Just("lock_timeout".to_string()),
+            Just("cover_timeout".to_string()),
+            Just("night_light".to_string()),
+            Just("night_light_start".to_string()),
+            Just("wallpapper".to_string()),
+            Just("\"日本語\"".to_string()),
+            "[a-z_]{1,12}",
+        ]
+    }
+
+    fn toml_value() -> impl Strategy<Value = String> {
+        prop_oneof![
+            any::<i64>().prop_map(|value| value.to_string()),
+            any::<f64>().prop_map(|value| format!("{:?}", value)),
+            Just("nan".to_string()),
+            Just("-inf".to_string()),
+            any::<bool>().prop_map(|value| value.to_string()),
+            any::<String>().prop_map(|value| format!("{:?}", value)),
+            "\"[^\"\\\\]{0,8}\"",
+            Just("[1, \"ü\", 2.5]".to_string()),
+            Just("{ column = [\"clock\", \"äöü\"] }".to_string()),
+            Just("\"%Q 🔒\"".to_string()),
+        ]
+    }
+
+    fn toml_document() -> impl Strategy<Value = String> {
+        let line = prop_oneof![
+            (toml_key(), toml_value()).prop_map(|(key, value)| format!("{} = {}", key, value)),
+            toml_key().prop_map(|key| format!("[{}]", key)),
+            any::<String>(),
+        ];
+        prop::collection::vec(line, 0..12).prop_map(|lines| lines.join("\n"))
+    }
+
+    proptest! {
+        #[test]
+        fn parse_error_never_panics(data in prop_oneof![toml_document(), any::<String>()]) {
+            if let Err(err) = toml::from_str::<Config>(&data) {
+                parse_error(&data, &err);
+            }
+            if let Err(err) = data.parse::<toml::Table>() {
+                parse_error(&data, &err);
+            }
+        }
+
+        #[test]
+        fn read_table_never_panics(data in toml_document()) {
+            let path = env::temp_dir().join(format!(
+                "zero-lock-test-{}-{:?}.toml",
+                std::process::id(),
+                std::thread::current().id()
+            ));
+            fs::write(&path, &data).unwrap();
+            let mut errors = Vec::new();
+            let table_opt = read_table(&path, &mut errors);
+            fs::remove_file(&path).unwrap();
+            prop_assert_eq!(table_opt.is_none(), !errors.is_empty());
+        }
+
+        #[test]
+        fn validate_ends_in_bounds(
+            clock_format in any::<String>(),
+            text_scale in any::<f32>(),
+            output_text_scale in any::<f32>(),
+            frosted_blur in any::<f32>(),
+            seconds in any::<u64>(),
+            seconds_opt in any::<Option<u64>>(),
+            wallpaper_motion_period in any::<u64>(),
+            night_light in any::<u32>(),
+            night_light_start in any::<String>(),
+            (opacity, radius, border_width) in (any::<f32>(), any::<f32>(), any::<f32>()),
+        ) {
+            let mut config = Config {
+                clock_format,
+                text_scale,
+                frosted_blur,
+                cover_timeout: seconds,
+                cursor_timeout: seconds,
+                idle_timeout: seconds,
+                grace_period: seconds,
+                clear_clipboard_delay: seconds,
+                wallpaper_motion_period,
+                display_off_timeout_opt: seconds_opt,
+                suspend_timeout_opt: seconds_opt,
+                lock_timeout_opt: seconds_opt,
+                night_light,
+                night_light_start,
+                ..Config::default()
+            };
+            config.outputs.insert(
+                "HDMI-A-1".to_string(),
+                OutputConfig {
+                    text_scale: output_text_scale,
+                    ..OutputConfig::default()
+                },
+            );
+            config.styles.insert(
+                WidgetKind::Clock,
+                WidgetStyle {
+                    opacity,
+                    radius,
+                    border_width,
+                    ..WidgetStyle::default()
+                },
+            );
+            config.validate(&mut Vec::new());
+
+            prop_assert!(!StrftimeItems::new(&config.clock_format).any(|item| item == Item::Error));
+            prop_assert!(config.text_scale > 0.0 && config.text_scale <= MAX_TEXT_SCALE);
+            prop_assert!((0.0..=MAX_FROSTED_BLUR).contains(&config.frosted_blur));
+            for seconds in [
+                config.cover_timeout,
+                config.cursor_timeout,
+                config.idle_timeout,
+                config.grace_period,
+                config.clear_clipboard_delay,
+                config.wallpaper_motion_period,
+            ] {
+                prop_assert!(seconds <= MAX_SECONDS);
+            }
+            prop_assert!(config.wallpaper_motion_period > 0);
+            for seconds_opt in [
+                config.display_off_timeout_opt,
+                config.suspend_timeout_opt,
+                config.lock_timeout_opt,
+            ] {
+                prop_assert!(seconds_opt.map_or(true, |seconds| seconds <= MAX_SECONDS));
+            }
+            prop_assert!(
+                config.night_light == 0
+                    || (NIGHT_LIGHT_MIN..=NIGHT_LIGHT_MAX).contains(&config.night_light)
+            );
+            prop_assert!(parse_time(&config.night_light_start).is_some());
+            let output_text_scale = config.outputs["HDMI-A-1"].text_scale;
+            prop_assert!(output_text_scale > 0.0 && output_text_scale <= MAX_TEXT_SCALE);
+            let style = config.styles[&WidgetKind::Clock];
+            prop_assert!((0.0..=1.0).contains(&style.opacity));
+            prop_assert!(style.radius >= 0.0 && style.radius.is_finite());
+            prop_assert!(style.border_width >= 0.0 && style.border_width.is_finite());
+
+            // Whatever validate leaves is valid
+            let mut errors = Vec::new();
+            config.validate(&mut errors);
+            prop_assert!(errors.is_empty(), "{:?}", errors);
+        }
+    }
+}
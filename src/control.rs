@@ -0,0 +1,161 @@
+//! Control socket of the running locker, for `zero-lock lock`, `unlock`, `status` and `stats`
+//!
+//! One command per connection, a line in and the reply until the socket closes. Only the user
+//! the locker runs as may connect, and only root may unlock.
+
+use cosmic::iced::{
+    futures::{channel::mpsc as futures_mpsc, SinkExt},
+    subscription, Subscription,
+};
+use std::{
+    any::TypeId,
+    env, fs,
+    io::{self, Read, Write},
+    os::unix::{fs::MetadataExt, net::UnixStream as StdUnixStream},
+    path::PathBuf,
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{UnixListener, UnixStream},
+    sync::mpsc,
+    time,
+};
+
+use crate::systemd;
+
+const SOCKET_NAME: &str = "zero-lock.sock";
+// Clients that connect and say nothing would hold up every other command
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug)]
+pub enum ControlEvent {
+    // With the profile to lock with, "lock work"
+    Lock(Option<String>),
+    Unlock,
+    // The status is sent back through this channel
+    Status(mpsc::Sender<String>),
+    Stats(mpsc::Sender<String>),
+}
+
+/// In the runtime dir, which only this user can reach
+pub fn socket_path() -> Option<PathBuf> {
+    Some(PathBuf::from(env::var_os("XDG_RUNTIME_DIR")?).join(SOCKET_NAME))
+}
+
+/// Sends a command to the running locker and returns its reply, fails if none is running
+pub fn request(command: &str) -> io::Result<String> {
+    let path = socket_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "XDG_RUNTIME_DIR is not set"))?;
+    let mut stream = StdUnixStream::connect(path)?;
+    stream.write_all(format!("{}\n", command).as_bytes())?;
+    stream.shutdown(std::net::Shutdown::Write)?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+    Ok(reply)
+}
+
+fn bind() -> io::Result<UnixListener> {
+    if let Some(listener) = systemd::listener() {
+        log::info!("control socket passed by the service manager");
+        listener.set_nonblocking(true)?;
+        return UnixListener::from_std(listener);
+    }
+    let path = socket_path()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "XDG_RUNTIME_DIR is not set"))?;
+    // Left behind by a locker that crashed, a running one was checked for on startup
+    if path.exists() && StdUnixStream::connect(&path).is_err() {
+        fs::remove_file(&path)?;
+    }
+    let listener = UnixListener::bind(&path)?;
+    log::info!("control socket at {:?}", path);
+    Ok(listener)
+}
+
+async fn handle(
+    stream: UnixStream,
+    uid: u32,
+    event_tx: &mut futures_mpsc::Sender<ControlEvent>,
+) -> io::Result<()> {
+    let peer_uid = stream.peer_cred()?.uid();
+    let (reader, mut writer) = stream.into_split();
+    if peer_uid != uid && peer_uid != 0 {
+        log::warn!("control socket: refusing UID {}", peer_uid);
+        return writer.write_all(b"error: permission denied\n").await;
+    }
+
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line).await?;
+    let (command, argument_opt) = match line.trim().split_once(' ') {
+        Some((command, argument)) => (command, Some(argument.trim().to_string())),
+        None => (line.trim(), None),
+    };
+    let reply = match (command, argument_opt) {
+        ("lock", profile_opt) => {
+            let _ = event_tx.send(ControlEvent::Lock(profile_opt)).await;
+            "ok\n".to_string()
+        }
+        // Anything running as this user could unlock otherwise, while it can't end the lock by
+        // killing the locker
+        ("unlock", None) if peer_uid != 0 => "error: only root can unlock\n".to_string(),
+        ("unlock", None) => {
+            log::info!("control socket: unlock requested by root");
+            let _ = event_tx.send(ControlEvent::Unlock).await;
+            "ok\n".to_string()
+        }
+        ("status", None) => {
+            let (reply_tx, mut reply_rx) = mpsc::channel(1);
+            let _ = event_tx.send(ControlEvent::Status(reply_tx)).await;
+            reply_rx.recv().await.unwrap_or_default()
+        }
+        ("stats", None) => {
+            let (reply_tx, mut reply_rx) = mpsc::channel(1);
+            let _ = event_tx.send(ControlEvent::Stats(reply_tx)).await;
+            reply_rx.recv().await.unwrap_or_default()
+        }
+        _ => format!("error: unknown command {:?}\n", line.trim()),
+    };
+    writer.write_all(reply.as_bytes()).await
+}
+
+pub fn subscription() -> Subscription<ControlEvent> {
+    struct ControlSubscription;
+
+    subscription::channel(
+        TypeId::of::<ControlSubscription>(),
+        16,
+        |mut event_tx| async move {
+            match bind() {
+                Ok(listener) => {
+                    // Peers are compared to the owner of this process
+                    let uid = fs::metadata("/proc/self").map_or(u32::MAX, |meta| meta.uid());
+                    loop {
+                        match listener.accept().await {
+                            Ok((stream, _)) => {
+                                match time::timeout(
+                                    CLIENT_TIMEOUT,
+                                    handle(stream, uid, &mut event_tx),
+                                )
+                                .await
+                                {
+                                    Ok(Ok(())) => {}
+                                    Ok(Err(err)) => log::warn!("control socket: {}", err),
+                                    Err(_) => log::warn!("control socket: client timed out"),
+                                }
+                            }
+                            Err(err) => {
+                                log::warn!("control socket: failed to accept: {}", err);
+                                break;
+                            }
+                        }
+                    }
+                }
+                Err(err) => log::warn!("no control socket: {}", err),
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
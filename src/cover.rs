@@ -0,0 +1,114 @@
+use cosmic::iced::touch;
+use std::time::{Duration, Instant};
+
+use crate::animation::Timeline;
+
+const REVEAL_DURATION: Duration = Duration::from_millis(250);
+// How far a finger has to travel upwards to count as a swipe
+const SWIPE_DISTANCE: f32 = 80.0;
+// How far the prompt slides in from
+pub const SLIDE_DISTANCE: f32 = 120.0;
+
+/// Shield over the prompt, only the clock is shown until the user asks for the prompt
+#[derive(Clone, Debug)]
+pub struct Cover {
+    enabled: bool,
+    animate: bool,
+    timeout: Duration,
+    revealed: bool,
+    timeline_opt: Option<Timeline>,
+    last_activity: Instant,
+    swipe_start_opt: Option<f32>,
+}
+
+impl Cover {
+    pub fn new(enabled: bool, animate: bool, timeout: Duration) -> Self {
+        Self {
+            enabled,
+            animate,
+            timeout,
+            revealed: !enabled,
+            timeline_opt: None,
+            last_activity: Instant::now(),
+            swipe_start_opt: None,
+        }
+    }
+
+    pub fn revealed(&self) -> bool {
+        self.revealed
+    }
+
+    pub fn animating(&self) -> bool {
+        self.timeline_opt.is_some()
+    }
+
+    /// Key press or click, returns true if this revealed the prompt
+    pub fn activity(&mut self) -> bool {
+        self.last_activity = Instant::now();
+        if self.revealed {
+            return false;
+        }
+        self.revealed = true;
+        if self.animate {
+            self.timeline_opt = Some(Timeline::new(REVEAL_DURATION));
+        }
+        true
+    }
+
+    /// Touch input, only a swipe up reveals the prompt so a stray tap doesn't
+    pub fn touch(&mut self, event: touch::Event) -> bool {
+        match event {
+            touch::Event::FingerPressed { position, .. } => {
+                self.swipe_start_opt = Some(position.y);
+                self.last_activity = Instant::now();
+                false
+            }
+            touch::Event::FingerMoved { position, .. } => match self.swipe_start_opt {
+                Some(start) if start - position.y >= SWIPE_DISTANCE => {
+                    self.swipe_start_opt = None;
+                    self.activity()
+                }
+                _ => false,
+            },
+            touch::Event::FingerLifted { .. } | touch::Event::FingerLost { .. } => {
+                self.swipe_start_opt = None;
+                false
+            }
+        }
+    }
+
+    /// When the prompt will be covered again, if it is shown
+    pub fn deadline(&self) -> Option<Instant> {
+        if !self.enabled || !self.revealed {
+            return None;
+        }
+        Some(self.last_activity + self.timeout)
+    }
+
+    /// Covers the prompt again after the timeout, returns true if it did
+    pub fn check_timeout(&mut self) -> bool {
+        if !self.enabled || !self.revealed || self.last_activity.elapsed() < self.timeout {
+            return false;
+        }
+        self.revealed = false;
+        self.timeline_opt = None;
+        true
+    }
+
+    pub fn frame(&mut self) {
+        if self
+            .timeline_opt
+            .map_or(false, |timeline| timeline.is_done())
+        {
+            self.timeline_opt = None;
+        }
+    }
+
+    /// Vertical offset of the prompt while it slides in
+    pub fn offset(&self) -> f32 {
+        match self.timeline_opt {
+            Some(timeline) => (1.0 - timeline.progress()) * SLIDE_DISTANCE,
+            None => 0.0,
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Crash reports in `$XDG_STATE_HOME/zero-lock/crashes`, written by the panic hook before it
+//! relaunches the locker
+//!
+//! A report has the version, the panic with its backtrace, the renderer and the outputs. Nothing
+//! typed, no command line or environment, and the home directory is shortened to `~` so paths
+//! don't carry the user name.
+
+use chrono::Local;
+use std::{
+    backtrace::Backtrace,
+    env,
+    fmt::{Display, Write},
+    fs,
+    path::{Path, PathBuf},
+    process,
+    sync::Mutex,
+    thread,
+};
+
+const DIR_NAME: &str = "zero-lock/crashes";
+// The oldest ones are removed beyond this
+const MAX_REPORTS: usize = 10;
+
+static OUTPUTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// The outputs for the next report, like "DP-1 2560x1440 at scale 1.5"
+pub fn outputs(outputs: Vec<String>) {
+    if let Ok(mut current) = OUTPUTS.lock() {
+        *current = outputs;
+    }
+}
+
+/// Writes a report for the panic `info`, its message and location, with `details` from the hook,
+/// and returns its path
+pub fn write(info: &dyn Display, details: &[(&str, String)]) -> Option<PathBuf> {
+    let dir = dir()?;
+    if let Err(err) = fs::create_dir_all(&dir) {
+        log::warn!("failed to create {}: {}", dir.display(), err);
+        return None;
+    }
+    let path = dir.join(format!(
+        "{}-{}.txt",
+        Local::now().format("%Y-%m-%dT%H-%M-%S"),
+        process::id()
+    ));
+    if let Err(err) = fs::write(&path, redact(&report(info, details))) {
+        log::warn!("failed to write {}: {}", path.display(), err);
+        return None;
+    }
+    prune(&dir);
+    Some(path)
+}
+
+fn report(info: &dyn Display, details: &[(&str, String)]) -> String {
+    // Writing to a String can't fail
+    let mut report = String::new();
+    let _ = writeln!(report, "zero-lock {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(report, "time: {}", Local::now().to_rfc3339());
+    let _ = writeln!(
+        report,
+        "thread: {}",
+        thread::current().name().unwrap_or("unnamed")
+    );
+    let _ = writeln!(report, "{}", info);
+    for (name, value) in details {
+        let _ = writeln!(report, "{}: {}", name, value);
+    }
+    // Poisoned or held by the panicking thread, the report goes without them
+    let outputs = OUTPUTS
+        .try_lock()
+        .map(|outputs| outputs.clone())
+        .unwrap_or_default();
+    let _ = writeln!(report, "outputs:");
+    for output in outputs {
+        let _ = writeln!(report, "  {}", output);
+    }
+    let _ = writeln!(report, "\n{}", Backtrace::force_capture());
+    report
+}
+
+fn redact(report: &str) -> String {
+    match env::var("HOME") {
+        Ok(home) if !home.is_empty() && home != "/" => report.replace(&home, "~"),
+        _ => report.to_string(),
+    }
+}
+
+fn dir() -> Option<PathBuf> {
+    let state_home = match env::var_os("XDG_STATE_HOME") {
+        Some(state_home) => PathBuf::from(state_home),
+        None => PathBuf::from(env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(state_home.join(DIR_NAME))
+}
+
+// Named by time, so the oldest sort first
+fn prune(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    let excess = paths.len().saturating_sub(MAX_REPORTS);
+    for path in paths.into_iter().take(excess) {
+        if let Err(err) = fs::remove_file(&path) {
+            log::warn!("failed to remove {}: {}", path.display(), err);
+        }
+    }
+}
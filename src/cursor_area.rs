@@ -0,0 +1,183 @@
+use cosmic::iced_core::event::{self, Event};
+use cosmic::iced_core::layout;
+use cosmic::iced_core::mouse;
+use cosmic::iced_core::overlay;
+use cosmic::iced_core::renderer;
+use cosmic::iced_core::widget::{Operation, Tree};
+use cosmic::iced_core::{Clipboard, Element, Layout, Length, Rectangle, Shell, Size, Widget};
+use cosmic::iced_renderer::core::widget::OperationOutputWrapper;
+
+use crate::gesture::Contact;
+
+/// Hides the pointer over its content, otherwise the content picks the cursor
+///
+/// With `on_contact` it also reports the pointer and touches with its bounds, the content still
+/// gets them.
+pub struct CursorArea<'a, Message, Renderer> {
+    content: Element<'a, Message, cosmic::Theme, Renderer>,
+    hidden: bool,
+    on_contact_opt: Option<Box<dyn Fn(Contact, Rectangle) -> Message + 'a>>,
+}
+
+impl<'a, Message, Renderer> CursorArea<'a, Message, Renderer> {
+    pub fn new(content: impl Into<Element<'a, Message, cosmic::Theme, Renderer>>) -> Self {
+        Self {
+            content: content.into(),
+            hidden: false,
+            on_contact_opt: None,
+        }
+    }
+
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    pub fn on_contact(mut self, on_contact: impl Fn(Contact, Rectangle) -> Message + 'a) -> Self {
+        self.on_contact_opt = Some(Box::new(on_contact));
+        self
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, cosmic::Theme, Renderer>
+    for CursorArea<'a, Message, Renderer>
+where
+    Renderer: cosmic::iced_core::Renderer,
+{
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&mut self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_mut(&mut self.content))
+    }
+
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation<OperationOutputWrapper<Message>>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation)
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        if let Some(on_contact) = &self.on_contact_opt {
+            let contact_opt = match &event {
+                Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                    Some(Contact::Pointer(*position))
+                }
+                Event::Mouse(mouse::Event::CursorLeft) => Some(Contact::PointerLeft),
+                Event::Touch(touch_event) => Some(Contact::Touch(*touch_event)),
+                _ => None,
+            };
+            if let Some(contact) = contact_opt {
+                shell.publish(on_contact(contact, layout.bounds()));
+            }
+        }
+        self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        if self.hidden {
+            return mouse::Interaction::None;
+        }
+        // Idle is the themed default arrow rather than whatever the compositor last set
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &cosmic::Theme,
+        renderer_style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            renderer_style,
+            layout,
+            cursor,
+            viewport,
+        )
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+    ) -> Option<overlay::Element<'b, Message, cosmic::Theme, Renderer>> {
+        self.content
+            .as_widget_mut()
+            .overlay(&mut tree.children[0], layout, renderer)
+    }
+}
+
+impl<'a, Message, Renderer> From<CursorArea<'a, Message, Renderer>>
+    for Element<'a, Message, cosmic::Theme, Renderer>
+where
+    Message: 'a,
+    Renderer: 'a + cosmic::iced_core::Renderer,
+{
+    fn from(
+        cursor_area: CursorArea<'a, Message, Renderer>,
+    ) -> Element<'a, Message, cosmic::Theme, Renderer> {
+        Element::new(cursor_area)
+    }
+}
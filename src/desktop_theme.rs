@@ -0,0 +1,90 @@
+use cosmic::{
+    cosmic_config::CosmicConfigEntry,
+    cosmic_theme::{self, ThemeMode},
+    iced::{
+        futures::{channel::mpsc, SinkExt, StreamExt},
+        subscription, Subscription,
+    },
+    Theme,
+};
+use std::{any::TypeId, sync::Arc, time::Duration};
+use tokio::time;
+
+/// The COSMIC desktop theme, dark or light as selected in Settings with its accent color
+pub fn load() -> Theme {
+    let is_dark = match ThemeMode::config().map(|config| ThemeMode::get_entry(&config)) {
+        Ok(Ok(mode)) => mode.is_dark,
+        Ok(Err((errs, mode))) => {
+            log::warn!("errors reading theme mode: {:?}", errs);
+            mode.is_dark
+        }
+        Err(err) => {
+            log::info!("failed to open theme mode config: {:?}", err);
+            true
+        }
+    };
+
+    let config_res = if is_dark {
+        cosmic_theme::Theme::dark_config()
+    } else {
+        cosmic_theme::Theme::light_config()
+    };
+    match config_res.map(|config| cosmic_theme::Theme::get_entry(&config)) {
+        Ok(Ok(theme)) => Theme::custom(Arc::new(theme)),
+        Ok(Err((errs, theme))) => {
+            log::warn!("errors reading theme: {:?}", errs);
+            Theme::custom(Arc::new(theme))
+        }
+        Err(err) => {
+            log::info!("failed to open theme config: {:?}", err);
+            if is_dark {
+                Theme::dark()
+            } else {
+                Theme::light()
+            }
+        }
+    }
+}
+
+pub fn subscription() -> Subscription<Theme> {
+    struct ThemeSubscription;
+
+    subscription::channel(
+        TypeId::of::<ThemeSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            let (change_tx, mut change_rx) = mpsc::channel(4);
+
+            // Switching between dark and light, or editing either theme
+            let mut watchers = Vec::with_capacity(3);
+            for config_res in [
+                ThemeMode::config(),
+                cosmic_theme::Theme::dark_config(),
+                cosmic_theme::Theme::light_config(),
+            ] {
+                let mut change_tx = change_tx.clone();
+                match config_res.and_then(|config| {
+                    config.watch(move |_config, _keys| {
+                        // A full channel already has a reload pending
+                        let _ = change_tx.try_send(());
+                    })
+                }) {
+                    Ok(watcher) => watchers.push(watcher),
+                    Err(err) => log::warn!("failed to watch theme config: {:?}", err),
+                }
+            }
+            drop(change_tx);
+
+            while change_rx.next().await.is_some() {
+                if msg_tx.send(load()).await.is_err() {
+                    // The locker stopped listening
+                    break;
+                }
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
@@ -0,0 +1,41 @@
+//! Returns once the session is locked, with the locker left running, for swaylock's `-f`
+//!
+//! swayidle and xss-lock wait for the locker to exit before they let the system sleep, so the
+//! session has to be locked by then. The locker says so on its control socket.
+
+use crate::control;
+use std::{
+    env,
+    error::Error,
+    ffi::OsString,
+    process::Command,
+    thread,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+// Locking takes well under a second, a locker this slow is not going to lock
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn main(args: Vec<OsString>) -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(env::current_exe()?).args(&args).spawn()?;
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            // Unlocked before the status was seen, or failed to lock
+            if status.success() {
+                return Ok(());
+            }
+            return Err(format!("locker exited with {}", status).into());
+        }
+        let locked =
+            control::request("status").map_or(false, |reply| reply.starts_with("state: locked"));
+        if locked {
+            return Ok(());
+        }
+        if started.elapsed() >= LOCK_TIMEOUT {
+            return Err("timed out waiting for the session to lock".into());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
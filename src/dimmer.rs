@@ -0,0 +1,226 @@
+use cosmic::iced_core::event::{self, Event};
+use cosmic::iced_core::layout;
+use cosmic::iced_core::mouse;
+use cosmic::iced_core::overlay;
+use cosmic::iced_core::renderer;
+use cosmic::iced_core::widget::{Operation, Tree};
+use cosmic::iced_core::{
+    Border, Clipboard, Color, Element, Layout, Length, Rectangle, Shadow, Shell, Size, Widget,
+};
+use cosmic::iced_renderer::core::widget::OperationOutputWrapper;
+
+// How far the tint goes at the warmest night light
+const TINT_STRENGTH: f32 = 0.5;
+
+/// An overlay approximating a night light at this color temperature, None at daylight
+///
+/// Gamma would scale the colors instead of blending over them, but lock surfaces can't set it.
+pub fn night_light_tint(kelvin: u32) -> Option<Color> {
+    let kelvin = kelvin.clamp(1000, 6500) as f32;
+    let strength = (6500.0 - kelvin) / 5500.0 * TINT_STRENGTH;
+    if strength <= 0.0 {
+        return None;
+    }
+    // Tanner Helland's fit of the black body colors, red is full below 6600K
+    let t = kelvin / 100.0;
+    let green = (99.470_8 * t.ln() - 161.119_57) / 255.0;
+    let blue = if t <= 19.0 {
+        0.0
+    } else {
+        (138.517_73 * (t - 10.0).ln() - 305.044_8) / 255.0
+    };
+    Some(Color::from_rgba(
+        1.0,
+        green.clamp(0.0, 1.0),
+        blue.clamp(0.0, 1.0),
+        strength,
+    ))
+}
+
+/// Darkens its content with a black overlay, from 0.0 for none to 1.0 for black
+pub struct Dimmer<'a, Message, Renderer> {
+    content: Element<'a, Message, cosmic::Theme, Renderer>,
+    level: f32,
+    tint_opt: Option<Color>,
+    asleep: bool,
+}
+
+impl<'a, Message, Renderer> Dimmer<'a, Message, Renderer> {
+    pub fn new(content: impl Into<Element<'a, Message, cosmic::Theme, Renderer>>) -> Self {
+        Self {
+            content: content.into(),
+            level: 0.0,
+            tint_opt: None,
+            asleep: false,
+        }
+    }
+
+    pub fn level(mut self, level: f32) -> Self {
+        self.level = level.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Blended over the content before dimming, see [`night_light_tint`]
+    pub fn tint(mut self, tint_opt: Option<Color>) -> Self {
+        self.tint_opt = tint_opt;
+        self
+    }
+
+    /// Input only wakes the screen, the content doesn't see it
+    pub fn asleep(mut self, asleep: bool) -> Self {
+        self.asleep = asleep;
+        self
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, cosmic::Theme, Renderer>
+    for Dimmer<'a, Message, Renderer>
+where
+    Renderer: cosmic::iced_core::Renderer,
+{
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&mut self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_mut(&mut self.content))
+    }
+
+    fn size(&self) -> Size<Length> {
+        self.content.as_widget().size()
+    }
+
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        self.content
+            .as_widget()
+            .layout(&mut tree.children[0], renderer, limits)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation<OperationOutputWrapper<Message>>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation)
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        if self.asleep
+            && matches!(
+                event,
+                Event::Keyboard(_) | Event::Mouse(_) | Event::Touch(_)
+            )
+        {
+            return event::Status::Captured;
+        }
+        self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event,
+            layout,
+            cursor,
+            renderer,
+            clipboard,
+            shell,
+            viewport,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &cosmic::Theme,
+        renderer_style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            renderer_style,
+            layout,
+            cursor,
+            viewport,
+        );
+
+        let dim_opt = (self.level > 0.0).then_some(Color {
+            a: self.level,
+            ..Color::BLACK
+        });
+        for color in [self.tint_opt, dim_opt].into_iter().flatten() {
+            // Its own layer so it is drawn over everything in the content
+            renderer.with_layer(layout.bounds(), |renderer| {
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: layout.bounds(),
+                        border: Border::default(),
+                        shadow: Shadow::default(),
+                    },
+                    color,
+                );
+            });
+        }
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+    ) -> Option<overlay::Element<'b, Message, cosmic::Theme, Renderer>> {
+        self.content
+            .as_widget_mut()
+            .overlay(&mut tree.children[0], layout, renderer)
+    }
+}
+
+impl<'a, Message, Renderer> From<Dimmer<'a, Message, Renderer>>
+    for Element<'a, Message, cosmic::Theme, Renderer>
+where
+    Message: 'a,
+    Renderer: 'a + cosmic::iced_core::Renderer,
+{
+    fn from(
+        dimmer: Dimmer<'a, Message, Renderer>,
+    ) -> Element<'a, Message, cosmic::Theme, Renderer> {
+        Element::new(dimmer)
+    }
+}
@@ -0,0 +1,56 @@
+//! Switching users through the display manager, which starts a greeter on another VT while this
+//! session stays locked
+
+use std::env;
+use zbus::{
+    dbus_proxy, fdo::DBusProxy, names::BusName, zvariant::OwnedObjectPath, Connection, Result,
+};
+
+const GDM_SERVICE: &str = "org.gnome.DisplayManager";
+const LIGHTDM_SERVICE: &str = "org.freedesktop.DisplayManager";
+
+#[dbus_proxy(
+    interface = "org.gnome.DisplayManager.LocalDisplayFactory",
+    default_service = "org.gnome.DisplayManager",
+    default_path = "/org/gnome/DisplayManager/LocalDisplayFactory"
+)]
+trait LocalDisplayFactory {
+    fn create_transient_display(&self) -> Result<OwnedObjectPath>;
+}
+
+// The path of this session's seat is in XDG_SEAT_PATH
+#[dbus_proxy(
+    interface = "org.freedesktop.DisplayManager.Seat",
+    default_service = "org.freedesktop.DisplayManager"
+)]
+trait Seat {
+    fn switch_to_greeter(&self) -> Result<()>;
+}
+
+async fn has_owner(zbus: &Connection, service: &'static str) -> Result<bool> {
+    let dbus = DBusProxy::new(zbus).await?;
+    Ok(dbus.name_has_owner(BusName::try_from(service)?).await?)
+}
+
+/// Whether GDM or LightDM is running and can start another greeter
+pub async fn available() -> Result<bool> {
+    let zbus = Connection::system().await?;
+    if env::var_os("XDG_SEAT_PATH").is_some() && has_owner(&zbus, LIGHTDM_SERVICE).await? {
+        return Ok(true);
+    }
+    has_owner(&zbus, GDM_SERVICE).await
+}
+
+pub async fn switch_user() -> Result<()> {
+    let zbus = Connection::system().await?;
+    if let Ok(seat_path) = env::var("XDG_SEAT_PATH") {
+        if has_owner(&zbus, LIGHTDM_SERVICE).await? {
+            let seat = SeatProxy::builder(&zbus).path(seat_path)?.build().await?;
+            return seat.switch_to_greeter().await;
+        }
+    }
+    let factory = LocalDisplayFactoryProxy::new(&zbus).await?;
+    let display = factory.create_transient_display().await?;
+    log::info!("started greeter display {}", display.as_str());
+    Ok(())
+}
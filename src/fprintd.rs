@@ -0,0 +1,117 @@
+//! Fingerprint unlock through fprintd, waiting next to the password prompt
+//!
+//! The reader is claimed over D-Bus instead of through a second PAM conversation, which could
+//! only be abandoned blocked inside pam_fprintd. Dropping the subscription closes its connection
+//! and fprintd releases the reader for the next claim.
+
+use cosmic::iced::{
+    futures::{channel::mpsc, SinkExt, StreamExt},
+    subscription, Subscription,
+};
+use std::{any::TypeId, time::Duration};
+use tokio::time;
+use zbus::{dbus_proxy, zvariant::OwnedObjectPath, Connection, Result};
+
+// pam_fprintd's default too
+const MAX_ATTEMPTS: u32 = 3;
+
+#[dbus_proxy(
+    interface = "net.reactivated.Fprint.Manager",
+    default_service = "net.reactivated.Fprint",
+    default_path = "/net/reactivated/Fprint/Manager"
+)]
+trait Manager {
+    fn get_default_device(&self) -> Result<OwnedObjectPath>;
+}
+
+#[dbus_proxy(
+    interface = "net.reactivated.Fprint.Device",
+    default_service = "net.reactivated.Fprint"
+)]
+trait Device {
+    fn claim(&self, username: &str) -> Result<()>;
+
+    fn verify_start(&self, finger_name: &str) -> Result<()>;
+
+    fn verify_stop(&self) -> Result<()>;
+
+    // "verify-match", "verify-no-match", or a retry like "verify-swipe-too-short" with done false
+    #[dbus_proxy(signal)]
+    fn verify_status(&self, result: &str, done: bool) -> Result<()>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FingerprintEvent {
+    // The reader is waiting for a finger
+    Ready,
+    NoMatch,
+    Match,
+    // Out of attempts or the reader went away, the password still works
+    Stopped,
+}
+
+pub fn subscription(username: String) -> Subscription<FingerprintEvent> {
+    struct FingerprintSubscription;
+
+    subscription::channel(
+        TypeId::of::<FingerprintSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            if let Err(err) = handler(&username, &mut msg_tx).await {
+                log::info!("no fingerprint unlock: {}", err);
+            }
+            let _ = msg_tx.send(FingerprintEvent::Stopped).await;
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+async fn handler(username: &str, msg_tx: &mut mpsc::Sender<FingerprintEvent>) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let manager = ManagerProxy::new(&zbus).await?;
+    let path = manager.get_default_device().await?;
+    let device = DeviceProxy::builder(&zbus).path(path)?.build().await?;
+    // Fails for users without enrolled fingers, or while another app holds the reader
+    device.claim(username).await?;
+    let mut statuses = device.receive_verify_status().await?;
+    for attempt in 1..=MAX_ATTEMPTS {
+        device.verify_start("any").await?;
+        if msg_tx.send(FingerprintEvent::Ready).await.is_err() {
+            // The locker stopped listening, dropping the connection releases the reader
+            return Ok(());
+        }
+        let result = loop {
+            let Some(status) = statuses.next().await else {
+                return Ok(());
+            };
+            let args = status.args()?;
+            if args.done {
+                break args.result.to_string();
+            }
+            log::debug!("fingerprint {}", args.result);
+        };
+        device.verify_stop().await?;
+        match result.as_str() {
+            "verify-match" => {
+                log::info!("fingerprint matched");
+                let _ = msg_tx.send(FingerprintEvent::Match).await;
+                return Ok(());
+            }
+            "verify-no-match" => {
+                log::warn!("fingerprint did not match, attempt {}", attempt);
+                if msg_tx.send(FingerprintEvent::NoMatch).await.is_err() {
+                    return Ok(());
+                }
+            }
+            // Disconnected or an unknown error, trying again won't help
+            _ => {
+                log::warn!("fingerprint verification stopped: {}", result);
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//! A blurred copy of the wallpaper for the frosted card behind the clock and the prompt
+//!
+//! The renderer can't blur what is behind a widget, so the card draws its part of a copy blurred
+//! once when the session locks. The copy is shrunk first, which keeps a 4K wallpaper from taking
+//! seconds and is most of the blur for free.
+
+use cosmic::iced::widget::image;
+use cosmic::iced_renderer::graphics::image::{image_rs::imageops::FilterType, load};
+use tokio::task;
+
+// Still sharp enough at the card's edge to line up with the wallpaper around it
+const MAX_WIDTH: u32 = 640;
+
+#[derive(Clone, Debug)]
+pub struct Frosted {
+    pub handle: image::Handle,
+    // Of the copy to the wallpaper it was made from
+    pub scale: f32,
+}
+
+/// `handle` blurred by `radius` pixels of the wallpaper's own size
+pub async fn blur(handle: image::Handle, radius: f32) -> Result<Frosted, String> {
+    task::spawn_blocking(move || blur_blocking(&handle, radius))
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+fn blur_blocking(handle: &image::Handle, radius: f32) -> Result<Frosted, String> {
+    let image = load(handle).map_err(|err| err.to_string())?;
+    let scale = (MAX_WIDTH as f32 / image.width().max(1) as f32).min(1.0);
+    let small = image.resize(
+        ((image.width() as f32 * scale) as u32).max(1),
+        ((image.height() as f32 * scale) as u32).max(1),
+        FilterType::Triangle,
+    );
+    // A gaussian reaches about three sigmas
+    let sigma = radius * scale / 3.0;
+    let blurred = if sigma > 0.0 {
+        small.blur(sigma)
+    } else {
+        small
+    };
+    let rgba = blurred.into_rgba8();
+    Ok(Frosted {
+        handle: image::Handle::from_pixels(rgba.width(), rgba.height(), rgba.into_raw()),
+        scale,
+    })
+}
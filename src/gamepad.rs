@@ -0,0 +1,128 @@
+//! Game controllers, for HTPCs that often have no keyboard attached
+//!
+//! The D-pad or left stick moves over the on-screen keyboard and opens it, A presses the key, B
+//! erases, X switches to symbols, L1 is shift, Y closes the keyboard and Start submits.
+
+use cosmic::iced::Subscription;
+#[cfg(feature = "gamepad")]
+use cosmic::iced::{
+    futures::{channel::mpsc, SinkExt},
+    subscription,
+};
+#[cfg(feature = "gamepad")]
+use gilrs::{Axis, Button, EventType, Gilrs};
+#[cfg(feature = "gamepad")]
+use std::{any::TypeId, mem, thread, time::Duration};
+#[cfg(feature = "gamepad")]
+use tokio::time;
+
+use crate::osk::Direction;
+
+// How far the stick has to go before it counts as a move, once until it is let go
+#[cfg(feature = "gamepad")]
+const STICK_THRESHOLD: f32 = 0.5;
+
+#[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+#[derive(Clone, Copy, Debug)]
+pub enum GamepadEvent {
+    Move(Direction),
+    Press,
+    Backspace,
+    Shift,
+    Symbols,
+    ToggleOsk,
+    Submit,
+}
+
+#[cfg(feature = "gamepad")]
+pub fn subscription() -> Subscription<GamepadEvent> {
+    struct GamepadSubscription;
+
+    subscription::channel(
+        TypeId::of::<GamepadSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            let (event_tx, mut event_rx) = tokio::sync::mpsc::channel(16);
+            // gilrs reads the devices with blocking calls
+            thread::spawn(move || {
+                if let Err(err) = events(event_tx) {
+                    log::info!("no game controllers: {}", err);
+                }
+            });
+            while let Some(event) = event_rx.recv().await {
+                if msg_tx.send(event).await.is_err() {
+                    // The locker stopped listening, the thread ends with the next event
+                    break;
+                }
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+#[cfg(not(feature = "gamepad"))]
+pub fn subscription() -> Subscription<GamepadEvent> {
+    Subscription::none()
+}
+
+// Until the subscription is dropped
+#[cfg(feature = "gamepad")]
+fn events(event_tx: tokio::sync::mpsc::Sender<GamepadEvent>) -> Result<(), gilrs::Error> {
+    let mut gilrs = Gilrs::new()?;
+    for (_id, gamepad) in gilrs.gamepads() {
+        log::info!("game controller {:?}", gamepad.name());
+    }
+    // Whether the stick is out on the X and Y axis
+    let mut deflected = [false; 2];
+    loop {
+        let Some(gilrs::Event { id, event, .. }) = gilrs.next_event_blocking(None) else {
+            continue;
+        };
+        let event_opt = match event {
+            EventType::ButtonPressed(button, _) => button_event(button),
+            EventType::AxisChanged(axis, value, _) => {
+                // Up is positive on gilrs' Y axis
+                let (index, direction) = match (axis, value > 0.0) {
+                    (Axis::LeftStickX, true) => (0, Direction::Right),
+                    (Axis::LeftStickX, false) => (0, Direction::Left),
+                    (Axis::LeftStickY, true) => (1, Direction::Up),
+                    (Axis::LeftStickY, false) => (1, Direction::Down),
+                    _ => continue,
+                };
+                let out = value.abs() >= STICK_THRESHOLD;
+                let was_out = mem::replace(&mut deflected[index], out);
+                (out && !was_out).then_some(GamepadEvent::Move(direction))
+            }
+            EventType::Connected => {
+                log::info!("game controller {:?} connected", gilrs.gamepad(id).name());
+                None
+            }
+            _ => None,
+        };
+        if let Some(event) = event_opt {
+            if event_tx.blocking_send(event).is_err() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gamepad")]
+fn button_event(button: Button) -> Option<GamepadEvent> {
+    Some(match button {
+        Button::DPadUp => GamepadEvent::Move(Direction::Up),
+        Button::DPadDown => GamepadEvent::Move(Direction::Down),
+        Button::DPadLeft => GamepadEvent::Move(Direction::Left),
+        Button::DPadRight => GamepadEvent::Move(Direction::Right),
+        Button::South => GamepadEvent::Press,
+        Button::East => GamepadEvent::Backspace,
+        Button::West => GamepadEvent::Symbols,
+        Button::North => GamepadEvent::ToggleOsk,
+        Button::LeftTrigger => GamepadEvent::Shift,
+        Button::Start => GamepadEvent::Submit,
+        _ => return None,
+    })
+}
@@ -0,0 +1,243 @@
+//! Touch gestures for tablets, which have no keyboard to reach a shortcut
+//!
+//! iced sends nothing while a finger rests, so a long press is confirmed by a delayed check with
+//! the serial of the press it was started for.
+//!
+//! The hot corner and edge swipe are measured against the lock surface's bounds, which only its
+//! widgets know, so `CursorArea` reports them as contacts instead of the event subscription.
+
+use cosmic::iced::{touch, Point, Rectangle};
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+// Held this long without moving opens the power menu
+pub const LONG_PRESS: Duration = Duration::from_millis(600);
+// A finger drifting further is dragging or swiping, not pressing
+const MOVE_TOLERANCE: f32 = 16.0;
+// Lifted sooner is a tap
+const TAP_DURATION: Duration = Duration::from_millis(250);
+// The second tap of a double tap comes this soon after the first and lands this close to it, a
+// hand brushing the screen rarely does both
+const DOUBLE_TAP_INTERVAL: Duration = Duration::from_millis(400);
+const DOUBLE_TAP_DISTANCE: f32 = 48.0;
+// Resting the pointer in the hot corner this long opens the power menu, passing through doesn't
+pub const HOT_CORNER_DWELL: Duration = Duration::from_millis(500);
+// The pointer stops at the screen edge, a few pixels are for scaled outputs rounding it off
+const CORNER_SIZE: f32 = 8.0;
+// An edge swipe starts this close to the edge, and pulls this far inwards
+const EDGE_SIZE: f32 = 24.0;
+const EDGE_SWIPE_DISTANCE: f32 = 80.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Edge {
+    // How far inside the bounds from this edge
+    fn distance(self, position: Point, bounds: Rectangle) -> f32 {
+        match self {
+            Self::Top => position.y - bounds.y,
+            Self::Bottom => bounds.y + bounds.height - position.y,
+            Self::Left => position.x - bounds.x,
+            Self::Right => bounds.x + bounds.width - position.x,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    fn contains(self, position: Point, bounds: Rectangle) -> bool {
+        let (vertical, horizontal) = match self {
+            Self::TopLeft => (Edge::Top, Edge::Left),
+            Self::TopRight => (Edge::Top, Edge::Right),
+            Self::BottomLeft => (Edge::Bottom, Edge::Left),
+            Self::BottomRight => (Edge::Bottom, Edge::Right),
+        };
+        vertical.distance(position, bounds) <= CORNER_SIZE
+            && horizontal.distance(position, bounds) <= CORNER_SIZE
+    }
+}
+
+/// Pointer or touch input on a lock surface, as reported by `CursorArea`
+#[derive(Clone, Copy, Debug)]
+pub enum Contact {
+    Pointer(Point),
+    PointerLeft,
+    Touch(touch::Event),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gesture {
+    // Check with `Gestures::long_press` after `LONG_PRESS`
+    Pressed(u64),
+    DoubleTap,
+    // Check with `Gestures::hot_corner` after `HOT_CORNER_DWELL`
+    Cornered(u64),
+    EdgeSwipe,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Press {
+    serial: u64,
+    finger: touch::Finger,
+    start: Point,
+    at: Instant,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Gestures {
+    press_opt: Option<Press>,
+    serial: u64,
+    // Where and when the last tap was, waiting for a second one
+    tap_opt: Option<(Point, Instant)>,
+    // Serial of the pointer's stay in the hot corner, while it is in there
+    corner_opt: Option<u64>,
+    // The finger that went down at the swipe edge, and where
+    swipe_opt: Option<(touch::Finger, Point)>,
+}
+
+impl Gestures {
+    pub fn touch(&mut self, event: touch::Event) -> Option<Gesture> {
+        match event {
+            touch::Event::FingerPressed { id, position } => {
+                // A second finger is a pinch or a palm, neither is a press
+                if self.press_opt.take().is_some() {
+                    self.tap_opt = None;
+                    return None;
+                }
+                self.serial += 1;
+                self.press_opt = Some(Press {
+                    serial: self.serial,
+                    finger: id,
+                    start: position,
+                    at: Instant::now(),
+                });
+                Some(Gesture::Pressed(self.serial))
+            }
+            touch::Event::FingerMoved { id, position } => {
+                if let Some(press) = self.press_opt {
+                    if press.finger == id && press.start.distance(position) > MOVE_TOLERANCE {
+                        self.press_opt = None;
+                    }
+                }
+                None
+            }
+            touch::Event::FingerLifted { id, .. } => {
+                let press = self.press_opt.filter(|press| press.finger == id)?;
+                self.press_opt = None;
+                if press.at.elapsed() > TAP_DURATION {
+                    return None;
+                }
+                match self.tap_opt.take() {
+                    Some((position, at))
+                        if at.elapsed() <= DOUBLE_TAP_INTERVAL
+                            && position.distance(press.start) <= DOUBLE_TAP_DISTANCE =>
+                    {
+                        Some(Gesture::DoubleTap)
+                    }
+                    _ => {
+                        self.tap_opt = Some((press.start, Instant::now()));
+                        None
+                    }
+                }
+            }
+            touch::Event::FingerLost { id, .. } => {
+                if self.press_opt.map_or(false, |press| press.finger == id) {
+                    self.press_opt = None;
+                }
+                None
+            }
+        }
+    }
+
+    /// True if the press with this serial is still held in place
+    pub fn long_press(&mut self, serial: u64) -> bool {
+        match self.press_opt {
+            Some(press) if press.serial == serial => {
+                self.press_opt = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Pointer and touches against the bounds of the surface they are on, for the configured hot
+    /// corner and edge swipe
+    pub fn contact(
+        &mut self,
+        contact: Contact,
+        bounds: Rectangle,
+        hot_corner_opt: Option<Corner>,
+        edge_swipe_opt: Option<Edge>,
+    ) -> Option<Gesture> {
+        match contact {
+            Contact::Pointer(position) => {
+                if !hot_corner_opt.is_some_and(|corner| corner.contains(position, bounds)) {
+                    self.corner_opt = None;
+                    return None;
+                }
+                // Only entering the corner starts the dwell, moving around inside it doesn't
+                if self.corner_opt.is_some() {
+                    return None;
+                }
+                self.serial += 1;
+                self.corner_opt = Some(self.serial);
+                Some(Gesture::Cornered(self.serial))
+            }
+            Contact::PointerLeft => {
+                self.corner_opt = None;
+                None
+            }
+            Contact::Touch(event) => {
+                let edge = edge_swipe_opt?;
+                match event {
+                    touch::Event::FingerPressed { id, position } => {
+                        // A second finger is a pinch or a palm, same as for presses
+                        if self.swipe_opt.take().is_some() {
+                            return None;
+                        }
+                        if edge.distance(position, bounds) <= EDGE_SIZE {
+                            self.swipe_opt = Some((id, position));
+                        }
+                        None
+                    }
+                    touch::Event::FingerMoved { id, position } => {
+                        let (finger, start) = self.swipe_opt?;
+                        if finger != id
+                            || edge.distance(position, bounds) - edge.distance(start, bounds)
+                                < EDGE_SWIPE_DISTANCE
+                        {
+                            return None;
+                        }
+                        self.swipe_opt = None;
+                        Some(Gesture::EdgeSwipe)
+                    }
+                    touch::Event::FingerLifted { id, .. } | touch::Event::FingerLost { id, .. } => {
+                        if self.swipe_opt.map_or(false, |(finger, _)| finger == id) {
+                            self.swipe_opt = None;
+                        }
+                        None
+                    }
+                }
+            }
+        }
+    }
+
+    /// True if the pointer entered the hot corner with this serial and is still in it
+    pub fn hot_corner(&self, serial: u64) -> bool {
+        self.corner_opt == Some(serial)
+    }
+}
@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+// Lock sources that get no grace period, input after resuming or opening the lid isn't the user
+// coming back in time
+pub const SLEEP: &str = "sleep";
+pub const LID: &str = "lid switch";
+
+// CLOCK_BOOTTIME keeps counting while suspended, Instant doesn't, a window started before a
+// suspend would still be open after it
+fn boot_time() -> Duration {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // Can only fail for an unknown clock or a bad pointer
+    unsafe { libc::clock_gettime(libc::CLOCK_BOOTTIME, &mut ts) };
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+/// The window after locking in which input unlocks without a password
+#[derive(Clone, Debug, Default)]
+pub struct GracePeriod {
+    started_opt: Option<Duration>,
+    // The pending lock was requested for sleep or a closed lid
+    skipped: bool,
+}
+
+impl GracePeriod {
+    /// Notes what the pending lock was requested by
+    pub fn requested(&mut self, source: &str) {
+        self.skipped = matches!(source, SLEEP | LID);
+    }
+
+    /// Opens the window unless the lock was for sleep or the lid, returns whether it did
+    pub fn start(&mut self) -> bool {
+        if self.skipped {
+            return false;
+        }
+        self.started_opt = Some(boot_time());
+        true
+    }
+
+    /// Closes the window early, the system is about to sleep
+    pub fn end(&mut self) {
+        self.started_opt = None;
+    }
+
+    pub fn contains(&self, period: Duration) -> bool {
+        self.started_opt.map_or(false, |started| {
+            boot_time().saturating_sub(started) < period
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERIOD: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn idle_lock_has_grace() {
+        let mut grace = GracePeriod::default();
+        grace.requested("idle timeout");
+        assert!(grace.start());
+        assert!(grace.contains(PERIOD));
+        assert!(!grace.contains(Duration::ZERO));
+    }
+
+    #[test]
+    fn sleep_lock_has_no_grace() {
+        for source in [SLEEP, LID] {
+            let mut grace = GracePeriod::default();
+            grace.requested(source);
+            assert!(!grace.start());
+            assert!(!grace.contains(PERIOD));
+        }
+    }
+
+    #[test]
+    fn sleep_ends_grace() {
+        let mut grace = GracePeriod::default();
+        grace.requested("control socket");
+        assert!(grace.start());
+        grace.end();
+        assert!(!grace.contains(PERIOD));
+    }
+
+    #[test]
+    fn boot_time_advances() {
+        let before = boot_time();
+        assert!(before > Duration::ZERO);
+        assert!(boot_time() >= before);
+    }
+}
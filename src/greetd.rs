@@ -0,0 +1,195 @@
+//! Greeter side of the greetd IPC protocol
+//!
+//! Messages are JSON with a native-endian u32 length prefix over the socket in `GREETD_SOCK`. The
+//! conversation is turned into the same events as PAM authentication, starting with a username
+//! prompt, so the prompt UI is shared with the locker.
+
+use cosmic::iced::{
+    futures::{channel::mpsc as futures_mpsc, SinkExt},
+    subscription, Subscription,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    any::TypeId,
+    env,
+    error::Error,
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::UnixStream,
+    sync::{mpsc, watch},
+    time,
+};
+
+use crate::{
+    auth::{self, AuthEvent},
+    fl,
+    sessions::Session,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Request {
+    CreateSession { username: String },
+    PostAuthMessageResponse { response: Option<String> },
+    StartSession { cmd: Vec<String>, env: Vec<String> },
+    CancelSession,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum AuthMessageType {
+    Visible,
+    Secret,
+    Info,
+    Error,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Response {
+    Success,
+    Error {
+        description: String,
+    },
+    AuthMessage {
+        auth_message_type: AuthMessageType,
+        auth_message: String,
+    },
+}
+
+struct Client {
+    stream: UnixStream,
+}
+
+impl Client {
+    async fn connect() -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let path = env::var_os("GREETD_SOCK").ok_or("GREETD_SOCK is not set")?;
+        Ok(Self {
+            stream: UnixStream::connect(path).await?,
+        })
+    }
+
+    async fn request(
+        &mut self,
+        request: &Request,
+    ) -> Result<Response, Box<dyn Error + Send + Sync>> {
+        let json = serde_json::to_vec(request)?;
+        self.stream
+            .write_all(&(json.len() as u32).to_ne_bytes())
+            .await?;
+        self.stream.write_all(&json).await?;
+
+        let mut len = [0; 4];
+        self.stream.read_exact(&mut len).await?;
+        let mut json = vec![0; u32::from_ne_bytes(len) as usize];
+        self.stream.read_exact(&mut json).await?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+}
+
+/// Logs in through greetd and starts the session selected at that point
+pub fn subscription(session_rx: watch::Receiver<Session>) -> Subscription<AuthEvent> {
+    struct GreetdSubscription;
+
+    subscription::channel(
+        TypeId::of::<GreetdSubscription>(),
+        16,
+        |mut event_tx| async move {
+            if let Err(err) = handler(&mut event_tx, session_rx).await {
+                log::error!("greetd error: {}", err);
+                let _ = event_tx.send(AuthEvent::Error(err.to_string())).await;
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+async fn handler(
+    event_tx: &mut futures_mpsc::Sender<AuthEvent>,
+    session_rx: watch::Receiver<Session>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let mut client = Client::connect().await?;
+    loop {
+        let (value_tx, mut value_rx) = mpsc::channel(16);
+        event_tx.send(AuthEvent::Channel(value_tx)).await?;
+        event_tx
+            .send(AuthEvent::Prompt(fl!("username-prompt"), false))
+            .await?;
+        let username = value_rx.recv().await.ok_or("prompt channel closed")?;
+        let mut answered_at = Instant::now();
+
+        event_tx.send(AuthEvent::Username(username.clone())).await?;
+
+        let mut response = client.request(&Request::CreateSession { username }).await?;
+        loop {
+            let request = match response {
+                Response::AuthMessage {
+                    auth_message_type,
+                    auth_message,
+                } => {
+                    let response = match auth_message_type {
+                        AuthMessageType::Visible | AuthMessageType::Secret => {
+                            let secret = matches!(auth_message_type, AuthMessageType::Secret);
+                            event_tx
+                                .send(AuthEvent::Prompt(auth_message, secret))
+                                .await?;
+                            let value = value_rx.recv().await.ok_or("prompt channel closed")?;
+                            answered_at = Instant::now();
+                            Some(value)
+                        }
+                        AuthMessageType::Info => {
+                            event_tx.send(AuthEvent::Info(auth_message)).await?;
+                            None
+                        }
+                        AuthMessageType::Error => {
+                            event_tx.send(AuthEvent::Error(auth_message)).await?;
+                            None
+                        }
+                    };
+                    Request::PostAuthMessageResponse { response }
+                }
+                Response::Success => {
+                    // greetd starts the session once the greeter exits
+                    let session = session_rx.borrow().clone();
+                    match client
+                        .request(&Request::StartSession {
+                            cmd: session.cmd.clone(),
+                            env: session.env,
+                        })
+                        .await?
+                    {
+                        Response::Success => {
+                            log::info!("starting session {:?}", session.cmd);
+                            event_tx.send(AuthEvent::Success).await?;
+                            return Ok(());
+                        }
+                        Response::Error { description } => {
+                            log::warn!("failed to start session: {}", description);
+                            event_tx.send(AuthEvent::Error(description)).await?;
+                        }
+                        Response::AuthMessage { .. } => {
+                            log::warn!("unexpected auth message when starting session");
+                        }
+                    }
+                    client.request(&Request::CancelSession).await?;
+                    break;
+                }
+                Response::Error { description } => {
+                    log::warn!("authentication failed: {}", description);
+                    // An unknown username fails right away otherwise
+                    time::sleep(auth::failure_padding(answered_at)).await;
+                    event_tx.send(AuthEvent::Failure(description)).await?;
+                    // Back to the username prompt
+                    client.request(&Request::CancelSession).await?;
+                    break;
+                }
+            };
+            response = client.request(&request).await?;
+        }
+    }
+}
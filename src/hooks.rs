@@ -0,0 +1,49 @@
+//! User commands from the config, run when locking, unlocking and after a failed attempt
+
+use std::{process::Stdio, time::Duration};
+use tokio::{process::Command, time};
+
+// Hooks that hang are killed, nothing waits on them except a locker about to exit
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Runs `command` with `sh -c` and logs its output, `name` is passed as `ZERO_LOCK_HOOK`
+pub async fn run(name: &'static str, command: String) {
+    let child_res = Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .env("ZERO_LOCK_HOOK", name)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn();
+    let child = match child_res {
+        Ok(child) => child,
+        Err(err) => {
+            log::warn!("failed to run {} hook {:?}: {}", name, command, err);
+            return;
+        }
+    };
+
+    match time::timeout(HOOK_TIMEOUT, child.wait_with_output()).await {
+        Ok(Ok(output)) => {
+            for line in String::from_utf8_lossy(&output.stdout).lines() {
+                log::info!("{} hook: {}", name, line);
+            }
+            for line in String::from_utf8_lossy(&output.stderr).lines() {
+                log::warn!("{} hook: {}", name, line);
+            }
+            if output.status.success() {
+                log::info!("{} hook finished", name);
+            } else {
+                log::warn!("{} hook exited with {}", name, output.status);
+            }
+        }
+        Ok(Err(err)) => log::warn!("failed to wait for {} hook: {}", name, err),
+        Err(_) => log::warn!(
+            "{} hook still running after {}s, killed it",
+            name,
+            HOOK_TIMEOUT.as_secs()
+        ),
+    }
+}
@@ -0,0 +1,129 @@
+//! Session idle time through ext-idle-notify, for locking from daemon mode
+//!
+//! Like output power, the protocol isn't exposed by iced, so this runs its own connection.
+//! Version 1 notifications respect idle inhibitors, so video players keep the session unlocked.
+
+use cosmic::iced::{futures::SinkExt, subscription, Subscription};
+use std::{any::TypeId, error::Error, time::Duration};
+use tokio::{sync::mpsc, task, time};
+use wayland_client::{
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::{wl_registry::WlRegistry, wl_seat::WlSeat},
+    Connection, Dispatch, Proxy, QueueHandle,
+};
+use wayland_protocols::ext::idle_notify::v1::client::{
+    ext_idle_notification_v1::{self, ExtIdleNotificationV1},
+    ext_idle_notifier_v1::ExtIdleNotifierV1,
+};
+
+struct State {
+    idle_tx: mpsc::UnboundedSender<bool>,
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        _state: &mut Self,
+        _registry: &WlRegistry,
+        _event: <WlRegistry as Proxy>::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlSeat, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _seat: &WlSeat,
+        _event: <WlSeat as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtIdleNotifierV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _notifier: &ExtIdleNotifierV1,
+        _event: <ExtIdleNotifierV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtIdleNotificationV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _notification: &ExtIdleNotificationV1,
+        event: ext_idle_notification_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_idle_notification_v1::Event::Idled => {
+                let _ = state.idle_tx.send(true);
+            }
+            ext_idle_notification_v1::Event::Resumed => {
+                let _ = state.idle_tx.send(false);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn watch_blocking(
+    timeout: Duration,
+    idle_tx: mpsc::UnboundedSender<bool>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, mut queue) = registry_queue_init::<State>(&conn)?;
+    let qh = queue.handle();
+    let notifier: ExtIdleNotifierV1 = globals.bind(&qh, 1..=1, ())?;
+    // Idle time is tracked per seat, the default seat is the one this session sits at
+    let seat: WlSeat = globals.bind(&qh, 1..=1, ())?;
+    let timeout_ms = timeout.as_millis().try_into().unwrap_or(u32::MAX);
+    let _notification = notifier.get_idle_notification(timeout_ms, &seat, &qh, ());
+    let mut state = State { idle_tx };
+    // Until the subscription stops listening
+    while !state.idle_tx.is_closed() {
+        queue.blocking_dispatch(&mut state)?;
+    }
+    Ok(())
+}
+
+/// True once the session has had no input for `timeout`, false when input resumes
+pub fn subscription(timeout: Duration) -> Subscription<bool> {
+    struct IdleNotifySubscription;
+
+    subscription::channel(
+        TypeId::of::<IdleNotifySubscription>(),
+        16,
+        move |mut msg_tx| async move {
+            let (idle_tx, mut idle_rx) = mpsc::unbounded_channel();
+            let watcher = task::spawn_blocking(move || {
+                if let Err(err) = watch_blocking(timeout, idle_tx) {
+                    log::warn!("not watching idle time: {}", err);
+                }
+            });
+            while let Some(idle) = idle_rx.recv().await {
+                if msg_tx.send(idle).await.is_err() {
+                    // The locker stopped listening
+                    break;
+                }
+            }
+            // The watcher ends with the next event after this
+            drop(idle_rx);
+            let _ = watcher.await;
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
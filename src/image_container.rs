@@ -2,7 +2,7 @@ use cosmic::iced::widget::{
     image::{draw, FilterMethod, Handle},
     Container,
 };
-use cosmic::iced::ContentFit;
+use cosmic::iced::{ContentFit, Vector};
 use cosmic::iced_core::event::{self, Event};
 use cosmic::iced_core::layout;
 use cosmic::iced_core::mouse;
@@ -22,6 +22,9 @@ where
     container: Container<'a, Message, Theme, Renderer>,
     image_opt: Option<Handle>,
     content_fit: ContentFit,
+    tile: bool,
+    behind_opt: Option<f32>,
+    motion_opt: Option<(f32, Vector)>,
 }
 
 impl<'a, Message, Renderer> ImageContainer<'a, Message, cosmic::Theme, Renderer>
@@ -34,6 +37,9 @@ where
             container,
             image_opt: None,
             content_fit: ContentFit::None,
+            tile: false,
+            behind_opt: None,
+            motion_opt: None,
         }
     }
 
@@ -46,6 +52,93 @@ where
         self.content_fit = content_fit;
         self
     }
+
+    /// Repeats the image at its own size instead of fitting it
+    pub fn tile(mut self, tile: bool) -> Self {
+        self.tile = tile;
+        self
+    }
+
+    /// Places the image over the whole surface like the wallpaper and only draws the part behind
+    /// the container, `scale` is the image's size to the wallpaper's
+    pub fn behind(mut self, scale: f32) -> Self {
+        self.behind_opt = Some(scale);
+        self
+    }
+
+    /// Draws the fitted image `zoom` times larger, moved by `pan` from -1.0 to 1.0 of the room
+    /// that leaves on each axis
+    pub fn motion(mut self, zoom: f32, pan: Vector) -> Self {
+        self.motion_opt = Some((zoom, pan));
+        self
+    }
+}
+
+// The image fitted to `area` and clipped to `bounds`, `scale` is its size to the one it is fitted
+// as
+fn draw_fitted<Renderer>(
+    renderer: &mut Renderer,
+    bounds: Rectangle,
+    area: Rectangle,
+    image: &Handle,
+    content_fit: ContentFit,
+    scale: f32,
+    motion_opt: Option<(f32, Vector)>,
+) where
+    Renderer: cosmic::iced_core::Renderer + cosmic::iced_core::image::Renderer<Handle = Handle>,
+{
+    let size = renderer.dimensions(image);
+    let image_size = Size::new(size.width as f32 / scale, size.height as f32 / scale);
+    let fitted = content_fit.fit(image_size, area.size());
+    let (zoom, pan) = motion_opt.unwrap_or((1.0, Vector::new(0.0, 0.0)));
+    let (width, height) = (fitted.width * zoom, fitted.height * zoom);
+    let rect = Rectangle {
+        x: area.center_x() - width / 2.0 + pan.x * (width - fitted.width) / 2.0,
+        y: area.center_y() - height / 2.0 + pan.y * (height - fitted.height) / 2.0,
+        width,
+        height,
+    };
+    renderer.with_layer(bounds, |renderer| {
+        renderer.draw(
+            image.clone(),
+            FilterMethod::Linear,
+            rect,
+            [0.0, 0.0, 0.0, 0.0],
+        );
+    });
+}
+
+// Copies of the image at its own size from the top left corner, clipped to the bounds
+fn draw_tiled<Renderer>(renderer: &mut Renderer, bounds: Rectangle, image: &Handle)
+where
+    Renderer: cosmic::iced_core::Renderer + cosmic::iced_core::image::Renderer<Handle = Handle>,
+{
+    let size = renderer.dimensions(image);
+    if size.width == 0 || size.height == 0 {
+        return;
+    }
+    let (width, height) = (size.width as f32, size.height as f32);
+    renderer.with_layer(bounds, |renderer| {
+        let mut y = bounds.y;
+        while y < bounds.y + bounds.height {
+            let mut x = bounds.x;
+            while x < bounds.x + bounds.width {
+                renderer.draw(
+                    image.clone(),
+                    FilterMethod::Linear,
+                    Rectangle {
+                        x,
+                        y,
+                        width,
+                        height,
+                    },
+                    [0.0, 0.0, 0.0, 0.0],
+                );
+                x += width;
+            }
+            y += height;
+        }
+    });
 }
 
 impl<'a, Message, Renderer> Widget<Message, cosmic::Theme, Renderer>
@@ -122,8 +215,28 @@ where
         cursor: mouse::Cursor,
         viewport: &Rectangle,
     ) {
-        match &self.image_opt {
-            Some(image) => draw(
+        match (&self.image_opt, self.behind_opt) {
+            // Like the wallpaper, which fills the surface
+            (Some(image), Some(scale)) => draw_fitted(
+                renderer,
+                layout.bounds(),
+                *viewport,
+                image,
+                self.content_fit,
+                scale,
+                self.motion_opt,
+            ),
+            (Some(image), None) if self.tile => draw_tiled(renderer, layout.bounds(), image),
+            (Some(image), None) if self.motion_opt.is_some() => draw_fitted(
+                renderer,
+                layout.bounds(),
+                layout.bounds(),
+                image,
+                self.content_fit,
+                1.0,
+                self.motion_opt,
+            ),
+            (Some(image), None) => draw(
                 renderer,
                 layout,
                 image,
@@ -131,7 +244,7 @@ where
                 FilterMethod::Linear,
                 [0.0, 0.0, 0.0, 0.0],
             ),
-            None => {}
+            (None, _) => {}
         }
 
         self.container.draw(
@@ -0,0 +1,138 @@
+//! Running timers from KDE Clock's kclockd, counting down on the lock screen
+//!
+//! GNOME Clocks and COSMIC keep their timers inside the app, kclockd is the only clock daemon
+//! that puts them on the session bus. It is not started for this, only a running one is asked.
+
+use cosmic::iced::{
+    futures::{channel::mpsc, SinkExt},
+    subscription, Subscription,
+};
+use std::{
+    any::TypeId,
+    time::{Duration, Instant},
+};
+use tokio::time;
+use zbus::{dbus_proxy, fdo::DBusProxy, names::BusName, Connection, Result};
+
+const SERVICE: &str = "org.kde.kclockd";
+// Often enough that a paused or restarted timer shows up quickly, between polls the countdown
+// runs from the last remaining time
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[dbus_proxy(
+    interface = "org.kde.kclock.TimerModel",
+    default_service = "org.kde.kclockd",
+    default_path = "/Timers"
+)]
+trait TimerModel {
+    // UUIDs without dashes, also the last part of each timer's path
+    #[dbus_proxy(name = "timers")]
+    fn timers(&self) -> Result<Vec<String>>;
+}
+
+#[dbus_proxy(
+    interface = "org.kde.kclock.Timer",
+    default_service = "org.kde.kclockd"
+)]
+trait Timer {
+    #[dbus_proxy(name = "elapsed")]
+    fn elapsed(&self) -> Result<i32>;
+
+    #[dbus_proxy(property, name = "label")]
+    fn label(&self) -> Result<String>;
+
+    // Seconds
+    #[dbus_proxy(property, name = "length")]
+    fn length(&self) -> Result<i32>;
+
+    #[dbus_proxy(property, name = "running")]
+    fn running(&self) -> Result<bool>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClockTimer {
+    pub label: String,
+    pub running: bool,
+    remaining: Duration,
+    fetched_at: Instant,
+}
+
+impl ClockTimer {
+    /// Counting down since it was fetched if running
+    pub fn remaining(&self) -> Duration {
+        if self.running {
+            self.remaining.saturating_sub(self.fetched_at.elapsed())
+        } else {
+            self.remaining
+        }
+    }
+}
+
+/// Timers that are running or paused partway, sent on every poll
+pub fn subscription() -> Subscription<Vec<ClockTimer>> {
+    struct TimersSubscription;
+
+    subscription::channel(
+        TypeId::of::<TimersSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            if let Err(err) = handler(&mut msg_tx).await {
+                log::info!("not watching clock timers: {}", err);
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+async fn handler(msg_tx: &mut mpsc::Sender<Vec<ClockTimer>>) -> Result<()> {
+    let zbus = Connection::session().await?;
+    let dbus = DBusProxy::new(&zbus).await?;
+    // No timers again wakes nothing up
+    let mut was_empty = false;
+    loop {
+        let timers = if dbus.name_has_owner(BusName::try_from(SERVICE)?).await? {
+            timers(&zbus).await.unwrap_or_else(|err| {
+                log::debug!("failed to read clock timers: {}", err);
+                Vec::new()
+            })
+        } else {
+            Vec::new()
+        };
+        if !(timers.is_empty() && was_empty) {
+            was_empty = timers.is_empty();
+            if msg_tx.send(timers).await.is_err() {
+                // The locker stopped listening
+                return Ok(());
+            }
+        }
+        time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn timers(zbus: &Connection) -> Result<Vec<ClockTimer>> {
+    let model = TimerModelProxy::new(zbus).await?;
+    let mut timers = Vec::new();
+    for uuid in model.timers().await? {
+        let timer = TimerProxy::builder(zbus)
+            .path(format!("/Timers/{}", uuid))?
+            .build()
+            .await?;
+        let length = timer.length().await?;
+        let elapsed = timer.elapsed().await?;
+        let running = timer.running().await?;
+        // Reset timers sit at their full length, they are not worth showing
+        if !running && elapsed == 0 {
+            continue;
+        }
+        timers.push(ClockTimer {
+            label: timer.label().await?,
+            running,
+            remaining: Duration::from_secs(length.saturating_sub(elapsed).max(0) as u64),
+            fetched_at: Instant::now(),
+        });
+    }
+    Ok(timers)
+}
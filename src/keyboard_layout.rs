@@ -0,0 +1,249 @@
+use cosmic::cosmic_config::{self, ConfigGet, ConfigSet};
+use serde::{Deserialize, Serialize};
+use std::{env, fs, path::PathBuf};
+
+const COMP_CONFIG_ID: &str = "com.system76.CosmicComp";
+const COMP_CONFIG_VERSION: u64 = 1;
+// The desktop's xkb_config while a lock has it switched, restored by the next start when the
+// locker didn't get to unlock
+const SAVED_FILE_NAME: &str = "zero-lock/xkb_config.json";
+
+// Mirrors cosmic-comp's xkb_config entry field for field, it is written back whole so the
+// repeat settings have cosmic-comp's defaults when missing, not zero
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+struct XkbConfig {
+    rules: String,
+    model: String,
+    layout: String,
+    variant: String,
+    options: Option<String>,
+    repeat_delay: u32,
+    repeat_rate: u32,
+}
+
+impl Default for XkbConfig {
+    fn default() -> Self {
+        Self {
+            rules: String::new(),
+            model: String::new(),
+            layout: String::new(),
+            variant: String::new(),
+            options: None,
+            repeat_delay: 600,
+            repeat_rate: 25,
+        }
+    }
+}
+
+/// The configured layouts, and which one is active as far as zero-lock knows
+///
+/// Clients can't switch the compositor's XKB group, and iced doesn't pass on the group of
+/// wl_keyboard's modifiers, so switching reorders xkb_config, which the compositor answers by
+/// activating the first layout. The desktop's order is written back on unlock, or on the next
+/// start after a crash or kill. Until the first switch the active group is unknown and every
+/// layout is named.
+#[derive(Clone, Debug, Default)]
+pub struct KeyboardLayouts {
+    // As the desktop has it, to restore on unlock
+    original_opt: Option<XkbConfig>,
+    config_opt: Option<XkbConfig>,
+    layouts: Vec<(String, String)>,
+    // Into layouts, known after a switch or with a single layout
+    active_opt: Option<usize>,
+    // The original is in the state file
+    saved: bool,
+}
+
+fn layouts(layout: &str, variant: &str) -> Vec<(String, String)> {
+    let mut variants = variant.split(',');
+    layout
+        .split(',')
+        .map(|layout| layout.trim())
+        .filter(|layout| !layout.is_empty())
+        .map(|layout| {
+            let variant = variants.next().unwrap_or_default().trim();
+            (layout.to_string(), variant.to_string())
+        })
+        .collect()
+}
+
+// "US" or "DE (neo)"
+fn label((layout, variant): &(String, String)) -> String {
+    if variant.is_empty() {
+        layout.to_uppercase()
+    } else {
+        format!("{} ({})", layout.to_uppercase(), variant)
+    }
+}
+
+fn save(config: &XkbConfig) -> Result<(), cosmic_config::Error> {
+    cosmic_config::Config::new(COMP_CONFIG_ID, COMP_CONFIG_VERSION)
+        .and_then(|comp_config| comp_config.set("xkb_config", config.clone()))
+}
+
+fn saved_path() -> Option<PathBuf> {
+    let state_home = match env::var_os("XDG_STATE_HOME") {
+        Some(state_home) => PathBuf::from(state_home),
+        None => PathBuf::from(env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(state_home.join(SAVED_FILE_NAME))
+}
+
+// Before the first switch, one that couldn't be undone after a crash isn't made
+fn save_original(config: &XkbConfig) -> Result<(), String> {
+    let path = saved_path().ok_or("no state directory")?;
+    let data = serde_json::to_string(config).map_err(|err| err.to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+    }
+    fs::write(&path, data).map_err(|err| err.to_string())
+}
+
+fn remove_original() {
+    let Some(path) = saved_path() else {
+        return;
+    };
+    if let Err(err) = fs::remove_file(&path) {
+        if err.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("failed to remove {:?}: {}", path, err);
+        }
+    }
+}
+
+// Left behind by a locker that switched and didn't get to unlock
+fn restore_left_behind() -> Option<XkbConfig> {
+    let data = fs::read_to_string(saved_path()?).ok()?;
+    let original = match serde_json::from_str::<XkbConfig>(&data) {
+        Ok(original) => original,
+        Err(err) => {
+            log::warn!("failed to parse saved xkb_config: {}", err);
+            remove_original();
+            return None;
+        }
+    };
+    match save(&original) {
+        Ok(()) => {
+            log::info!("restored the desktop's keyboard layouts after an unclean exit");
+            remove_original();
+            Some(original)
+        }
+        Err(err) => {
+            log::warn!("failed to restore keyboard layouts: {:?}", err);
+            None
+        }
+    }
+}
+
+impl KeyboardLayouts {
+    pub fn load() -> Self {
+        let config_opt = restore_left_behind().or_else(|| {
+            cosmic_config::Config::new(COMP_CONFIG_ID, COMP_CONFIG_VERSION)
+                .and_then(|config| config.get::<XkbConfig>("xkb_config"))
+                .map_err(|err| log::info!("failed to read xkb_config: {:?}", err))
+                .ok()
+        });
+
+        let layouts = match &config_opt {
+            Some(config) => layouts(&config.layout, &config.variant),
+            None => layouts(
+                &env::var("XKB_DEFAULT_LAYOUT").unwrap_or_default(),
+                &env::var("XKB_DEFAULT_VARIANT").unwrap_or_default(),
+            ),
+        };
+        let active_opt = (layouts.len() == 1).then_some(0);
+
+        Self {
+            original_opt: config_opt.clone(),
+            config_opt,
+            layouts,
+            active_opt,
+            saved: false,
+        }
+    }
+
+    /// Short label for the active layout, or all of them while it isn't known
+    pub fn active(&self) -> Option<String> {
+        match self.active_opt {
+            Some(active) => self.layouts.get(active).map(label),
+            None if self.layouts.is_empty() => None,
+            None => Some(
+                self.layouts
+                    .iter()
+                    .map(label)
+                    .collect::<Vec<_>>()
+                    .join(" / "),
+            ),
+        }
+    }
+
+    pub fn can_cycle(&self) -> bool {
+        self.config_opt.is_some() && self.layouts.len() > 1
+    }
+
+    /// Moves the next layout to the front, the compositor activates the first layout when
+    /// xkb_config changes. Only the layouts change, the rest of the entry is written as read
+    pub fn cycle(&mut self) {
+        if !self.can_cycle() {
+            return;
+        }
+        if let (Some(original), false) = (&self.original_opt, self.saved) {
+            if let Err(err) = save_original(original) {
+                log::warn!("not switching keyboard layout, failed to save it: {}", err);
+                return;
+            }
+            self.saved = true;
+        }
+        // The first switch from an unknown group lands on the second layout, like a toggle
+        self.layouts.rotate_left(1);
+        self.active_opt = Some(0);
+
+        let Some(config) = &mut self.config_opt else {
+            return;
+        };
+        config.layout = self
+            .layouts
+            .iter()
+            .map(|(layout, _)| layout.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        config.variant = self
+            .layouts
+            .iter()
+            .map(|(_, variant)| variant.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        match save(config) {
+            Ok(()) => log::info!("switched keyboard layout to {:?}", self.active()),
+            Err(err) => log::warn!("failed to switch keyboard layout: {:?}", err),
+        }
+    }
+
+    /// Writes the desktop's layout order back if it was switched while locked
+    pub fn restore(&mut self) {
+        let (Some(original), Some(config)) = (&self.original_opt, &self.config_opt) else {
+            return;
+        };
+        // Cycled back to the desktop's order
+        if original == config {
+            if self.saved {
+                remove_original();
+                self.saved = false;
+            }
+            return;
+        }
+        match save(original) {
+            Ok(()) => {
+                log::info!("restored the desktop's keyboard layouts");
+                remove_original();
+                self.saved = false;
+            }
+            // Left for the next start to try again
+            Err(err) => log::warn!("failed to restore keyboard layouts: {:?}", err),
+        }
+        self.config_opt = Some(original.clone());
+        self.layouts = layouts(&original.layout, &original.variant);
+        self.active_opt = (self.layouts.len() == 1).then_some(0);
+    }
+}
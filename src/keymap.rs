@@ -0,0 +1,173 @@
+//! Shortcuts on the lock surface, the defaults with `key_bindings` from the config on top
+//!
+//! Bindings are written like "Ctrl+U", "Super+Space" or "F1", modifiers first, and the key is a
+//! character or one of the names in `NAMED_KEYS`.
+
+use cosmic::iced::keyboard::{key::Named, Key, Modifiers};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyAction {
+    ClearInput,
+    PowerMenu,
+    CycleLayout,
+    ToggleSeconds,
+    ToggleLargeText,
+    ToggleKeyboard,
+    BrightnessUp,
+    BrightnessDown,
+    VolumeUp,
+    VolumeDown,
+    Mute,
+    PlayPause,
+    NextTrack,
+    PreviousTrack,
+    // Unbinds a default
+    None,
+}
+
+const DEFAULT_BINDINGS: [(&str, KeyAction); 17] = [
+    ("Escape", KeyAction::ClearInput),
+    ("Ctrl+U", KeyAction::ClearInput),
+    ("F1", KeyAction::PowerMenu),
+    // Same shortcut as the COSMIC default for switching layouts
+    ("Super+Space", KeyAction::CycleLayout),
+    // Zooming in, where plus is shifted or not depending on the layout
+    ("Ctrl++", KeyAction::ToggleLargeText),
+    ("Ctrl+Shift++", KeyAction::ToggleLargeText),
+    ("Ctrl+=", KeyAction::ToggleLargeText),
+    ("BrightnessUp", KeyAction::BrightnessUp),
+    ("BrightnessDown", KeyAction::BrightnessDown),
+    ("AudioVolumeUp", KeyAction::VolumeUp),
+    ("AudioVolumeDown", KeyAction::VolumeDown),
+    ("AudioVolumeMute", KeyAction::Mute),
+    ("MediaPlayPause", KeyAction::PlayPause),
+    ("MediaPlay", KeyAction::PlayPause),
+    ("MediaPause", KeyAction::PlayPause),
+    ("MediaTrackNext", KeyAction::NextTrack),
+    ("MediaTrackPrevious", KeyAction::PreviousTrack),
+];
+
+const NAMED_KEYS: [(&str, Named); 29] = [
+    ("Escape", Named::Escape),
+    ("Tab", Named::Tab),
+    ("Space", Named::Space),
+    ("Insert", Named::Insert),
+    ("Delete", Named::Delete),
+    ("Home", Named::Home),
+    ("End", Named::End),
+    ("F1", Named::F1),
+    ("F2", Named::F2),
+    ("F3", Named::F3),
+    ("F4", Named::F4),
+    ("F5", Named::F5),
+    ("F6", Named::F6),
+    ("F7", Named::F7),
+    ("F8", Named::F8),
+    ("F9", Named::F9),
+    ("F10", Named::F10),
+    ("F11", Named::F11),
+    ("F12", Named::F12),
+    ("BrightnessUp", Named::BrightnessUp),
+    ("BrightnessDown", Named::BrightnessDown),
+    ("AudioVolumeUp", Named::AudioVolumeUp),
+    ("AudioVolumeDown", Named::AudioVolumeDown),
+    ("AudioVolumeMute", Named::AudioVolumeMute),
+    ("MediaPlayPause", Named::MediaPlayPause),
+    ("MediaPlay", Named::MediaPlay),
+    ("MediaPause", Named::MediaPause),
+    ("MediaTrackNext", Named::MediaTrackNext),
+    ("MediaTrackPrevious", Named::MediaTrackPrevious),
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum BindingKey {
+    Named(Named),
+    // Lowercase, shift is matched as a modifier instead
+    Character(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Binding {
+    key: BindingKey,
+    modifiers: Modifiers,
+}
+
+impl Binding {
+    fn parse(binding: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = binding.split('+').map(str::trim).collect();
+        // "Ctrl++" binds the plus key
+        if binding.ends_with("++") {
+            parts.truncate(parts.len() - 2);
+            parts.push("+");
+        }
+        let key = parts.pop()?;
+        let mut modifiers = Modifiers::empty();
+        for modifier in parts {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => Modifiers::CTRL,
+                "alt" => Modifiers::ALT,
+                "shift" => Modifiers::SHIFT,
+                "super" | "logo" => Modifiers::LOGO,
+                _ => return None,
+            };
+        }
+        let key = match NAMED_KEYS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+        {
+            Some((_, named)) => BindingKey::Named(*named),
+            None if key.chars().count() == 1 => BindingKey::Character(key.to_lowercase()),
+            None => return None,
+        };
+        Some(Self { key, modifiers })
+    }
+
+    fn matches(&self, key: &Key, modifiers: Modifiers) -> bool {
+        let key_matches = match (&self.key, key) {
+            (BindingKey::Named(named), Key::Named(pressed)) => named == pressed,
+            (BindingKey::Character(c), Key::Character(pressed)) => c == &pressed.to_lowercase(),
+            _ => false,
+        };
+        key_matches && self.modifiers == modifiers
+    }
+}
+
+/// Whether the config can bind `binding`
+pub fn is_valid(binding: &str) -> bool {
+    Binding::parse(binding).is_some()
+}
+
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    bindings: Vec<(Binding, KeyAction)>,
+}
+
+impl Keymap {
+    /// The defaults with `key_bindings` replacing them, invalid bindings were dropped with the
+    /// config's errors
+    pub fn new(key_bindings: &HashMap<String, KeyAction>) -> Self {
+        let mut bindings: Vec<(Binding, KeyAction)> = Vec::new();
+        let configured = key_bindings
+            .iter()
+            .map(|(binding, action)| (binding.as_str(), *action));
+        for (binding, action) in DEFAULT_BINDINGS.into_iter().chain(configured) {
+            let Some(binding) = Binding::parse(binding) else {
+                continue;
+            };
+            bindings.retain(|(bound, _)| bound != &binding);
+            bindings.push((binding, action));
+        }
+        Self { bindings }
+    }
+
+    pub fn action(&self, key: &Key, modifiers: Modifiers) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .find(|(binding, _)| binding.matches(key, modifiers))
+            .map(|(_, action)| *action)
+            .filter(|action| *action != KeyAction::None)
+    }
+}
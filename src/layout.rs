@@ -0,0 +1,235 @@
+use cosmic::{
+    iced::{
+        alignment::{Alignment, Horizontal, Vertical},
+        Length,
+    },
+    widget, Element,
+};
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WidgetKind {
+    Clock,
+    AwayFor,
+    // Wrong passwords and fingers since locking
+    FailedAttempts,
+    // Running timers of the desktop clock app
+    Timers,
+    Message,
+    Status,
+    Notifications,
+    Unread,
+    Media,
+    Brightness,
+    Power,
+    Plugins,
+    // Host, kernel, uptime and pending reboots, not in the default layout
+    SystemInfo,
+    Prompt,
+    Keyboard,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    // Row and column in the 3x3 grid
+    fn cell(self) -> (usize, usize) {
+        match self {
+            Self::TopLeft => (0, 0),
+            Self::Top => (0, 1),
+            Self::TopRight => (0, 2),
+            Self::Left => (1, 0),
+            Self::Center => (1, 1),
+            Self::Right => (1, 2),
+            Self::BottomLeft => (2, 0),
+            Self::Bottom => (2, 1),
+            Self::BottomRight => (2, 2),
+        }
+    }
+}
+
+// `"clock"`, `{ row = [...] }` or `{ column = [...] }` in the config
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Node {
+    Row(Vec<Node>),
+    Column(Vec<Node>),
+    #[serde(untagged)]
+    Widget(WidgetKind),
+}
+
+impl Node {
+    /// Builds the node, returning None when every widget in it is empty
+    pub fn view<'a, M: 'a>(
+        &self,
+        widget_fn: &dyn Fn(WidgetKind) -> Option<Element<'a, M>>,
+    ) -> Option<Element<'a, M>> {
+        match self {
+            Self::Widget(kind) => widget_fn(*kind),
+            Self::Row(nodes) => {
+                let children: Vec<_> = nodes
+                    .iter()
+                    .filter_map(|node| node.view(widget_fn))
+                    .collect();
+                if children.is_empty() {
+                    return None;
+                }
+                Some(
+                    widget::row::with_children(children)
+                        .spacing(12)
+                        .align_items(Alignment::Center)
+                        .into(),
+                )
+            }
+            Self::Column(nodes) => {
+                let children: Vec<_> = nodes
+                    .iter()
+                    .filter_map(|node| node.view(widget_fn))
+                    .collect();
+                if children.is_empty() {
+                    return None;
+                }
+                Some(
+                    widget::column::with_children(children)
+                        .spacing(8)
+                        .align_items(Alignment::Center)
+                        .into(),
+                )
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Placement {
+    pub anchor: Anchor,
+    pub node: Node,
+}
+
+// A list of `[[layout]]` tables in the config
+#[derive(Clone, Debug, Deserialize)]
+#[serde(transparent)]
+pub struct Layout {
+    pub placements: Vec<Placement>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            placements: vec![
+                Placement {
+                    anchor: Anchor::Top,
+                    node: Node::Column(vec![
+                        Node::Widget(WidgetKind::Clock),
+                        Node::Widget(WidgetKind::AwayFor),
+                        Node::Widget(WidgetKind::FailedAttempts),
+                        Node::Widget(WidgetKind::Timers),
+                        Node::Widget(WidgetKind::Message),
+                        Node::Widget(WidgetKind::Status),
+                        Node::Widget(WidgetKind::Plugins),
+                        Node::Widget(WidgetKind::Notifications),
+                        Node::Widget(WidgetKind::Unread),
+                        Node::Widget(WidgetKind::Media),
+                        Node::Widget(WidgetKind::Brightness),
+                        Node::Widget(WidgetKind::Power),
+                    ]),
+                },
+                Placement {
+                    anchor: Anchor::Center,
+                    node: Node::Column(vec![
+                        Node::Widget(WidgetKind::Prompt),
+                        Node::Widget(WidgetKind::Keyboard),
+                    ]),
+                },
+            ],
+        }
+    }
+}
+
+impl Layout {
+    /// Portrait outputs are too narrow for three columns, so the cells of each row are stacked
+    pub fn view<'a, M: 'a>(
+        &self,
+        widget_fn: &dyn Fn(WidgetKind) -> Option<Element<'a, M>>,
+        portrait: bool,
+    ) -> Element<'a, M> {
+        // iced has no stack widget, so anchors are cells of a 3x3 grid
+        let mut cells: [[Vec<Element<'a, M>>; 3]; 3] = Default::default();
+        for placement in self.placements.iter() {
+            if let Some(element) = placement.node.view(widget_fn) {
+                let (row, col) = placement.anchor.cell();
+                cells[row][col].push(element);
+            }
+        }
+
+        let mut rows = Vec::with_capacity(3);
+        for (row, row_cells) in cells.into_iter().enumerate() {
+            let (vertical, height) = match row {
+                0 => (Vertical::Top, Length::Shrink),
+                1 => (Vertical::Center, Length::Fill),
+                _ => (Vertical::Bottom, Length::Shrink),
+            };
+
+            // A single occupied cell gets the whole width so wide rows are not squeezed
+            let occupied = row_cells.iter().filter(|cell| !cell.is_empty()).count();
+            let mut children = Vec::with_capacity(3);
+            for (col, elements) in row_cells.into_iter().enumerate() {
+                if elements.is_empty() && (occupied <= 1 || portrait) {
+                    continue;
+                }
+                let (horizontal, alignment) = match col {
+                    0 => (Horizontal::Left, Alignment::Start),
+                    1 => (Horizontal::Center, Alignment::Center),
+                    _ => (Horizontal::Right, Alignment::End),
+                };
+                children.push(
+                    widget::container(
+                        widget::column::with_children(elements)
+                            .spacing(8)
+                            .align_items(alignment),
+                    )
+                    .padding(10)
+                    .width(Length::Fill)
+                    .height(height)
+                    .align_x(horizontal)
+                    .align_y(vertical)
+                    .into(),
+                );
+            }
+            if portrait {
+                rows.push(
+                    widget::column::with_children(children)
+                        .width(Length::Fill)
+                        .height(height)
+                        .into(),
+                );
+            } else {
+                rows.push(
+                    widget::row::with_children(children)
+                        .width(Length::Fill)
+                        .height(height)
+                        .into(),
+                );
+            }
+        }
+
+        widget::column::with_children(rows)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+}
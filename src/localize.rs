@@ -0,0 +1,44 @@
+//! Translations of the lock screen's own text, from the Fluent files in i18n/
+//!
+//! PAM prompts and errors are translated by PAM, and names of apps, players and networks are
+//! shown as they are.
+
+use i18n_embed::{
+    fluent::{fluent_language_loader, FluentLanguageLoader},
+    DefaultLocalizer, DesktopLanguageRequester, LanguageLoader, Localizer,
+};
+use once_cell::sync::Lazy;
+use rust_embed::RustEmbed;
+
+#[derive(RustEmbed)]
+#[folder = "i18n/"]
+struct Localizations;
+
+pub static LANGUAGE_LOADER: Lazy<FluentLanguageLoader> = Lazy::new(|| {
+    let loader: FluentLanguageLoader = fluent_language_loader!();
+    // Embedded, so only a broken build fails here
+    loader
+        .load_fallback_language(&Localizations)
+        .expect("failed to load the fallback language");
+    loader
+});
+
+#[macro_export]
+macro_rules! fl {
+    ($message_id:literal) => {{
+        i18n_embed_fl::fl!($crate::localize::LANGUAGE_LOADER, $message_id)
+    }};
+
+    ($message_id:literal, $($args:expr),*) => {{
+        i18n_embed_fl::fl!($crate::localize::LANGUAGE_LOADER, $message_id, $($args), *)
+    }};
+}
+
+/// Picks the language from LANGUAGE, LC_ALL, LC_MESSAGES and LANG, English if none is translated
+pub fn init() {
+    let localizer = DefaultLocalizer::new(&*LANGUAGE_LOADER, &Localizations);
+    let requested_languages = DesktopLanguageRequester::requested_languages();
+    if let Err(err) = localizer.select(&requested_languages) {
+        log::warn!("failed to load translations: {}", err);
+    }
+}
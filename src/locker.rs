@@ -1,64 +1,1227 @@
 use chrono::{DateTime, Local};
 use cosmic::{
-    font::FONT_BOLD,
     iced::{
-        self, alignment,
+        self,
         event::{
             self,
             wayland::{Event as WaylandEvent, OutputEvent, SessionLockEvent},
         },
-        subscription,
-        wayland::session_lock::{destroy_lock_surface, get_lock_surface, lock, unlock},
-        Length, Subscription,
+        keyboard::{key::Named, Key, Modifiers},
+        mouse, touch,
+        wayland::{
+            actions::layer_surface::{IcedOutput, SctkLayerSurfaceSettings},
+            layer_surface::{
+                destroy_layer_surface, get_layer_surface, Anchor, KeyboardInteractivity, Layer,
+            },
+            session_lock::{destroy_lock_surface, get_lock_surface, lock, unlock},
+        },
+        widget::image,
+        Subscription,
     },
     iced_widget::text,
     widget::Widget,
 };
-use std::{collections::HashMap, error::Error, process, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    path::{Path, PathBuf},
+    process,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use cosmic::{
     app::{message, Command, Core, Settings},
     executor::{self, multi::Executor},
     iced_runtime::core::window::Id as SurfaceId,
-    style, widget, Element,
+    widget, Application, Element,
 };
 
+use cosmic::cctk::sctk::output::OutputInfo;
+use tokio::sync::{mpsc, watch};
 use wayland_client::{protocol::wl_output::WlOutput, Proxy};
+use zbus::zvariant::OwnedFd;
+
+use crate::{
+    accounts::{self, UserInfo},
+    alarms::{self, Alarm},
+    ambient_light,
+    animation::{self, Timeline},
+    auth::{self, AuthEvent, Unlockers},
+    background_dir,
+    backlight::{Backlight, KeyboardBacklight},
+    bluetooth::{self, BluetoothInfo},
+    clipboard,
+    config::{
+        self, BackgroundScaling, Config, LockOnLid, NotificationPrivacy, OutputConfig,
+        OutputContent, OutputMode, TypingFeedback, UnreadSource,
+    },
+    control::{self, ControlEvent},
+    cover::Cover,
+    crash_report,
+    cursor_area::CursorArea,
+    desktop_theme,
+    dimmer::{self, Dimmer},
+    display_manager,
+    fprintd::{self, FingerprintEvent},
+    frosted::{self, Frosted},
+    gamepad::{self, GamepadEvent},
+    gesture::{self, Contact, Gesture, Gestures},
+    grace::{self, GracePeriod},
+    greetd, hooks, idle_notify,
+    kclock::{self, ClockTimer},
+    keyboard_layout::KeyboardLayouts,
+    keymap::{KeyAction, Keymap},
+    layout::WidgetKind,
+    logind::{self, PowerAction},
+    motion::{self, Motion},
+    mpris::{self, MediaAction, MediaInfo},
+    networkmanager::{self, VpnState},
+    notifications::{self, Notification, NotificationEvent},
+    osk::{OskKey, VirtualKeyboard},
+    output, output_power,
+    packagekit::{self, UpdatesInfo},
+    panic_hook,
+    pipewire::{self, CaptureInfo, VolumeAction},
+    plugin::{self, Plugin, PluginEvent},
+    power_profiles::{self, PowerProfileInfo},
+    power_supply, render,
+    screensaver::{self, ScreenSaverEvent},
+    sessions::{self, Session},
+    signals::{self, SignalEvent},
+    stats::Stats,
+    swaylock,
+    system_info::SystemInfo,
+    systemd, timer,
+    typing::TypingIndicator,
+    unread::{self, Unread},
+    upower::{self, Peripheral, UpsInfo},
+    wallpaper::{self, Wallpaper, Wallpapers},
+    Action, Args,
+};
+
+mod view;
+
+// Covers are small, but a long playlist shouldn't grow the cache forever
+const ART_CACHE_SIZE: usize = 16;
+const NOTIFICATION_PREVIEWS: usize = 32;
+const BRIGHTNESS_STEP: f32 = 5.0;
+// Retry backoff after the compositor ends the lock, doubling up to the maximum
+const RELOCK_DELAY: Duration = Duration::from_millis(100);
+const RELOCK_DELAY_MAX: Duration = Duration::from_secs(10);
+// How long the event loop gets to wind down after unlocking
+const EXIT_GRACE: Duration = Duration::from_secs(2);
+// A second SIGTERM this soon after a refused one terminates a locked locker
+const FORCE_TERMINATE: Duration = Duration::from_secs(5);
+// More outputs than this are mirrored unless configured, composing each one costs more
+const INDEPENDENT_OUTPUTS_MAX: usize = 2;
+// Part of the display off timeout spent fading out
+const DIM_DURATION: Duration = Duration::from_secs(5);
+// Countdown shown before suspending
+const SUSPEND_WARNING: Duration = Duration::from_secs(30);
+// Lock surfaces fade in from black, the compositor doesn't show the desktop behind them
+const LOCK_FADE_DURATION: Duration = Duration::from_millis(400);
+// Fading out after authenticating, so the desktop doesn't flash in
+const UNLOCK_FADE_DURATION: Duration = Duration::from_millis(250);
+// Time for the compositor to give a lock surface keyboard focus
+const GRAB_TIMEOUT: Duration = Duration::from_secs(3);
+// From the last lock surface being configured to its first frame being on screen
+const SLEEP_DRAW_GRACE: Duration = Duration::from_millis(100);
+
+fn clear_clipboard() -> Command<Message> {
+    Command::perform(clipboard::clear(), |res| {
+        match res {
+            Ok(()) => log::info!("cleared the clipboard"),
+            Err(err) => log::warn!("failed to clear the clipboard: {}", err),
+        }
+        message::app(Message::None)
+    })
+}
+
+async fn resume_media(paused_players: Vec<String>) {
+    if paused_players.is_empty() {
+        return;
+    }
+    if let Err(err) = mpris::resume(paused_players).await {
+        log::warn!("failed to resume media players: {}", err);
+    }
+}
+
+fn hook(name: &'static str, command_opt: &Option<String>) -> Command<Message> {
+    match command_opt.clone() {
+        Some(command) => {
+            Command::perform(hooks::run(name, command), |()| message::app(Message::None))
+        }
+        None => Command::none(),
+    }
+}
+
+// High contrast replaces the desktop theme
+fn theme(high_contrast: bool) -> cosmic::Theme {
+    if high_contrast {
+        cosmic::Theme::dark_hc()
+    } else {
+        desktop_theme::load()
+    }
+}
+
+// The configured image, picked at random for a "dir:" background
+fn background(config: &Config) -> Option<PathBuf> {
+    let path = config.background_opt.as_deref()?;
+    match background_dir::directory(path) {
+        Some(dir) => {
+            background_dir::pick(&dir, &config.background_exclude, config.background_history)
+        }
+        None => Some(path.to_path_buf()),
+    }
+}
+
+// A configured background wins over the desktop's
+fn wallpapers(config: &Config, background_opt: Option<&Path>) -> Wallpapers {
+    let color_opt = config.background_color_opt.map(|color| color.0);
+    match (background_opt, color_opt) {
+        (Some(path), _) => Wallpapers::all(Wallpaper::Image {
+            handle: image::Handle::from_path(path),
+            content_fit: config.background_scaling.content_fit(),
+            tile: config.background_scaling == BackgroundScaling::Tile,
+            color_opt,
+        }),
+        (None, Some(color)) => Wallpapers::all(Wallpaper::Color(color)),
+        (None, None) if config.desktop_wallpaper => wallpaper::load(),
+        (None, None) => Wallpapers::default(),
+    }
+}
+
+// Where the config comes from, reapplied when the file changes
+#[derive(Clone)]
+struct ConfigSource {
+    path_opt: Option<PathBuf>,
+    // From --profile, or the last lock request that named one
+    profile_opt: Option<String>,
+    // Command line options win over the file
+    image_opt: Option<PathBuf>,
+    grace_opt: Option<u64>,
+    // Named like in swaylock's config, "color" and its value
+    swaylock_options: Vec<(&'static str, Option<String>)>,
+    preview: bool,
+}
+
+impl ConfigSource {
+    fn load(&self) -> (Config, Option<String>) {
+        let (mut config, error_opt) =
+            Config::load(self.path_opt.as_deref(), self.profile_opt.as_deref());
+        config.apply_env();
+        // An enforced grace period is a policy the command line doesn't get around either
+        if let Some(image) = &self.image_opt {
+            if !config.enforced.contains_key("background") {
+                config.background_opt = Some(image.clone());
+            }
+        }
+        if let Some(grace) = self.grace_opt {
+            if !config.enforced.contains_key("grace_period") {
+                config.grace_period = grace;
+            }
+        }
+        for (name, value_opt) in self.swaylock_options.iter() {
+            swaylock::apply(&mut config, name, value_opt.as_deref());
+        }
+        if self.preview {
+            // Dims and blanks the window, but never powers off or suspends the machine it runs on
+            config.suspend_timeout_opt = None;
+        }
+        (config, error_opt)
+    }
+}
 
-pub fn main() -> Result<(), Box<dyn Error>> {
-    let flags = Flags {};
-    let settings = Settings::default().no_main_window(true);
+pub fn main(current_user: pwd::Passwd, args: Args, greeter: bool) -> Result<(), Box<dyn Error>> {
+    let (render_opt, size) = match args.action_opt {
+        Some(Action::Render {
+            output,
+            size: (width, height),
+        }) => (Some(output), iced::Size::new(width as f32, height as f32)),
+        _ => (None, iced::Size::new(1280.0, 720.0)),
+    };
+    let preview = args.preview || render_opt.is_some();
+    let mut swaylock_options = Vec::new();
+    if args.color_opt.is_some() {
+        swaylock_options.push(("color", args.color_opt));
+    }
+    if args.scaling_opt.is_some() {
+        swaylock_options.push(("scaling", args.scaling_opt));
+    }
+    if args.no_unlock_indicator {
+        swaylock_options.push(("no-unlock-indicator", None));
+    }
+    let config_source = ConfigSource {
+        path_opt: args.config_opt,
+        profile_opt: args.profile_opt,
+        image_opt: args.image_opt,
+        grace_opt: args.grace_opt,
+        swaylock_options,
+        preview,
+    };
+    let (config, config_error_opt) = config_source.load();
+    // Otherwise GPU errors fall back on their own, and panics are handled by the hook
+    if config.software_rendering {
+        panic_hook::software_rendering();
+    }
+    let flags = Flags {
+        current_user,
+        config_source,
+        config,
+        config_error_opt,
+        message_opt: args.message_opt,
+        preview,
+        render_opt,
+        daemon: args.daemon && !greeter,
+        lock_now: args.lock_now,
+        greeter,
+    };
+    let settings = if preview {
+        Settings::default().size(size)
+    } else {
+        Settings::default().no_main_window(true)
+    };
     cosmic::app::run::<App>(settings, flags)?;
 
     Ok(())
 }
 
+#[derive(Clone, Copy, Debug)]
+enum Activity {
+    Active,
+    // No input for a while, background work and animations are paused
+    Idle,
+    // Fading to black before the displays are turned off
+    Dimming(Timeline),
+    // Idle with the displays powered off
+    DisplayOff,
+}
+
 #[derive(Clone, Debug)]
 enum State {
     Locking,
-    Locked,
-    Unlocking,
+    Locked(Activity),
+    // Authenticated, fading out until the lock is released
+    Unlocking(Option<Timeline>),
     Unlocked,
 }
 
+struct Prompt {
+    text: String,
+    secret: bool,
+    value: String,
+    // Counted from 1 in each conversation, later ones ask for an OTP or token after the password
+    step: usize,
+}
+
 pub struct App {
     core: Core,
     flags: Flags,
     now: DateTime<Local>,
     surface_ids: HashMap<WlOutput, SurfaceId>,
+    output_infos: HashMap<SurfaceId, OutputInfo>,
+    // Last configured size of each lock surface, the toolkit acknowledges configures and
+    // redraws, this is what the layout is decided from
+    surface_sizes: HashMap<SurfaceId, (u32, u32)>,
     state: State,
+    vpn_opt: Option<VpnState>,
+    bluetooth_opt: Option<BluetoothInfo>,
+    // Apps still recording, shown in red
+    capture: CaptureInfo,
+    keyboard_layouts: KeyboardLayouts,
+    media_opt: Option<MediaInfo>,
+    art_cache: HashMap<String, Option<image::Handle>>,
+    secret_image_opt: Option<image::Handle>,
+    // Resolved from the config, so a config change doesn't pick another one from a dir
+    background_opt: Option<PathBuf>,
+    wallpapers: Wallpapers,
+    // Blurred wallpapers for frosted_card, keyed by the id of the image they were made from
+    frosted: HashMap<u64, Frosted>,
+    motion: Motion,
+    notification_count: usize,
+    notifications: Vec<Notification>,
+    do_not_disturb_opt: Option<bool>,
+    power_menu: bool,
+    // Opened by a long press, with buttons sized for fingers
+    power_menu_touch: bool,
+    power_confirm_opt: Option<PowerAction>,
+    backlight_opt: Option<Backlight>,
+    brightness: f32,
+    // Turned off with the displays, and the brightness to restore
+    keyboard_backlight_opt: Option<(KeyboardBacklight, u32)>,
+    power_profile_opt: Option<PowerProfileInfo>,
+    // Read again on every lock, the next one is picked when drawing
+    alarms: Vec<Alarm>,
+    clock_timers: Vec<ClockTimer>,
+    system_info_opt: Option<SystemInfo>,
+    updates: UpdatesInfo,
+    // Same order as the unread counters in the config, None for notification counters
+    unread: Vec<Option<Unread>>,
+    // Notifications received while locked by app name, for the counters that count them whatever
+    // the previews show
+    unread_notifications: HashMap<String, Unread>,
+    ambient_lux_opt: Option<f64>,
+    // Wireless keyboards, mice and headsets with their batteries
+    peripherals: Vec<Peripheral>,
+    ups_opt: Option<UpsInfo>,
+    // ups_critical_action already ran since the UPS went on battery
+    ups_acted: bool,
+    // Users with sessions of their own, a restart would end them too
+    other_users: usize,
+    locked_at_opt: Option<DateTime<Local>>,
+    // When each password or finger was rejected since locking, the owner sees them on return
+    failed_attempts: Vec<DateTime<Local>>,
+    plugins: Vec<Plugin>,
+    active_surface_id_opt: Option<SurfaceId>,
+    text_input_ids: HashMap<SurfaceId, widget::Id>,
+    value_tx_opt: Option<mpsc::Sender<String>>,
+    prompt_opt: Option<Prompt>,
+    // Prompts so far in this conversation
+    prompt_steps: usize,
+    // fprintd is waiting for a finger
+    fingerprint_ready: bool,
+    info_opt: Option<String>,
+    error_opt: Option<String>,
+    osk: VirtualKeyboard,
+    keymap: Keymap,
+    // Toggled with a key binding, the clock ticks every second meanwhile
+    clock_seconds: bool,
+    cover: Cover,
+    gestures: Gestures,
+    typing: TypingIndicator,
+    cursor_hidden: bool,
+    pointer_moved_at: Instant,
+    relock_attempts: u32,
+    // A relock after a crash or taking over a stale lock gets none, or crashing the locker would
+    // reopen the window
+    grace: GracePeriod,
+    // The next lock takes over from a locker that didn't get to unlock
+    recovering_lock: bool,
+    watchdog_interval_opt: Option<Duration>,
+    // Base for timer subscription IDs
+    started: Instant,
+    // Messages since the last tick, each one redraws every surface
+    wakeups: u64,
+    // Bumped when the wall clock or timezone changes, restarting the minute timer
+    clock_changes: u64,
+    // Layer shell surfaces stand in for the session lock, see SessionLockEvent::NotSupported
+    layer_shell: bool,
+    input_at: Instant,
+    // Auto-suspend was last attempted or skipped
+    suspend_checked_at: Instant,
+    // Counting down to an auto-suspend
+    suspend_warning: bool,
+    // Fading in the new lock surfaces
+    lock_timeline_opt: Option<Timeline>,
+    // No lock surface has had keyboard focus since
+    unfocused_at: Instant,
+    // Keyboard focus didn't arrive in time, the prompt stays hidden until it does
+    grab_failed: bool,
+    // False while switched to another VT, the compositor takes keyboard focus away meanwhile
+    session_active: bool,
+    // Names of outputs that are turned off, by us or anyone else
+    powered_off: HashSet<String>,
+    // Time between the last two animation frames, for the debug overlay
+    frame_at: Instant,
+    frame_time: Duration,
+    // Everything logged while handling a message is tagged with the current phase
+    phase_span: tracing::Span,
+    // Greeter mode, the sessions to pick from and the one greetd will start
+    sessions: Vec<Session>,
+    session_index: usize,
+    session_tx: watch::Sender<Session>,
+    username_opt: Option<String>,
+    users: Vec<UserInfo>,
+    // A display manager can start a greeter for someone else
+    switch_user_available: bool,
+    // Locked since, for the ScreenSaver interface
+    active_tx: watch::Sender<Option<DateTime<Local>>>,
+    // Holds off sleep until every output shows the lock screen, so the desktop never shows on
+    // resume, taken again after every resume
+    sleep_inhibitor_opt: Option<Arc<OwnedFd>>,
+    // Sleep is waiting on the inhibitor
+    sleep_pending: bool,
+    // Daemon mode, no input for the lock timeout, locking once nothing inhibits it
+    session_idle: bool,
+    // Apps that called Inhibit on the ScreenSaver interface
+    screensaver_inhibitors: Vec<String>,
+    stats: Stats,
+    // A refused SIGTERM, another one soon after forces the locker to exit
+    terminate_at_opt: Option<Instant>,
+    // Media players paused when locking, resumed after unlocking
+    paused_players: Vec<String>,
+    // Capture sources muted when locking, unmuted after unlocking
+    muted_sources: Vec<u32>,
 }
 
 #[derive(Clone)]
-pub struct Flags {}
+pub struct Flags {
+    current_user: pwd::Passwd,
+    config_source: ConfigSource,
+    config: Config,
+    // Why the config file was not used, shown on every output
+    config_error_opt: Option<String>,
+    // Status message shown to passers-by, "Back at 14:00"
+    message_opt: Option<String>,
+    // The lock UI in a regular window, without locking the session
+    preview: bool,
+    // Saves the preview to this PNG file and exits
+    render_opt: Option<PathBuf>,
+    // Keeps running unlocked and locks when idle or asked to, instead of locking right away
+    daemon: bool,
+    // Locks right away in daemon mode too
+    lock_now: bool,
+    // Logging in through greetd, there is no session to lock
+    greeter: bool,
+}
 
 #[derive(Clone, Debug)]
 pub enum Message {
     None,
+    LoadDeferred,
     OutputEvent(OutputEvent, WlOutput),
     SessionLockEvent(SessionLockEvent),
     Unlock,
+    Relock,
+    Watchdog,
+    ConfigChanged,
+    Shutdown,
+    Exit,
+    // Reads back the preview window for `zero-lock render`
+    Render,
+    Rendered(iced::window::Screenshot),
     Tick,
+    ClockChanged,
+    Vpn(Option<VpnState>),
+    Bluetooth(Option<BluetoothInfo>),
+    Capture(CaptureInfo),
+    BluetoothToggle,
+    // Whether a widget, like the focused text input, already handled the key
+    KeyPressed(Key, Modifiers, bool),
+    CycleLayout,
+    Media(Option<MediaInfo>),
+    MediaControl(MediaAction),
+    AlbumArt(String, Option<image::Handle>),
+    Notification(NotificationEvent),
+    ToggleDoNotDisturb,
+    PowerMenu(bool),
+    PowerConfirm(Option<PowerAction>),
+    PowerAction(PowerAction),
+    Brightness(f32),
+    Volume(VolumeAction),
+    MediaKey(MediaAction),
+    // Bus names of the players paused when locking
+    MediaPaused(Vec<String>),
+    // PipeWire node ids of the sources muted when locking
+    SourcesMuted(Vec<u32>),
+    // Clears the clipboard if still locked since this time
+    ClearClipboard(DateTime<Local>),
+    Alarms(Vec<Alarm>),
+    ClockTimers(Vec<ClockTimer>),
+    ClockSecond,
+    // Redraws the timers counting down
+    TimerCountdown,
+    Updates(UpdatesInfo),
+    Unread(Vec<Option<Unread>>),
+    OtherUsers(usize),
+    Frosted(u64, Option<Frosted>),
+    WallpaperMotion(Instant),
+    AmbientLight(f64),
+    Peripherals(Vec<Peripheral>),
+    Ups(Option<UpsInfo>),
+    PowerProfile(Option<PowerProfileInfo>),
+    SetPowerProfile(String),
+    Auth(AuthEvent),
+    Fingerprint(FingerprintEvent),
+    // The account checks after a fingerprint matched
+    FingerprintAccount(Result<(), String>),
+    Input(String),
+    Submit,
+    Osk(OskKey),
+    Gamepad(GamepadEvent),
+    ToggleOsk,
+    Pointer,
+    PointerMoved,
+    CursorTimeout,
+    IdleTimeout,
+    DisplayOffTimeout,
+    SuspendWarning,
+    SuspendCountdown,
+    SuspendTimeout,
+    GrabTimeout,
+    OutputPower(String, bool),
+    Resized(SurfaceId, u32, u32),
+    Sleep(bool),
+    Theme(cosmic::Theme),
+    Touch(touch::Event),
+    LongPress(u64),
+    Contact(Contact, iced::Rectangle),
+    HotCorner(u64),
+    Frame,
+    CoverTimeout,
+    Session(usize),
+    SwitchUserAvailable(bool),
+    Users(Vec<UserInfo>),
+    User(String),
+    SwitchUser,
+    ScreenSaver(ScreenSaverEvent),
+    Signal(SignalEvent),
+    LogindSession(bool),
+    SessionActive(bool),
+    StaleLock,
+    Control(ControlEvent),
+    LidClosed(bool),
+    IdleNotify(bool),
+    IdleInhibitors(Vec<String>),
+    SleepInhibitor(Option<Arc<OwnedFd>>),
+    ReleaseSleepInhibitor,
+}
+
+impl App {
+    fn create_surface(&self, surface_id: SurfaceId, output: WlOutput) -> Command<Message> {
+        if !self.layer_shell {
+            return get_lock_surface(surface_id, output);
+        }
+        get_layer_surface(SctkLayerSurfaceSettings {
+            id: surface_id,
+            layer: Layer::Overlay,
+            keyboard_interactivity: KeyboardInteractivity::Exclusive,
+            anchor: Anchor::all(),
+            output: IcedOutput::Output(output),
+            namespace: "zero-lock".to_string(),
+            size: Some((None, None)),
+            // Cover panels and docks instead of being placed next to them
+            exclusive_zone: -1,
+            ..Default::default()
+        })
+    }
+
+    fn destroy_surface(&self, surface_id: SurfaceId) -> Command<Message> {
+        if self.layer_shell {
+            destroy_layer_surface(surface_id)
+        } else {
+            destroy_lock_surface(surface_id)
+        }
+    }
+
+    /// Counts unread mail again every minute while locked and not idle
+    fn load_unread(&self) -> Command<Message> {
+        if self.flags.config.unread.is_empty()
+            || !matches!(self.state, State::Locked(_))
+            || self.is_idle()
+        {
+            return Command::none();
+        }
+        let sources = self
+            .flags
+            .config
+            .unread
+            .iter()
+            .map(|counter| counter.source.clone())
+            .collect();
+        Command::perform(unread::load(sources), |unread| {
+            message::app(Message::Unread(unread))
+        })
+    }
+
+    fn load_other_users(&self) -> Command<Message> {
+        if !matches!(self.state, State::Locked(_)) || self.is_idle() {
+            return Command::none();
+        }
+        let username = self.flags.current_user.name.clone();
+        Command::perform(logind::other_users(username), |res| {
+            let other_users = res.unwrap_or_else(|err| {
+                log::info!("failed to list sessions: {}", err);
+                0
+            });
+            message::app(Message::OtherUsers(other_users))
+        })
+    }
+
+    // Blurs each wallpaper once, the frosted card is plain translucent until then
+    fn frost(&self) -> Command<Message> {
+        if !self.flags.config.frosted_card {
+            return Command::none();
+        }
+        let radius = self.flags.config.frosted_blur;
+        let commands = self
+            .wallpapers
+            .images()
+            .filter(|handle| !self.frosted.contains_key(&handle.id()))
+            .map(|handle| {
+                let id = handle.id();
+                Command::perform(frosted::blur(handle.clone(), radius), move |res| {
+                    let frosted_opt = res
+                        .map_err(|err| log::warn!("failed to blur wallpaper: {}", err))
+                        .ok();
+                    message::app(Message::Frosted(id, frosted_opt))
+                })
+            });
+        Command::batch(commands)
+    }
+
+    // For a crash report, the panic hook can't reach the app
+    fn report_outputs(&self) {
+        let outputs = self
+            .output_infos
+            .values()
+            .map(|info| {
+                let (width, height) = output::size(info).unwrap_or_default();
+                format!(
+                    "{} {}x{} at scale {}",
+                    output::name(info),
+                    width,
+                    height,
+                    output::scale(info)
+                )
+            })
+            .collect();
+        crash_report::outputs(outputs);
+    }
+
+    /// Replaces every lock surface with a new one, which draws from scratch
+    fn recreate_surfaces(&mut self) -> Vec<Command<Message>> {
+        let outputs: Vec<_> = self.surface_ids.keys().cloned().collect();
+        outputs
+            .into_iter()
+            .filter_map(|output| self.recreate_surface(output))
+            .collect()
+    }
+
+    fn recreate_surface(&mut self, output: WlOutput) -> Option<Command<Message>> {
+        let surface_id = SurfaceId::unique();
+        let old_surface_id = self.surface_ids.insert(output.clone(), surface_id)?;
+        log::info!(
+            "output {}: recreating surface {:?} as {:?}",
+            output.id(),
+            old_surface_id,
+            surface_id
+        );
+        self.text_input_ids.remove(&old_surface_id);
+        self.text_input_ids.insert(surface_id, widget::Id::unique());
+        self.surface_sizes.remove(&old_surface_id);
+        if let Some(output_info) = self.output_infos.remove(&old_surface_id) {
+            self.output_infos.insert(surface_id, output_info);
+        }
+        if self.active_surface_id_opt == Some(old_surface_id) {
+            self.active_surface_id_opt = Some(surface_id);
+        }
+        Some(Command::batch(vec![
+            self.destroy_surface(old_surface_id),
+            self.create_surface(surface_id, output),
+        ]))
+    }
+
+    fn output_mode(&self) -> OutputMode {
+        match self.flags.config.output_mode_opt {
+            Some(output_mode) => output_mode,
+            None if self.surface_ids.len() > INDEPENDENT_OUTPUTS_MAX => OutputMode::Mirrored,
+            None => OutputMode::Independent,
+        }
+    }
+
+    fn output_config(&self, surface_id: SurfaceId) -> Option<&OutputConfig> {
+        if self.output_mode() == OutputMode::Mirrored {
+            return None;
+        }
+        let output_info = self.output_infos.get(&surface_id)?;
+        self.flags.config.outputs.get(&output::name(output_info))
+    }
+
+    /// Any key, pointer or touch input resumes from idle right away. Waking a dimmed or dark
+    /// screen returns the command to run instead of handling the input, so the first key press
+    /// is neither typed nor reveals the prompt
+    fn input(&mut self) -> Option<Command<Message>> {
+        self.input_at = Instant::now();
+        self.suspend_warning = false;
+        match self.state {
+            State::Locked(Activity::Idle) => {
+                log::debug!("resuming from idle");
+                self.state = State::Locked(Activity::Active);
+                None
+            }
+            State::Locked(Activity::Dimming(_)) => {
+                log::debug!("waking from dimmed");
+                self.state = State::Locked(Activity::Active);
+                Some(Command::none())
+            }
+            State::Locked(Activity::DisplayOff) if self.flags.preview => {
+                self.state = State::Locked(Activity::Active);
+                Some(Command::none())
+            }
+            State::Locked(Activity::DisplayOff) => {
+                log::info!("turning displays on");
+                self.state = State::Locked(Activity::Active);
+                Some(Command::batch(vec![
+                    display_power(true),
+                    self.restore_keyboard_backlight(),
+                ]))
+            }
+            _ => None,
+        }
+    }
+
+    fn suspend_at(&self, timeout: u64) -> Instant {
+        self.input_at.max(self.suspend_checked_at) + Duration::from_secs(timeout)
+    }
+
+    fn suspend_warning_at(&self, timeout: u64) -> Instant {
+        self.input_at.max(self.suspend_checked_at)
+            + Duration::from_secs(timeout).saturating_sub(SUSPEND_WARNING)
+    }
+
+    /// Time left until the auto-suspend while counting down
+    fn suspend_in(&self) -> Option<Duration> {
+        if !self.suspend_warning {
+            return None;
+        }
+        let timeout = self.flags.config.suspend_timeout_opt?;
+        Some(
+            self.suspend_at(timeout)
+                .saturating_duration_since(Instant::now()),
+        )
+    }
+
+    /// Why the machine should stay awake, inhibitors are checked when suspending
+    fn suspend_excluded(&self) -> Option<&'static str> {
+        if !self.flags.config.suspend_on_ac && power_supply::on_ac() {
+            return Some("on AC power");
+        }
+        if self.media_opt.as_ref().map_or(false, |media| media.playing) {
+            return Some("while media is playing");
+        }
+        None
+    }
+
+    /// From the configured size, output info can lag behind a mode change
+    fn is_portrait(&self, surface_id: SurfaceId) -> bool {
+        match self.surface_sizes.get(&surface_id) {
+            Some((width, height)) => height > width,
+            None => self
+                .output_infos
+                .get(&surface_id)
+                .map_or(false, output::is_portrait),
+        }
+    }
+
+    fn is_powered(&self, surface_id: SurfaceId) -> bool {
+        self.output_infos
+            .get(&surface_id)
+            .map_or(true, |info| !self.powered_off.contains(&output::name(info)))
+    }
+
+    fn is_asleep(&self) -> bool {
+        matches!(
+            self.state,
+            State::Locked(Activity::Dimming(_) | Activity::DisplayOff)
+        )
+    }
+
+    fn display_off(&mut self) -> Command<Message> {
+        self.state = State::Locked(Activity::DisplayOff);
+        if self.flags.preview {
+            return Command::none();
+        }
+        log::info!("turning displays off");
+        Command::batch(vec![display_power(false), self.keyboard_backlight_off()])
+    }
+
+    fn keyboard_backlight_off(&mut self) -> Command<Message> {
+        if !self.flags.config.keyboard_backlight_off || self.keyboard_backlight_opt.is_some() {
+            return Command::none();
+        }
+        let Some(keyboard_backlight) = KeyboardBacklight::load() else {
+            return Command::none();
+        };
+        // Already off, nothing to restore
+        let brightness = keyboard_backlight.brightness().unwrap_or(0);
+        if brightness == 0 {
+            return Command::none();
+        }
+        let name = keyboard_backlight.name.clone();
+        self.keyboard_backlight_opt = Some((keyboard_backlight, brightness));
+        keyboard_brightness(name, 0)
+    }
+
+    /// Back to the brightness from before the displays turned off
+    fn restore_keyboard_backlight(&mut self) -> Command<Message> {
+        match self.keyboard_backlight_opt.take() {
+            Some((keyboard_backlight, brightness)) => {
+                keyboard_brightness(keyboard_backlight.name, brightness)
+            }
+            None => Command::none(),
+        }
+    }
+
+    /// Whether the wallpaper pans and zooms, it holds still wherever it was otherwise
+    fn wallpaper_motion(&self) -> bool {
+        self.flags.config.wallpaper_motion && !self.flags.config.reduced_motion
+    }
+
+    fn wallpaper_moving(&self) -> bool {
+        let power_saver = self
+            .power_profile_opt
+            .as_ref()
+            .map_or(false, |info| info.active == "power-saver");
+        self.wallpaper_motion()
+            && matches!(self.state, State::Locked(Activity::Active))
+            && !power_saver
+    }
+
+    fn is_idle(&self) -> bool {
+        matches!(
+            self.state,
+            State::Locked(Activity::Idle | Activity::DisplayOff)
+        )
+    }
+
+    /// Whether the surface has keyboard focus, the prompt is only shown there
+    fn is_focused(&self, surface_id: SurfaceId) -> bool {
+        self.active_surface_id_opt == Some(surface_id)
+    }
+
+    fn unfocus(&mut self) {
+        if self.active_surface_id_opt.take().is_some() {
+            self.unfocused_at = Instant::now();
+        }
+    }
+
+    fn inhibit_sleep(&self) -> Command<Message> {
+        if self.flags.preview || self.flags.greeter {
+            return Command::none();
+        }
+        Command::perform(logind::inhibit_sleep(), |res| {
+            let inhibitor_opt = match res {
+                Ok(fd) => Some(Arc::new(fd)),
+                Err(err) => {
+                    log::warn!("failed to inhibit sleep: {}", err);
+                    None
+                }
+            };
+            message::app(Message::SleepInhibitor(inhibitor_opt))
+        })
+    }
+
+    /// Lets sleep go ahead once the lock is up and drawn on every output
+    fn release_sleep_inhibitor(&self) -> Command<Message> {
+        if !self.sleep_pending
+            || self.sleep_inhibitor_opt.is_none()
+            || !matches!(self.state, State::Locked(_))
+            || self
+                .surface_ids
+                .values()
+                .any(|surface_id| !self.surface_sizes.contains_key(surface_id))
+        {
+            return Command::none();
+        }
+        Command::perform(tokio::time::sleep(SLEEP_DRAW_GRACE), |()| {
+            message::app(Message::ReleaseSleepInhibitor)
+        })
+    }
+
+    fn lock_requested(&mut self, source: &str) -> Command<Message> {
+        // Otherwise this process only runs to lock the session, until it is unlocked
+        if !self.flags.daemon || !matches!(self.state, State::Unlocked) {
+            log::info!("lock requested by {}, already {:?}", source, self.state);
+            return Command::none();
+        }
+        log::info!("locking, requested by {}", source);
+        let config = &self.flags.config;
+        if config
+            .background_opt
+            .as_deref()
+            .and_then(background_dir::directory)
+            .is_some()
+        {
+            self.background_opt = background(config);
+            self.wallpapers = wallpapers(config, self.background_opt.as_deref());
+        }
+        self.grace.requested(source);
+        self.state = State::Locking;
+        self.phase_span = tracing::info_span!(parent: None, "locking");
+        lock()
+    }
+
+    /// Locks on idle unless apps or logind inhibit it, checked again every minute until then
+    fn idle_lock(&self) -> Command<Message> {
+        if !self.session_idle || !matches!(self.state, State::Unlocked) {
+            return Command::none();
+        }
+        if !self.screensaver_inhibitors.is_empty() {
+            for inhibitor in self.screensaver_inhibitors.iter() {
+                log::info!("not locking on idle, inhibited by {}", inhibitor);
+            }
+            return Command::none();
+        }
+        Command::perform(logind::idle_inhibitors(), |res| {
+            let inhibitors = res.unwrap_or_else(|err| {
+                log::warn!("failed to list idle inhibitors: {}", err);
+                Vec::new()
+            });
+            message::app(Message::IdleInhibitors(inhibitors))
+        })
+    }
+
+    /// Applies a changed config file to the surfaces that are already shown
+    fn reload_config(&mut self) -> Command<Message> {
+        let (config, config_error_opt) = self.flags.config_source.load();
+        log::info!("config changed, reloading");
+        self.flags.config_error_opt = config_error_opt;
+        // Plugins, the renderer and the on-screen keyboard keep what they started with
+        self.secret_image_opt = config
+            .secret_image_opt
+            .as_ref()
+            .map(image::Handle::from_path);
+        if config.background_opt != self.flags.config.background_opt {
+            self.background_opt = background(&config);
+        }
+        self.wallpapers = wallpapers(&config, self.background_opt.as_deref());
+        let command = if config.high_contrast != self.flags.config.high_contrast {
+            cosmic::app::command::set_theme(theme(config.high_contrast))
+        } else {
+            Command::none()
+        };
+        self.keymap = Keymap::new(&config.key_bindings);
+        if config.frosted_blur != self.flags.config.frosted_blur {
+            self.frosted.clear();
+        }
+        if config.wallpaper_motion_period != self.flags.config.wallpaper_motion_period {
+            self.motion = Motion::new(Duration::from_secs(config.wallpaper_motion_period));
+        }
+        // The clock, layout and timeouts read it on every frame and tick
+        self.flags.config = config;
+        Command::batch([command, self.frost()])
+    }
+
+    fn in_grace_period(&self) -> bool {
+        let grace_period = self.flags.config.grace_period;
+        grace_period > 0
+            && matches!(self.state, State::Locked(_))
+            && self.grace.contains(Duration::from_secs(grace_period))
+    }
+
+    /// Unlocks without authenticating, for root, logind and the grace period
+    fn admin_unlock(&mut self, source: &str) -> Command<Message> {
+        if !matches!(self.state, State::Locked(_)) {
+            return Command::none();
+        }
+        log::info!("unlock requested by {}", source);
+        self.update(Message::Unlock)
+    }
+
+    /// SIGTERM, refused while locked unless it is sent again within [`FORCE_TERMINATE`]
+    fn terminate(&mut self) -> Command<Message> {
+        let protecting =
+            !self.flags.preview && !self.flags.greeter && !matches!(self.state, State::Unlocked);
+        if protecting {
+            let forced = self.terminate_at_opt.map_or(false, |terminate_at| {
+                terminate_at.elapsed() < FORCE_TERMINATE
+            });
+            if !forced {
+                log::warn!(
+                    "refusing SIGTERM while locked, send it again within {}s to force it",
+                    FORCE_TERMINATE.as_secs()
+                );
+                self.terminate_at_opt = Some(Instant::now());
+                return Command::none();
+            }
+            // The compositor keeps the session locked until another locker takes over
+            log::warn!("terminating while locked, the session stays locked");
+        } else {
+            log::info!("terminating");
+        }
+        self.update(Message::Shutdown)
+    }
+
+    /// The players to resume after unlocking, none unless configured to
+    fn take_paused_players(&mut self) -> Vec<String> {
+        let paused_players = std::mem::take(&mut self.paused_players);
+        if self.flags.config.resume_media {
+            paused_players
+        } else {
+            Vec::new()
+        }
+    }
+
+    // Called when first locked, the delay starts from locked_at_opt
+    fn clear_clipboard_after_delay(&self) -> Command<Message> {
+        let Some(locked_at) = self.locked_at_opt else {
+            return Command::none();
+        };
+        let delay = self.flags.config.clear_clipboard_delay;
+        if delay == 0 {
+            return clear_clipboard();
+        }
+        Command::perform(tokio::time::sleep(Duration::from_secs(delay)), move |()| {
+            message::app(Message::ClearClipboard(locked_at))
+        })
+    }
+
+    fn record_unlock(&mut self) {
+        let locked_for = self.locked_at_opt.map_or(Duration::ZERO, |locked_at| {
+            (Local::now() - locked_at).to_std().unwrap_or_default()
+        });
+        self.stats.unlocked(locked_for);
+    }
+
+    /// Reply to `zero-lock status`
+    fn status(&self) -> String {
+        let state = match self.state {
+            State::Locking => "locking",
+            State::Locked(_) => "locked",
+            State::Unlocking(_) => "unlocking",
+            State::Unlocked => "unlocked",
+        };
+        let mut status = format!("state: {}\n", state);
+        if let Some(locked_at) = self.locked_at_opt {
+            status.push_str(&format!("locked at: {}\n", locked_at.format("%F %T")));
+        }
+        for inhibitor in self.screensaver_inhibitors.iter() {
+            status.push_str(&format!("idle inhibited by: {}\n", inhibitor));
+        }
+        status
+    }
+
+    /// Forgets the last lock in daemon mode, outputs are kept for the next one
+    fn reset_lock(&mut self) {
+        let outputs: Vec<_> = self.surface_ids.keys().cloned().collect();
+        for output in outputs {
+            // Lock surfaces are gone with the lock, the next lock creates new ones
+            let surface_id = SurfaceId::unique();
+            let Some(old_surface_id) = self.surface_ids.insert(output, surface_id) else {
+                continue;
+            };
+            self.text_input_ids.remove(&old_surface_id);
+            self.text_input_ids.insert(surface_id, widget::Id::unique());
+            if let Some(output_info) = self.output_infos.remove(&old_surface_id) {
+                self.output_infos.insert(surface_id, output_info);
+            }
+        }
+        self.surface_sizes.clear();
+        self.active_surface_id_opt = None;
+        self.locked_at_opt = None;
+        self.grace = GracePeriod::default();
+        self.recovering_lock = false;
+        self.failed_attempts.clear();
+        self.notification_count = 0;
+        self.notifications.clear();
+        self.unread_notifications.clear();
+        // Not watched while unlocked, the next lock starts from what its subscriptions send
+        self.vpn_opt = None;
+        self.bluetooth_opt = None;
+        self.peripherals.clear();
+        self.capture = CaptureInfo::default();
+        self.clock_timers.clear();
+        self.media_opt = None;
+        self.power_profile_opt = None;
+        self.lock_timeline_opt = None;
+        self.grab_failed = false;
+        self.power_menu = false;
+        self.power_confirm_opt = None;
+        self.value_tx_opt = None;
+        self.prompt_opt = None;
+        self.fingerprint_ready = false;
+        self.info_opt = None;
+        self.error_opt = None;
+    }
+
+    fn select_session(&mut self, index: usize) {
+        if let Some(session) = self.sessions.get(index) {
+            self.session_index = index;
+            self.session_tx.send_replace(session.clone());
+        }
+    }
+
+    fn focus_prompt(&self) -> Command<Message> {
+        match self
+            .active_surface_id_opt
+            .and_then(|surface_id| self.text_input_ids.get(&surface_id))
+        {
+            Some(text_input_id) => widget::text_input::focus(text_input_id.clone()),
+            None => Command::none(),
+        }
+    }
+
+    /// Global shortcuts and passthrough keys, None if the key was not handled
+    fn key_pressed(&mut self, key: &Key, modifiers: Modifiers) -> Option<Command<Message>> {
+        let command = match self.keymap.action(key, modifiers)? {
+            KeyAction::ClearInput => {
+                if let Some(prompt) = &mut self.prompt_opt {
+                    prompt.value.clear();
+                    self.typing.erase();
+                }
+                Command::none()
+            }
+            KeyAction::PowerMenu => self.update(Message::PowerMenu(!self.power_menu)),
+            KeyAction::CycleLayout => self.update(Message::CycleLayout),
+            KeyAction::ToggleSeconds => {
+                self.clock_seconds = !self.clock_seconds;
+                self.now = Local::now();
+                Command::none()
+            }
+            KeyAction::ToggleLargeText => {
+                let large_text = !self.flags.config.large_text;
+                // Applied right away, the config reload that follows the save agrees
+                self.flags.config.large_text = large_text;
+                if !self.flags.preview {
+                    config::set_large_text(large_text);
+                }
+                Command::none()
+            }
+            KeyAction::ToggleKeyboard => self.update(Message::ToggleOsk),
+            KeyAction::BrightnessUp => {
+                self.update(Message::Brightness(self.brightness + BRIGHTNESS_STEP))
+            }
+            KeyAction::BrightnessDown => {
+                self.update(Message::Brightness(self.brightness - BRIGHTNESS_STEP))
+            }
+            KeyAction::VolumeUp => self.update(Message::Volume(VolumeAction::Raise)),
+            KeyAction::VolumeDown => self.update(Message::Volume(VolumeAction::Lower)),
+            KeyAction::Mute => self.update(Message::Volume(VolumeAction::ToggleMute)),
+            KeyAction::PlayPause => self.update(Message::MediaKey(MediaAction::PlayPause)),
+            KeyAction::NextTrack => self.update(Message::MediaKey(MediaAction::Next)),
+            KeyAction::PreviousTrack => self.update(Message::MediaKey(MediaAction::Previous)),
+            KeyAction::None => return None,
+        };
+        Some(command)
+    }
+
+    /// Secret prompts typed without a text input, so the length isn't shown
+    fn segments_input(&self) -> bool {
+        matches!(
+            self.flags.config.typing_feedback,
+            TypingFeedback::Segments | TypingFeedback::Hidden
+        ) && self
+            .prompt_opt
+            .as_ref()
+            .map_or(false, |prompt| prompt.secret)
+    }
+
+    /// Keys typed into the prompt when there is no text input to receive them
+    fn typed(&mut self, key: &Key, modifiers: Modifiers) -> Command<Message> {
+        let Some(prompt) = &mut self.prompt_opt else {
+            return Command::none();
+        };
+        match key {
+            Key::Named(Named::Enter) => return self.update(Message::Submit),
+            Key::Named(Named::Backspace) => {
+                if prompt.value.pop().is_some() {
+                    self.typing.erase();
+                }
+            }
+            Key::Named(Named::Space) => {
+                prompt.value.push(' ');
+                self.typing.keystroke();
+            }
+            Key::Character(c) if !modifiers.control() && !modifiers.alt() && !modifiers.logo() => {
+                prompt.value.push_str(c);
+                self.typing.keystroke();
+            }
+            _ => {}
+        }
+        Command::none()
+    }
 }
 
 impl cosmic::Application for App {
@@ -90,43 +1253,289 @@ impl cosmic::Application for App {
         core.window.use_template = false;
 
         let now = Local::now();
-        let app = App {
+        let osk = VirtualKeyboard::new(flags.config.virtual_keyboard, flags.config.high_contrast);
+        let cover = Cover::new(
+            flags.config.cover_screen,
+            !flags.config.reduced_motion,
+            Duration::from_secs(flags.config.cover_timeout),
+        );
+        let typing = TypingIndicator::new(!flags.config.reduced_motion);
+        let secret_image_opt = flags
+            .config
+            .secret_image_opt
+            .as_ref()
+            .map(image::Handle::from_path);
+        let background_opt = background(&flags.config);
+        let wallpapers = wallpapers(&flags.config, background_opt.as_deref());
+        let theme = theme(flags.config.high_contrast);
+        // Nothing slow before the lock request, everything optional is loaded once it went out
+        let mut commands = Vec::with_capacity(5);
+        // Taken before anything else, a standalone locker relaunched by a panic is relocking too
+        let relock = panic_hook::relock();
+        let lock_now = !flags.daemon || flags.lock_now || relock;
+        if !flags.preview && !flags.greeter && lock_now {
+            commands.push(lock());
+        }
+        commands.extend([
+            Command::perform(async {}, |()| message::app(Message::LoadDeferred)),
+            cosmic::app::command::set_theme(theme),
+            // ext-session-lock locks every seat of this compositor, on multi-seat systems each
+            // seat runs its own compositor and locker and authenticates on its own
+            Command::perform(logind::session_seat(), |res| {
+                match res {
+                    Ok((session, seat)) => log::info!("locking session {} on {}", session, seat),
+                    Err(err) => log::info!("failed to find logind session: {}", err),
+                }
+                message::app(Message::None)
+            }),
+        ]);
+        let sessions = if flags.greeter {
+            let mut sessions = sessions::load();
+            if sessions.is_empty() {
+                sessions.push(Session::command(flags.config.greeter_session.clone()));
+            }
+            sessions
+        } else {
+            Vec::new()
+        };
+        let (session_tx, _) = watch::channel(
+            sessions
+                .first()
+                .cloned()
+                .unwrap_or_else(|| Session::command(flags.config.greeter_session.clone())),
+        );
+        // One small file, and only when persisted
+        let stats = Stats::load(flags.config.persist_stats && !flags.preview && !flags.greeter);
+        let preview = flags.preview;
+        let greeter = flags.greeter;
+        let mut app = App {
             core,
             flags,
             now,
-            state: State::Unlocked,
+            state: State::Locking,
             surface_ids: HashMap::new(),
+            output_infos: HashMap::new(),
+            surface_sizes: HashMap::new(),
+            vpn_opt: None,
+            bluetooth_opt: None,
+            capture: CaptureInfo::default(),
+            keyboard_layouts: KeyboardLayouts::default(),
+            media_opt: None,
+            art_cache: HashMap::new(),
+            secret_image_opt,
+            background_opt,
+            wallpapers,
+            frosted: HashMap::new(),
+            motion: Motion::new(Duration::from_secs(flags.config.wallpaper_motion_period)),
+            notification_count: 0,
+            notifications: Vec::new(),
+            do_not_disturb_opt: None,
+            power_menu: false,
+            power_menu_touch: false,
+            power_confirm_opt: None,
+            backlight_opt: None,
+            brightness: 100.0,
+            keyboard_backlight_opt: None,
+            power_profile_opt: None,
+            alarms: Vec::new(),
+            clock_timers: Vec::new(),
+            system_info_opt: None,
+            updates: UpdatesInfo::default(),
+            unread: Vec::new(),
+            unread_notifications: HashMap::new(),
+            ambient_lux_opt: None,
+            peripherals: Vec::new(),
+            ups_opt: None,
+            ups_acted: false,
+            other_users: 0,
+            locked_at_opt: None,
+            failed_attempts: Vec::new(),
+            plugins: Vec::new(),
+            active_surface_id_opt: None,
+            text_input_ids: HashMap::new(),
+            value_tx_opt: None,
+            prompt_opt: None,
+            prompt_steps: 0,
+            fingerprint_ready: false,
+            info_opt: None,
+            error_opt: None,
+            osk,
+            keymap: Keymap::new(&flags.config.key_bindings),
+            clock_seconds: false,
+            cover,
+            gestures: Gestures::default(),
+            typing,
+            // Nothing to point at until the pointer moves
+            cursor_hidden: true,
+            pointer_moved_at: Instant::now(),
+            relock_attempts: 0,
+            grace: GracePeriod::default(),
+            recovering_lock: relock,
+            watchdog_interval_opt: systemd::watchdog_interval(),
+            started: Instant::now(),
+            wakeups: 0,
+            clock_changes: 0,
+            layer_shell: false,
+            input_at: Instant::now(),
+            suspend_checked_at: Instant::now(),
+            suspend_warning: false,
+            lock_timeline_opt: None,
+            unfocused_at: Instant::now(),
+            grab_failed: false,
+            session_active: true,
+            powered_off: HashSet::new(),
+            frame_at: Instant::now(),
+            frame_time: Duration::ZERO,
+            phase_span: tracing::info_span!(parent: None, "locking"),
+            sessions,
+            session_index: 0,
+            session_tx,
+            username_opt: None,
+            users: Vec::new(),
+            switch_user_available: false,
+            active_tx: watch::channel(None).0,
+            sleep_inhibitor_opt: None,
+            sleep_pending: false,
+            session_idle: false,
+            screensaver_inhibitors: Vec::new(),
+            stats,
+            terminate_at_opt: None,
+            paused_players: Vec::new(),
+            muted_sources: Vec::new(),
         };
+        if preview {
+            // The main window stands in for a focused lock surface, authenticating closes it
+            log::info!("previewing without locking the session");
+            app.state = State::Locked(Activity::Active);
+            app.phase_span = tracing::info_span!(parent: None, "preview");
+            app.locked_at_opt = Some(now);
+            app.text_input_ids
+                .insert(SurfaceId::MAIN, widget::Id::unique());
+            app.active_surface_id_opt = Some(SurfaceId::MAIN);
+            commands.push(app.frost());
+            if app.flags.render_opt.is_some() {
+                commands.push(Command::perform(
+                    tokio::time::sleep(render::SETTLE_DELAY),
+                    |()| message::app(Message::Render),
+                ));
+            }
+        } else if !lock_now {
+            log::info!("waiting to lock");
+            app.state = State::Unlocked;
+            app.phase_span = tracing::info_span!(parent: None, "unlocked");
+            // Restarted by the service manager, which doesn't know whether the last daemon died
+            // holding the lock, but its locked hint is still set then
+            commands.push(Command::perform(logind::locked_hint(), |res| match res {
+                Ok(true) => message::app(Message::StaleLock),
+                Ok(false) => message::app(Message::None),
+                Err(err) => {
+                    log::warn!("failed to read locked hint: {}", err);
+                    message::app(Message::None)
+                }
+            }));
+            systemd::notify("READY=1");
+        } else if greeter {
+            // Overlay surfaces are created as outputs appear, greetd keeps the session and the
+            // compositor goes away once the chosen session starts
+            log::info!("greeting through greetd");
+            app.state = State::Locked(Activity::Active);
+            app.layer_shell = true;
+            app.phase_span = tracing::info_span!(parent: None, "greeter");
+            app.locked_at_opt = Some(now);
+        }
 
-        (app, lock())
+        commands.push(app.inhibit_sleep());
+
+        (app, Command::batch(commands))
     }
 
     fn update(&mut self, message: Self::Message) -> iced::Command<message::Message<Self::Message>> {
+        let _entered = self.phase_span.clone().entered();
+        self.wakeups += 1;
+        // Moving the pointer doesn't count, the surfaces appearing under it would unlock
+        if matches!(
+            message,
+            Message::KeyPressed(..) | Message::Pointer | Message::Touch(_) | Message::Gamepad(_)
+        ) && self.in_grace_period()
+        {
+            return self.admin_unlock("input in the grace period");
+        }
+        // Dark displays wait for a double tap instead, a hand or a pocket brushing the screen
+        // would wake them all the time
+        let tap_to_wake = self.flags.config.double_tap_wake && self.is_asleep();
+        if matches!(
+            message,
+            Message::KeyPressed(..)
+                | Message::Pointer
+                | Message::PointerMoved
+                | Message::Gamepad(_)
+        ) || (matches!(message, Message::Touch(_)) && !tap_to_wake)
+        {
+            if let Some(command) = self.input() {
+                return command;
+            }
+        }
         match message {
+            // Outputs are only covered by lock surfaces
+            Message::OutputEvent(..) if self.flags.preview => Command::none(),
             Message::OutputEvent(output_event, output) => match output_event {
                 OutputEvent::Created(output_info_opt) => {
                     log::info!("output {}: created", output.id());
 
+                    let mut commands = Vec::with_capacity(2);
+                    // HashMap::insert would keep the stale key, so remove first to bind the new
+                    // surface to the fresh WlOutput
+                    if let Some(old_surface_id) = self.surface_ids.remove(&output) {
+                        log::warn!(
+                            "output {}: replacing surface ID {:?}",
+                            output.id(),
+                            old_surface_id
+                        );
+                        self.text_input_ids.remove(&old_surface_id);
+                        self.output_infos.remove(&old_surface_id);
+                        self.surface_sizes.remove(&old_surface_id);
+                        if self.active_surface_id_opt == Some(old_surface_id) {
+                            self.unfocus();
+                        }
+                        if matches!(self.state, State::Locked(_)) {
+                            commands.push(self.destroy_surface(old_surface_id));
+                        }
+                    }
+
                     let surface_id = SurfaceId::unique();
-                    match self.surface_ids.insert(output.clone(), surface_id) {
-                        Some(old_surface_id) => {
-                            //TODO: remove old surface?
-                            log::warn!(
-                                "output {}: already had surface ID {:?}",
-                                output.id(),
-                                old_surface_id
-                            );
+                    self.text_input_ids.insert(surface_id, widget::Id::unique());
+                    self.surface_ids.insert(output.clone(), surface_id);
+                    if let Some(output_info) = output_info_opt {
+                        log::info!(
+                            "output {}: {} at scale {}",
+                            output.id(),
+                            output::name(&output_info),
+                            output::scale(&output_info)
+                        );
+                        self.output_infos.insert(surface_id, output_info);
+                        self.report_outputs();
+                    }
+                    // Hotplugged after locking, it would show nothing until a lock surface exists
+                    if matches!(self.state, State::Locked(_)) {
+                        commands.push(self.create_surface(surface_id, output));
+                        // No focus events come for layer surfaces
+                        if self.layer_shell && self.active_surface_id_opt.is_none() {
+                            self.active_surface_id_opt = Some(surface_id);
+                            commands.push(self.focus_prompt());
                         }
-                        None => {}
                     }
-                    Command::none()
+                    Command::batch(commands)
                 }
                 OutputEvent::Removed => {
                     log::info!("output {}: removed", output.id());
                     match self.surface_ids.remove(&output) {
                         Some(surface_id) => {
-                            if matches!(self.state, State::Locked) {
-                                return destroy_lock_surface(surface_id);
+                            self.text_input_ids.remove(&surface_id);
+                            self.output_infos.remove(&surface_id);
+                            self.surface_sizes.remove(&surface_id);
+                            self.report_outputs();
+                            if matches!(self.state, State::Locked(_)) {
+                                return self.destroy_surface(surface_id);
                             }
                         }
                         None => {
@@ -135,77 +1544,1358 @@ impl cosmic::Application for App {
                     }
                     Command::none()
                 }
-                OutputEvent::InfoUpdate(_output_info) => {
-                    log::info!("output {}: info update", output.id());
+                OutputEvent::InfoUpdate(output_info) => {
+                    log::info!(
+                        "output {}: info update, scale {}",
+                        output.id(),
+                        output::scale(&output_info)
+                    );
+                    let Some(surface_id) = self.surface_ids.get(&output).copied() else {
+                        return Command::none();
+                    };
+                    let resized = self
+                        .output_infos
+                        .get(&surface_id)
+                        .map_or(false, |old_info| {
+                            output::size(old_info) != output::size(&output_info)
+                                || output::scale(old_info) != output::scale(&output_info)
+                        });
+                    // Clock size and orientation follow from the new info on the next view
+                    self.output_infos.insert(surface_id, output_info);
+                    self.report_outputs();
+                    // A fresh surface is configured and drawn at the new size and scale, instead
+                    // of stretching or cropping buffers from the old mode
+                    if resized && matches!(self.state, State::Locked(_)) {
+                        if let Some(command) = self.recreate_surface(output) {
+                            return command;
+                        }
+                    }
                     Command::none()
                 }
             },
             Message::SessionLockEvent(session_lock_event) => match session_lock_event {
                 SessionLockEvent::Focused(_, surface_id) => {
                     log::info!("focus surface {:?}", surface_id);
-                    Command::none()
+                    // Only mapped surfaces get focus
+                    panic_hook::rendered();
+                    self.active_surface_id_opt = Some(surface_id);
+                    if self.grab_failed {
+                        log::info!("keyboard focus arrived, showing the prompt");
+                        self.grab_failed = false;
+                    }
+                    self.focus_prompt()
                 }
                 SessionLockEvent::Locked => {
-                    log::info!("session locked");
-                    self.state = State::Locked;
-                    let mut commands = Vec::with_capacity(self.surface_ids.len());
+                    log::info!(
+                        "session locked {}ms after start",
+                        self.started.elapsed().as_millis()
+                    );
+                    self.state = State::Locked(Activity::Active);
+                    self.phase_span = tracing::info_span!(parent: None, "locked");
+                    self.unfocused_at = Instant::now();
+                    self.relock_attempts = 0;
+                    systemd::notify("READY=1");
+                    let mut commands = Vec::with_capacity(self.surface_ids.len() + 1);
+                    commands.push(Command::perform(logind::set_locked_hint(true), |res| {
+                        if let Err(err) = res {
+                            log::warn!("failed to set locked hint: {}", err);
+                        }
+                        message::app(Message::None)
+                    }));
+                    // Keep the original time if this is a relock
+                    if self.locked_at_opt.is_none() {
+                        self.locked_at_opt = Some(Local::now());
+                        if self.recovering_lock {
+                            log::info!("no grace period when taking over a lock");
+                        } else if !self.flags.greeter && !self.grace.start() {
+                            log::info!("no grace period when locking for sleep or the lid");
+                        }
+                        self.stats.locked();
+                        commands.push(hook("lock", &self.flags.config.on_lock_opt));
+                        if self.flags.config.pause_media {
+                            commands.push(Command::perform(mpris::pause_all(), |res| {
+                                let paused = res.unwrap_or_else(|err| {
+                                    log::warn!("failed to pause media players: {}", err);
+                                    Vec::new()
+                                });
+                                message::app(Message::MediaPaused(paused))
+                            }));
+                        }
+                        if self.flags.config.mute_microphone {
+                            commands.push(Command::perform(pipewire::mute_sources(), |res| {
+                                let muted = res.unwrap_or_else(|err| {
+                                    log::warn!("failed to mute microphones: {}", err);
+                                    Vec::new()
+                                });
+                                message::app(Message::SourcesMuted(muted))
+                            }));
+                        }
+                        if self.flags.config.clear_clipboard {
+                            commands.push(self.clear_clipboard_after_delay());
+                        }
+                        // An upgrade may have happened since
+                        self.system_info_opt = SystemInfo::load();
+                        commands.push(self.load_unread());
+                        commands.push(self.load_other_users());
+                        commands.push(self.frost());
+                        if self.flags.config.next_alarm {
+                            commands.push(Command::perform(alarms::load(), |res| {
+                                let alarms = res.unwrap_or_else(|err| {
+                                    log::info!("no alarms: {}", err);
+                                    Vec::new()
+                                });
+                                message::app(Message::Alarms(alarms))
+                            }));
+                        }
+                    }
+                    self.active_tx.send_replace(self.locked_at_opt);
+                    panic_hook::locked(true);
+                    for plugin in self.plugins.iter() {
+                        plugin.event(PluginEvent::Locked);
+                    }
+                    if !self.flags.config.reduced_motion {
+                        self.lock_timeline_opt = Some(Timeline::new(LOCK_FADE_DURATION));
+                    }
                     for (output, surface_id) in self.surface_ids.iter() {
-                        commands.push(get_lock_surface(*surface_id, output.clone()));
+                        commands.push(self.create_surface(*surface_id, output.clone()));
                     }
                     return Command::batch(commands);
                 }
+                SessionLockEvent::Unlocked if self.flags.daemon => {
+                    log::info!("session unlocked, waiting to lock again");
+                    self.record_unlock();
+                    self.keyboard_layouts.restore();
+                    self.state = State::Unlocked;
+                    self.phase_span = tracing::info_span!(parent: None, "unlocked");
+                    panic_hook::locked(false);
+                    self.reset_lock();
+                    let paused_players = self.take_paused_players();
+                    let muted_sources = std::mem::take(&mut self.muted_sources);
+                    Command::batch(vec![
+                        self.restore_keyboard_backlight(),
+                        Command::perform(logind::set_locked_hint(false), |res| {
+                            if let Err(err) = res {
+                                log::warn!("failed to clear locked hint: {}", err);
+                            }
+                            message::app(Message::None)
+                        }),
+                        hook("unlock", &self.flags.config.on_unlock_opt),
+                        Command::perform(resume_media(paused_players), |()| {
+                            message::app(Message::None)
+                        }),
+                        Command::perform(pipewire::unmute_sources(muted_sources), |()| {
+                            message::app(Message::None)
+                        }),
+                    ])
+                }
                 SessionLockEvent::Unlocked => {
                     log::info!("session unlocked");
+                    self.record_unlock();
+                    self.keyboard_layouts.restore();
                     self.state = State::Unlocked;
-                    process::exit(0)
+                    panic_hook::locked(false);
+                    // Plugins are destroyed while their library is still loaded and the runtime
+                    // is alive, lock surfaces are already gone with the lock
+                    self.plugins.clear();
+                    self.surface_ids.clear();
+                    self.surface_sizes.clear();
+                    self.text_input_ids.clear();
+                    self.active_surface_id_opt = None;
+                    // Only this session was unlocked, clear its hint and run the hook before
+                    // shutting down, which would kill it
+                    let on_unlock_opt = self.flags.config.on_unlock_opt.clone();
+                    let paused_players = self.take_paused_players();
+                    let muted_sources = std::mem::take(&mut self.muted_sources);
+                    let keyboard_backlight_opt = self.keyboard_backlight_opt.take();
+                    Command::perform(
+                        async move {
+                            if let Some((keyboard_backlight, brightness)) = keyboard_backlight_opt {
+                                if let Err(err) = logind::set_brightness(
+                                    "leds",
+                                    keyboard_backlight.name,
+                                    brightness,
+                                )
+                                .await
+                                {
+                                    log::warn!("failed to set keyboard backlight: {}", err);
+                                }
+                            }
+                            if let Err(err) = logind::set_locked_hint(false).await {
+                                log::warn!("failed to clear locked hint: {}", err);
+                            }
+                            resume_media(paused_players).await;
+                            pipewire::unmute_sources(muted_sources).await;
+                            if let Some(on_unlock) = on_unlock_opt {
+                                hooks::run("unlock", on_unlock).await;
+                            }
+                        },
+                        |()| message::app(Message::Shutdown),
+                    )
+                }
+                SessionLockEvent::Finished => {
+                    // The compositor refused or revoked the lock, exiting here would leave the
+                    // session unprotected so keep trying to lock again
+                    log::warn!(
+                        "session lock finished, relocking (attempt {})",
+                        self.relock_attempts + 1
+                    );
+                    self.phase_span = tracing::info_span!(
+                        parent: None,
+                        "locking",
+                        relock = self.relock_attempts + 1
+                    );
+                    let mut commands = Vec::with_capacity(self.surface_ids.len() + 1);
+                    if matches!(self.state, State::Locked(_)) {
+                        for surface_id in self.surface_ids.values() {
+                            commands.push(destroy_lock_surface(*surface_id));
+                        }
+                    }
+                    self.state = State::Locking;
+                    // Any running conversation belongs to the old lock
+                    self.value_tx_opt = None;
+                    self.prompt_opt = None;
+
+                    let delay = RELOCK_DELAY
+                        .saturating_mul(2u32.saturating_pow(self.relock_attempts))
+                        .min(RELOCK_DELAY_MAX);
+                    self.relock_attempts = self.relock_attempts.saturating_add(1);
+                    commands.push(Command::perform(tokio::time::sleep(delay), |()| {
+                        message::app(Message::Relock)
+                    }));
+                    Command::batch(commands)
+                }
+                SessionLockEvent::NotSupported => {
+                    // Exclusive overlay surfaces hide the desktop and take the keyboard, but the
+                    // compositor won't keep the session locked if this process dies
+                    log::warn!("session lock not supported, falling back to layer shell surfaces");
+                    self.layer_shell = true;
+                    // No focus events come for layer surfaces
+                    self.active_surface_id_opt = self.surface_ids.values().next().copied();
+                    self.update(Message::SessionLockEvent(SessionLockEvent::Locked))
+                }
+                SessionLockEvent::Unfocused(_, surface_id) => {
+                    log::info!("unfocus surface {:?}", surface_id);
+                    if self.active_surface_id_opt == Some(surface_id) {
+                        self.unfocus();
+                    }
+                    Command::none()
                 }
-                SessionLockEvent::Finished => todo!(),
-                SessionLockEvent::NotSupported => todo!(),
-                SessionLockEvent::Unfocused(_, _) => todo!(),
-                //TODO: handle finished signal
             },
-            Message::None => todo!(),
-            Message::Unlock => unlock(),
-            Message::Tick => {
-                self.now = Local::now();
+            Message::None => Command::none(),
+            // Sent from the UI loop, so a hung renderer stops the heartbeat
+            Message::Watchdog => {
+                systemd::notify("WATCHDOG=1");
                 Command::none()
             }
-        }
+            Message::Shutdown => Command::batch(vec![
+                // Ends the event loop so run returns to main
+                iced::window::close(SurfaceId::MAIN),
+                Command::perform(tokio::time::sleep(EXIT_GRACE), |()| {
+                    message::app(Message::Exit)
+                }),
+            ]),
+            Message::Exit => {
+                log::warn!("event loop still running after unlock, exiting");
+                log::logger().flush();
+                process::exit(0)
+            }
+            Message::Render => iced::window::screenshot(SurfaceId::MAIN, |screenshot| {
+                message::app(Message::Rendered(screenshot))
+            }),
+            Message::Rendered(screenshot) => {
+                let Some(path) = &self.flags.render_opt else {
+                    return Command::none();
+                };
+                if let Err(err) = render::save(path, &screenshot) {
+                    log::error!("failed to write {}: {}", path.display(), err);
+                    log::logger().flush();
+                    process::exit(1)
+                }
+                log::info!("rendered the lock screen to {}", path.display());
+                self.update(Message::Shutdown)
+            }
+            Message::Relock => {
+                if matches!(self.state, State::Locking) {
+                    lock()
+                } else {
+                    Command::none()
+                }
+            }
+            Message::Unlock => {
+                self.state = State::Unlocking(None);
+                self.active_tx.send_replace(None);
+                self.phase_span = tracing::info_span!(parent: None, "unlocking");
+                for plugin in self.plugins.iter() {
+                    plugin.event(PluginEvent::Unlocking);
+                }
+                if self.flags.preview {
+                    return self.update(Message::Shutdown);
+                }
+                if self.flags.greeter {
+                    // greetd starts the session once the greeter has exited
+                    let mut commands = Vec::with_capacity(self.surface_ids.len() + 1);
+                    for surface_id in self.surface_ids.values() {
+                        commands.push(self.destroy_surface(*surface_id));
+                    }
+                    commands.push(self.update(Message::Shutdown));
+                    return Command::batch(commands);
+                }
+                if self.layer_shell {
+                    // There is no lock to release, so no Unlocked event either
+                    let mut commands = Vec::with_capacity(self.surface_ids.len() + 1);
+                    for surface_id in self.surface_ids.values() {
+                        commands.push(self.destroy_surface(*surface_id));
+                    }
+                    commands.push(Command::perform(async {}, |()| {
+                        message::app(Message::SessionLockEvent(SessionLockEvent::Unlocked))
+                    }));
+                    return Command::batch(commands);
+                }
+                unlock()
+            }
+            Message::LoadDeferred => {
+                self.backlight_opt = Backlight::load();
+                if let Some(brightness) = self
+                    .backlight_opt
+                    .as_ref()
+                    .and_then(|backlight| backlight.brightness())
+                {
+                    self.brightness = brightness;
+                }
+                self.keyboard_layouts = KeyboardLayouts::load();
+                self.system_info_opt = SystemInfo::load();
+                self.do_not_disturb_opt = notifications::do_not_disturb();
+                self.plugins = plugin::load(&self.flags.config.plugins);
+                // Locked before the plugins were loaded
+                if matches!(self.state, State::Locked(_))
+                    && !self.flags.preview
+                    && !self.flags.greeter
+                {
+                    for plugin in self.plugins.iter() {
+                        plugin.event(PluginEvent::Locked);
+                    }
+                }
+                log::info!(
+                    "loaded optional state {}ms after start",
+                    self.started.elapsed().as_millis()
+                );
+                if self.flags.greeter {
+                    return Command::perform(accounts::users(), |res| {
+                        let users = res.unwrap_or_else(|err| {
+                            log::info!("no user list: {}", err);
+                            Vec::new()
+                        });
+                        message::app(Message::Users(users))
+                    });
+                }
+                // Nothing to keep locked while someone else logs in
+                if self.flags.preview {
+                    return Command::none();
+                }
+                Command::perform(display_manager::available(), |res| {
+                    let available = res.unwrap_or_else(|err| {
+                        log::info!("no display manager to switch users with: {}", err);
+                        false
+                    });
+                    message::app(Message::SwitchUserAvailable(available))
+                })
+            }
+            Message::ClockChanged => {
+                self.clock_changes += 1;
+                self.now = Local::now();
+                // Plugins showing the time redraw on ticks
+                for plugin in self.plugins.iter() {
+                    plugin.event(PluginEvent::Tick);
+                }
+                Command::none()
+            }
+            Message::Tick => {
+                log::debug!("{} wakeups in the last minute", self.wakeups);
+                self.wakeups = 0;
+                self.now = Local::now();
+                for plugin in self.plugins.iter() {
+                    plugin.event(PluginEvent::Tick);
+                }
+                // Inhibitors may have gone away without any input since
+                Command::batch(vec![
+                    self.idle_lock(),
+                    self.load_unread(),
+                    self.load_other_users(),
+                ])
+            }
+            Message::Vpn(vpn_opt) => {
+                self.vpn_opt = vpn_opt;
+                Command::none()
+            }
+            Message::Bluetooth(bluetooth_opt) => {
+                self.bluetooth_opt = bluetooth_opt;
+                Command::none()
+            }
+            Message::Capture(capture) => {
+                if !capture.is_empty() {
+                    log::info!(
+                        "apps capturing, microphone: {:?}, camera: {:?}, screen: {:?}",
+                        capture.microphone,
+                        capture.camera,
+                        capture.screen
+                    );
+                }
+                self.capture = capture;
+                Command::none()
+            }
+            Message::BluetoothToggle => match &self.bluetooth_opt {
+                Some(info) => Command::perform(bluetooth::set_powered(!info.powered), |res| {
+                    if let Err(err) = res {
+                        log::warn!("failed to toggle bluetooth: {}", err);
+                    }
+                    message::app(Message::None)
+                }),
+                None => Command::none(),
+            },
+            Message::KeyPressed(key, modifiers, captured) => {
+                if let Some(command) = self.key_pressed(&key, modifiers) {
+                    return command;
+                }
+                let revealing = self.cover.activity();
+                if self.segments_input() {
+                    // Only a character carries over, Enter shouldn't submit an empty password
+                    if revealing && !matches!(key, Key::Character(_)) {
+                        return Command::none();
+                    }
+                    return self.typed(&key, modifiers);
+                }
+                if revealing {
+                    // The key that reveals the prompt starts the password, typed like any other
+                    // key so the typing indicator counts it
+                    let command = match key {
+                        Key::Character(_) => self.typed(&key, modifiers),
+                        _ => Command::none(),
+                    };
+                    return Command::batch([command, self.focus_prompt()]);
+                }
+                if !captured {
+                    // Focus sits on a surface without the text input, keep the single password
+                    // buffer and move the input focus to it
+                    let command = self.typed(&key, modifiers);
+                    return Command::batch(vec![command, self.focus_prompt()]);
+                }
+                Command::none()
+            }
+            Message::Pointer => {
+                if self.cover.activity() {
+                    return self.focus_prompt();
+                }
+                Command::none()
+            }
+            Message::PointerMoved => {
+                self.cursor_hidden = false;
+                self.pointer_moved_at = Instant::now();
+                Command::none()
+            }
+            Message::IdleTimeout => {
+                if matches!(self.state, State::Locked(Activity::Active))
+                    && self.input_at.elapsed()
+                        >= Duration::from_secs(self.flags.config.idle_timeout)
+                {
+                    log::debug!("idle");
+                    self.state = State::Locked(Activity::Idle);
+                }
+                Command::none()
+            }
+            Message::DisplayOffTimeout => {
+                let Some(timeout) = self.flags.config.display_off_timeout_opt else {
+                    return Command::none();
+                };
+                if matches!(self.state, State::Locked(Activity::Active | Activity::Idle))
+                    && self.input_at.elapsed() >= dim_after(timeout)
+                {
+                    if self.flags.config.reduced_motion {
+                        return self.display_off();
+                    }
+                    log::debug!("dimming displays");
+                    self.state = State::Locked(Activity::Dimming(Timeline::new(DIM_DURATION)));
+                }
+                Command::none()
+            }
+            Message::SuspendWarning => {
+                let Some(timeout) = self.flags.config.suspend_timeout_opt else {
+                    return Command::none();
+                };
+                if !matches!(self.state, State::Locked(_))
+                    || self.suspend_warning_at(timeout) > Instant::now()
+                {
+                    return Command::none();
+                }
+                match self.suspend_excluded() {
+                    Some(reason) => {
+                        // Try again after another timeout
+                        log::debug!("not suspending {}", reason);
+                        self.suspend_checked_at = Instant::now();
+                    }
+                    None => self.suspend_warning = true,
+                }
+                Command::none()
+            }
+            // Redraws the countdown
+            Message::SuspendCountdown => Command::none(),
+            Message::SuspendTimeout => {
+                let Some(timeout) = self.flags.config.suspend_timeout_opt else {
+                    return Command::none();
+                };
+                if !self.suspend_warning || self.suspend_at(timeout) > Instant::now() {
+                    return Command::none();
+                }
+                self.suspend_warning = false;
+                self.suspend_checked_at = Instant::now();
+                // Plugged in or started playing during the countdown
+                if let Some(reason) = self.suspend_excluded() {
+                    log::debug!("not suspending {}", reason);
+                    return Command::none();
+                }
+                log::info!("suspending after {}s locked without input", timeout);
+                Command::perform(logind::auto_suspend(), |res| {
+                    match res {
+                        Ok(true) => {}
+                        Ok(false) => log::info!("not suspending, sleep is inhibited"),
+                        Err(err) => log::warn!("failed to suspend: {}", err),
+                    }
+                    message::app(Message::None)
+                })
+            }
+            Message::GrabTimeout => {
+                if matches!(self.state, State::Locked(_))
+                    && self.active_surface_id_opt.is_none()
+                    && self.unfocused_at.elapsed() >= GRAB_TIMEOUT
+                {
+                    // Keys would go to whatever has keyboard focus instead of the prompt
+                    log::error!("no lock surface has keyboard focus, hiding the prompt");
+                    self.grab_failed = true;
+                }
+                Command::none()
+            }
+            Message::Resized(surface_id, width, height) => {
+                // Late configures for surfaces that were already replaced or destroyed
+                if !self.text_input_ids.contains_key(&surface_id) {
+                    log::debug!("surface {:?}: ignoring stale configure", surface_id);
+                    return Command::none();
+                }
+                log::debug!("surface {:?}: configured {}x{}", surface_id, width, height);
+                self.surface_sizes.insert(surface_id, (width, height));
+                self.release_sleep_inhibitor()
+            }
+            Message::OutputPower(name, on) => {
+                log::info!("output {}: powered {}", name, if on { "on" } else { "off" });
+                if on {
+                    self.powered_off.remove(&name);
+                } else {
+                    self.powered_off.insert(name);
+                }
+                Command::none()
+            }
+            Message::Signal(SignalEvent::Terminate) => self.terminate(),
+            Message::Signal(SignalEvent::ToggleDebugOverlay) => {
+                self.flags.config.debug_overlay = !self.flags.config.debug_overlay;
+                Command::none()
+            }
+            // Every update is followed by a redraw
+            Message::Signal(SignalEvent::Redraw) => Command::none(),
+            Message::ScreenSaver(ScreenSaverEvent::Lock) => self.lock_requested("D-Bus"),
+            Message::ScreenSaver(ScreenSaverEvent::Inhibitors(inhibitors)) => {
+                self.screensaver_inhibitors = inhibitors;
+                Command::none()
+            }
+            Message::LogindSession(true) => self.lock_requested("logind"),
+            Message::StaleLock => {
+                self.recovering_lock = true;
+                self.lock_requested("locked hint of a previous locker")
+            }
+            // When logind suspends for the lid too, the sleep inhibitor holds it off until drawn
+            Message::LidClosed(true) => {
+                // Whoever opens the lid again has to type the password
+                self.grace.end();
+                match self.flags.config.lock_on_lid {
+                    LockOnLid::Always => self.lock_requested(grace::LID),
+                    LockOnLid::OnBattery if !power_supply::on_ac() => {
+                        self.lock_requested(grace::LID)
+                    }
+                    LockOnLid::OnBattery | LockOnLid::Never => Command::none(),
+                }
+            }
+            // The compositor holds off idle itself while a surface inhibits it, logind
+            // inhibitors are only known to whoever asks
+            Message::IdleNotify(idle) => {
+                self.session_idle = idle;
+                if idle {
+                    return self.idle_lock();
+                }
+                Command::none()
+            }
+            Message::IdleInhibitors(inhibitors) => {
+                if !self.session_idle {
+                    return Command::none();
+                }
+                if inhibitors.is_empty() {
+                    return self.lock_requested("idle timeout");
+                }
+                for inhibitor in inhibitors {
+                    log::info!("not locking on idle, inhibited by {}", inhibitor);
+                }
+                Command::none()
+            }
+            Message::LidClosed(false) => Command::none(),
+            // loginctl unlock-session, needs the same privileges as killing the locker
+            Message::LogindSession(false) => self.admin_unlock("logind"),
+            Message::SessionActive(false) => {
+                log::info!("switched away from the session while {:?}", self.state);
+                self.session_active = false;
+                Command::none()
+            }
+            Message::SessionActive(true) => {
+                log::info!("switched back to the session while {:?}", self.state);
+                self.session_active = true;
+                if !matches!(self.state, State::Locked(_)) {
+                    return Command::none();
+                }
+                // Compositors should keep the lock surfaces through the switch, any that lost
+                // their configure are replaced so nothing of the desktop shows through
+                let unconfigured: Vec<_> = self
+                    .surface_ids
+                    .iter()
+                    .filter(|(_, surface_id)| !self.surface_sizes.contains_key(surface_id))
+                    .map(|(output, _)| output.clone())
+                    .collect();
+                let mut commands = Vec::with_capacity(unconfigured.len() + 1);
+                for output in unconfigured {
+                    log::warn!(
+                        "output {}: lock surface not configured after switching back",
+                        output.id()
+                    );
+                    commands.extend(self.recreate_surface(output));
+                }
+                // Keyboard focus comes back with a focus event, the grab timeout starts over
+                // since it couldn't arrive while switched away
+                if self.active_surface_id_opt.is_none() {
+                    log::info!("no keyboard focus yet after switching back");
+                    self.unfocused_at = Instant::now();
+                    self.grab_failed = false;
+                }
+                commands.push(self.focus_prompt());
+                Command::batch(commands)
+            }
+            Message::Control(ControlEvent::Lock(profile_opt)) => {
+                let mut commands = Vec::with_capacity(2);
+                // Kept for the locks after this one, until a request names another profile
+                if profile_opt.is_some() && profile_opt != self.flags.config_source.profile_opt {
+                    if matches!(self.state, State::Unlocked) {
+                        self.flags.config_source.profile_opt = profile_opt;
+                        commands.push(self.reload_config());
+                    } else {
+                        log::info!("already locked, not switching to profile {:?}", profile_opt);
+                    }
+                }
+                commands.push(self.lock_requested("control socket"));
+                Command::batch(commands)
+            }
+            Message::Control(ControlEvent::Unlock) => self.admin_unlock("control socket"),
+            Message::Control(ControlEvent::Status(reply_tx)) => {
+                let _ = reply_tx.try_send(self.status());
+                Command::none()
+            }
+            Message::Control(ControlEvent::Stats(reply_tx)) => {
+                let _ = reply_tx.try_send(self.stats.report());
+                Command::none()
+            }
+            Message::ScreenSaver(ScreenSaverEvent::WakeUp) => {
+                self.input().unwrap_or_else(Command::none)
+            }
+            Message::Sleep(true) => {
+                log::info!("preparing for sleep");
+                self.sleep_pending = true;
+                // Whoever wakes the system has to type the password
+                self.grace.end();
+                Command::batch(vec![
+                    self.lock_requested(grace::SLEEP),
+                    self.release_sleep_inhibitor(),
+                ])
+            }
+            Message::SleepInhibitor(inhibitor_opt) => {
+                self.sleep_inhibitor_opt = inhibitor_opt;
+                self.release_sleep_inhibitor()
+            }
+            Message::ReleaseSleepInhibitor => {
+                if self.sleep_pending && self.sleep_inhibitor_opt.take().is_some() {
+                    log::info!("lock screen drawn, letting the system sleep");
+                }
+                Command::none()
+            }
+            Message::Sleep(false) => {
+                log::info!("resumed from sleep");
+                // Timers and the minute tick are behind by the time spent asleep
+                self.now = Local::now();
+                self.suspend_warning = false;
+                self.sleep_pending = false;
+                let mut commands = Vec::with_capacity(self.surface_ids.len() * 2 + 2);
+                commands.push(self.inhibit_sleep());
+                if let Some(command) = self.input() {
+                    commands.push(command);
+                }
+                // Buffers from before the suspend are often stale or gone, and outputs that
+                // changed while asleep may not have been seen yet
+                if matches!(self.state, State::Locked(_)) {
+                    commands.extend(self.recreate_surfaces());
+                }
+                Command::batch(commands)
+            }
+            Message::CursorTimeout => {
+                if self.pointer_moved_at.elapsed()
+                    >= Duration::from_secs(self.flags.config.cursor_timeout)
+                {
+                    self.cursor_hidden = true;
+                }
+                Command::none()
+            }
+            Message::Touch(event) if tap_to_wake => {
+                if self.gestures.touch(event) == Some(Gesture::DoubleTap) {
+                    log::info!("double tap, waking up");
+                    return self.input().unwrap_or_else(Command::none);
+                }
+                Command::none()
+            }
+            Message::Touch(event) => {
+                let mut commands = Vec::with_capacity(2);
+                if let Some(Gesture::Pressed(serial)) = self.gestures.touch(event) {
+                    commands.push(Command::perform(
+                        tokio::time::sleep(gesture::LONG_PRESS),
+                        move |()| message::app(Message::LongPress(serial)),
+                    ));
+                }
+                if self.cover.touch(event) {
+                    commands.push(self.focus_prompt());
+                }
+                Command::batch(commands)
+            }
+            Message::LongPress(serial) => {
+                if self.gestures.long_press(serial) && matches!(self.state, State::Locked(_)) {
+                    log::info!("long press, opening the power menu");
+                    self.power_menu = true;
+                    self.power_menu_touch = true;
+                    self.power_confirm_opt = None;
+                }
+                Command::none()
+            }
+            Message::Contact(contact, bounds) => {
+                let gesture_opt = self.gestures.contact(
+                    contact,
+                    bounds,
+                    self.flags.config.hot_corner_opt,
+                    self.flags.config.edge_swipe_opt,
+                );
+                match gesture_opt {
+                    Some(Gesture::Cornered(serial)) => {
+                        Command::perform(tokio::time::sleep(gesture::HOT_CORNER_DWELL), move |()| {
+                            message::app(Message::HotCorner(serial))
+                        })
+                    }
+                    Some(Gesture::EdgeSwipe) if matches!(self.state, State::Locked(_)) => {
+                        log::info!("edge swipe, opening the power menu");
+                        self.power_menu = true;
+                        self.power_menu_touch = true;
+                        self.power_confirm_opt = None;
+                        Command::none()
+                    }
+                    _ => Command::none(),
+                }
+            }
+            Message::HotCorner(serial) => {
+                if self.gestures.hot_corner(serial) && matches!(self.state, State::Locked(_)) {
+                    log::info!("pointer in the hot corner, opening the power menu");
+                    self.power_menu = true;
+                    self.power_menu_touch = false;
+                    self.power_confirm_opt = None;
+                }
+                Command::none()
+            }
+            Message::Frame => {
+                self.frame_time = self.frame_at.elapsed();
+                self.frame_at = Instant::now();
+                self.cover.frame();
+                self.typing.frame();
+                if self
+                    .lock_timeline_opt
+                    .map_or(false, |timeline| timeline.is_done())
+                {
+                    self.lock_timeline_opt = None;
+                }
+                match self.state {
+                    State::Locked(Activity::Dimming(timeline)) if timeline.is_done() => {
+                        return self.display_off();
+                    }
+                    State::Unlocking(Some(timeline)) if timeline.is_done() => {
+                        return self.update(Message::Unlock);
+                    }
+                    _ => {}
+                }
+                Command::none()
+            }
+            Message::CoverTimeout => {
+                if self.cover.check_timeout() {
+                    // Don't leave a half typed password behind the cover
+                    if let Some(prompt) = &mut self.prompt_opt {
+                        prompt.value.clear();
+                    }
+                }
+                Command::none()
+            }
+            Message::Theme(theme) => cosmic::app::command::set_theme(theme),
+            Message::ConfigChanged => self.reload_config(),
+            Message::CycleLayout => {
+                self.keyboard_layouts.cycle();
+                Command::none()
+            }
+            Message::Media(media_opt) => {
+                let art_url_opt = media_opt.as_ref().and_then(|media| media.art_url.clone());
+                self.media_opt = media_opt;
+                match art_url_opt {
+                    Some(art_url) if !self.art_cache.contains_key(&art_url) => {
+                        if self.art_cache.len() >= ART_CACHE_SIZE {
+                            self.art_cache.clear();
+                        }
+                        // Reserve the entry so the art is only loaded once
+                        self.art_cache.insert(art_url.clone(), None);
+                        Command::perform(mpris::load_art(art_url.clone()), move |handle_opt| {
+                            message::app(Message::AlbumArt(art_url.clone(), handle_opt))
+                        })
+                    }
+                    _ => Command::none(),
+                }
+            }
+            Message::AlbumArt(art_url, handle_opt) => {
+                self.art_cache.insert(art_url, handle_opt);
+                Command::none()
+            }
+            Message::Notification(event) => {
+                match event {
+                    NotificationEvent::Received(notification) => {
+                        self.notification_count += 1;
+                        let privacy = self
+                            .flags
+                            .config
+                            .notification_privacy(&notification.app_name);
+                        let counted = self.flags.config.unread.iter().any(|counter| {
+                            matches!(
+                                &counter.source,
+                                UnreadSource::Notifications(app_name)
+                                    if app_name == &notification.app_name
+                            )
+                        });
+                        if counted {
+                            let unread = self
+                                .unread_notifications
+                                .entry(notification.app_name.clone())
+                                .or_default();
+                            unread.count += 1;
+                            // Hidden ones are counted without their summary
+                            if privacy != NotificationPrivacy::Hide {
+                                unread.latest_opt = Some(notification.summary.clone());
+                            }
+                        }
+                        if self.flags.config.notification_previews
+                            && privacy != NotificationPrivacy::Hide
+                        {
+                            if self.notifications.len() >= NOTIFICATION_PREVIEWS {
+                                self.notifications.remove(0);
+                            }
+                            self.notifications.push(notification);
+                        }
+                    }
+                    NotificationEvent::Closed => {
+                        self.notification_count = self.notification_count.saturating_sub(1);
+                    }
+                }
+                Command::none()
+            }
+            Message::PowerMenu(power_menu) => {
+                self.power_menu = power_menu;
+                self.power_menu_touch = false;
+                self.power_confirm_opt = None;
+                Command::none()
+            }
+            Message::PowerConfirm(power_confirm_opt) => {
+                self.power_confirm_opt = power_confirm_opt;
+                Command::none()
+            }
+            Message::PowerAction(action) => {
+                self.power_menu = false;
+                self.power_confirm_opt = None;
+                Command::perform(logind::power_action(action), move |res| {
+                    if let Err(err) = res {
+                        log::warn!("failed to {:?}: {}", action, err);
+                    }
+                    message::app(Message::None)
+                })
+            }
+            Message::Users(users) => {
+                self.users = users;
+                Command::none()
+            }
+            // Answers the username prompt, as if it had been typed
+            Message::User(name) => {
+                if self.username_opt.is_some() {
+                    return Command::none();
+                }
+                if let Some(prompt) = &mut self.prompt_opt {
+                    prompt.value = name;
+                    return self.update(Message::Submit);
+                }
+                Command::none()
+            }
+            Message::SwitchUserAvailable(available) => {
+                self.switch_user_available = available;
+                Command::none()
+            }
+            Message::SwitchUser => {
+                self.power_menu = false;
+                // The greeter takes over the VT, this session stays locked behind it
+                Command::perform(display_manager::switch_user(), |res| {
+                    if let Err(err) = res {
+                        log::warn!("failed to switch user: {}", err);
+                    }
+                    message::app(Message::None)
+                })
+            }
+            Message::Brightness(brightness) => match &self.backlight_opt {
+                Some(backlight) => {
+                    self.brightness = brightness.clamp(1.0, 100.0);
+                    let raw = backlight.raw_brightness(self.brightness);
+                    Command::perform(
+                        logind::set_brightness("backlight", backlight.name.clone(), raw),
+                        |res| {
+                            if let Err(err) = res {
+                                log::warn!("failed to set brightness: {}", err);
+                            }
+                            message::app(Message::None)
+                        },
+                    )
+                }
+                None => Command::none(),
+            },
+            Message::Volume(action) => Command::perform(pipewire::volume(action), move |res| {
+                if let Err(err) = res {
+                    log::warn!("failed to change volume ({:?}): {}", action, err);
+                }
+                message::app(Message::None)
+            }),
+            // Media keys work even when the media controls are hidden
+            Message::MediaPaused(paused) => {
+                self.paused_players = paused;
+                Command::none()
+            }
+            Message::SourcesMuted(muted) => {
+                log::info!("muted {} capture sources", muted.len());
+                self.muted_sources = muted;
+                Command::none()
+            }
+            Message::Alarms(alarms) => {
+                self.alarms = alarms;
+                Command::none()
+            }
+            Message::ClockTimers(clock_timers) => {
+                self.clock_timers = clock_timers;
+                Command::none()
+            }
+            Message::TimerCountdown => Command::none(),
+            Message::ClockSecond => {
+                self.now = Local::now();
+                Command::none()
+            }
+            Message::Unread(unread) => {
+                self.unread = unread;
+                Command::none()
+            }
+            Message::OtherUsers(other_users) => {
+                self.other_users = other_users;
+                Command::none()
+            }
+            Message::WallpaperMotion(now) => {
+                self.motion.frame(now);
+                Command::none()
+            }
+            Message::Frosted(id, frosted_opt) => {
+                if let Some(frosted) = frosted_opt {
+                    self.frosted.insert(id, frosted);
+                }
+                Command::none()
+            }
+            Message::Peripherals(peripherals) => {
+                self.peripherals = peripherals;
+                Command::none()
+            }
+            Message::Ups(ups_opt) => {
+                self.ups_opt = ups_opt;
+                let Some(ups) = &self.ups_opt else {
+                    return Command::none();
+                };
+                if !ups.on_battery {
+                    self.ups_acted = false;
+                    return Command::none();
+                }
+                let Some(action) = self.flags.config.ups_critical_action_opt else {
+                    return Command::none();
+                };
+                let critical =
+                    Duration::from_secs(self.flags.config.ups_critical_minutes.saturating_mul(60));
+                // Without an estimate the UPS shuts down on its own when empty
+                let Some(time_to_empty) = ups.time_to_empty_opt.filter(|time| *time <= critical)
+                else {
+                    return Command::none();
+                };
+                if self.ups_acted || self.flags.preview || !matches!(self.state, State::Locked(_)) {
+                    return Command::none();
+                }
+                self.ups_acted = true;
+                log::warn!(
+                    "UPS has {}s left on battery, {:?}",
+                    time_to_empty.as_secs(),
+                    action
+                );
+                Command::perform(logind::power_action(action), move |res| {
+                    if let Err(err) = res {
+                        log::warn!("failed to {:?}: {}", action, err);
+                    }
+                    message::app(Message::None)
+                })
+            }
+            Message::AmbientLight(lux) => {
+                log::debug!("ambient light {} lux", lux);
+                self.ambient_lux_opt = Some(lux);
+                Command::none()
+            }
+            Message::Updates(updates) => {
+                log::info!("{:?}", updates);
+                self.updates = updates;
+                Command::none()
+            }
+            Message::ClearClipboard(locked_at) => {
+                // Unlocking in the meantime leaves the clipboard alone
+                if !matches!(self.state, State::Locked(_)) || self.locked_at_opt != Some(locked_at)
+                {
+                    return Command::none();
+                }
+                clear_clipboard()
+            }
+            Message::MediaKey(action) => Command::perform(mpris::control_current(action), |res| {
+                if let Err(err) = res {
+                    log::warn!("failed to control media player: {}", err);
+                }
+                message::app(Message::None)
+            }),
+            Message::PowerProfile(power_profile_opt) => {
+                self.power_profile_opt = power_profile_opt;
+                Command::none()
+            }
+            Message::SetPowerProfile(profile) => {
+                Command::perform(power_profiles::set_active_profile(profile), |res| {
+                    if let Err(err) = res {
+                        log::warn!("failed to set power profile: {}", err);
+                    }
+                    message::app(Message::None)
+                })
+            }
+            Message::Auth(event) => match event {
+                AuthEvent::Channel(value_tx) => {
+                    self.value_tx_opt = Some(value_tx);
+                    // A new conversation, greetd asks for the username again
+                    self.username_opt = None;
+                    self.prompt_steps = 0;
+                    Command::none()
+                }
+                AuthEvent::Username(username) => {
+                    // Start where this user left off
+                    let last_opt = sessions::last(&username);
+                    if let Some(index) = self
+                        .sessions
+                        .iter()
+                        .position(|session| last_opt.as_deref() == Some(session.name.as_str()))
+                    {
+                        self.select_session(index);
+                    }
+                    self.username_opt = Some(username);
+                    // The user's own conversation starts after the username
+                    self.prompt_steps = 0;
+                    Command::none()
+                }
+                AuthEvent::Prompt(text, secret) => {
+                    self.prompt_steps += 1;
+                    log::info!(
+                        "prompt {} {}",
+                        self.prompt_steps,
+                        if secret { "without echo" } else { "with echo" }
+                    );
+                    self.prompt_opt = Some(Prompt {
+                        text,
+                        secret,
+                        value: String::new(),
+                        step: self.prompt_steps,
+                    });
+                    self.focus_prompt()
+                }
+                AuthEvent::Info(info) => {
+                    self.info_opt = Some(info);
+                    Command::none()
+                }
+                AuthEvent::Error(error) => {
+                    self.error_opt = Some(error);
+                    Command::none()
+                }
+                AuthEvent::Failure(error) => {
+                    self.error_opt = Some(error);
+                    self.failed_attempts.push(Local::now());
+                    self.stats.failed();
+                    hook("fail", &self.flags.config.on_fail_opt)
+                }
+                AuthEvent::Success => {
+                    self.prompt_opt = None;
+                    self.error_opt = None;
+                    if let (Some(username), Some(session)) =
+                        (&self.username_opt, self.sessions.get(self.session_index))
+                    {
+                        sessions::remember(username, &session.name);
+                    }
+                    if self.flags.config.reduced_motion {
+                        return self.update(Message::Unlock);
+                    }
+                    self.state = State::Unlocking(Some(Timeline::new(UNLOCK_FADE_DURATION)));
+                    self.phase_span = tracing::info_span!(parent: None, "unlocking");
+                    Command::none()
+                }
+            },
+            Message::Fingerprint(event) => match event {
+                FingerprintEvent::Ready => {
+                    self.fingerprint_ready = true;
+                    Command::none()
+                }
+                FingerprintEvent::NoMatch => {
+                    self.error_opt = Some(crate::fl!("fingerprint-no-match"));
+                    self.failed_attempts.push(Local::now());
+                    self.stats.failed();
+                    hook("fail", &self.flags.config.on_fail_opt)
+                }
+                FingerprintEvent::Match => {
+                    self.fingerprint_ready = false;
+                    // fprintd only knows the finger, the PAM service still decides whether the
+                    // account may log in, the password prompt stays up meanwhile
+                    Command::perform(
+                        auth::account(
+                            self.flags.config.pam_service.clone(),
+                            self.flags.current_user.name.clone(),
+                        ),
+                        |res| message::app(Message::FingerprintAccount(res)),
+                    )
+                }
+                FingerprintEvent::Stopped => {
+                    self.fingerprint_ready = false;
+                    Command::none()
+                }
+            },
+            // The password may have unlocked in the meantime
+            Message::FingerprintAccount(_) if !matches!(self.state, State::Locked(_)) => {
+                Command::none()
+            }
+            Message::FingerprintAccount(Ok(())) => {
+                // Ends the PAM conversation waiting on the password, its subscription is
+                // dropped with the state change
+                self.value_tx_opt = None;
+                self.update(Message::Auth(AuthEvent::Success))
+            }
+            Message::FingerprintAccount(Err(error)) => {
+                log::warn!(
+                    "fingerprint matched, but the account check failed: {}",
+                    error
+                );
+                self.update(Message::Auth(AuthEvent::Failure(error)))
+            }
+            Message::Session(index) => {
+                self.select_session(index);
+                Command::none()
+            }
+            Message::Input(value) => {
+                self.cover.activity();
+                if let Some(prompt) = &mut self.prompt_opt {
+                    prompt.value = value;
+                }
+                Command::none()
+            }
+            Message::Submit => match (self.prompt_opt.take(), &self.value_tx_opt) {
+                (Some(prompt), Some(value_tx)) => {
+                    self.info_opt = None;
+                    self.error_opt = None;
+                    let value_tx = value_tx.clone();
+                    Command::perform(
+                        async move {
+                            if let Err(err) = value_tx.send(prompt.value).await {
+                                log::warn!("failed to send prompt value: {}", err);
+                            }
+                        },
+                        |()| message::app(Message::None),
+                    )
+                }
+                (prompt_opt, _) => {
+                    self.prompt_opt = prompt_opt;
+                    Command::none()
+                }
+            },
+            Message::Osk(key) => match self.osk.press(key) {
+                Some(OskKey::Char(c)) => {
+                    if let Some(prompt) = &mut self.prompt_opt {
+                        prompt.value.push(c);
+                        self.typing.keystroke();
+                    }
+                    Command::none()
+                }
+                Some(OskKey::Space) => {
+                    if let Some(prompt) = &mut self.prompt_opt {
+                        prompt.value.push(' ');
+                        self.typing.keystroke();
+                    }
+                    Command::none()
+                }
+                Some(OskKey::Backspace) => {
+                    if let Some(prompt) = &mut self.prompt_opt {
+                        prompt.value.pop();
+                        self.typing.erase();
+                    }
+                    Command::none()
+                }
+                Some(OskKey::Enter) => self.update(Message::Submit),
+                _ => Command::none(),
+            },
+            Message::ToggleOsk => {
+                self.osk.visible = !self.osk.visible;
+                Command::none()
+            }
+            Message::Gamepad(event) => {
+                // The first press only reveals the prompt, like a key
+                if self.cover.activity() {
+                    return self.focus_prompt();
+                }
+                match event {
+                    GamepadEvent::Move(direction) => {
+                        self.osk.select(direction);
+                        Command::none()
+                    }
+                    GamepadEvent::Press => match self.osk.selected() {
+                        Some(key) if self.osk.visible => self.update(Message::Osk(key)),
+                        _ => {
+                            self.osk.show_selection();
+                            Command::none()
+                        }
+                    },
+                    GamepadEvent::Backspace => self.update(Message::Osk(OskKey::Backspace)),
+                    GamepadEvent::Shift => self.update(Message::Osk(OskKey::Shift)),
+                    GamepadEvent::Symbols => self.update(Message::Osk(OskKey::Symbols)),
+                    GamepadEvent::ToggleOsk => self.update(Message::ToggleOsk),
+                    GamepadEvent::Submit => self.update(Message::Submit),
+                }
+            }
+            Message::ToggleDoNotDisturb => {
+                if let Some(do_not_disturb) = self.do_not_disturb_opt {
+                    if notifications::set_do_not_disturb(!do_not_disturb) {
+                        self.do_not_disturb_opt = Some(!do_not_disturb);
+                    }
+                }
+                Command::none()
+            }
+            Message::MediaControl(action) => match &self.media_opt {
+                Some(media) => {
+                    Command::perform(mpris::control(media.bus_name.clone(), action), |res| {
+                        if let Err(err) = res {
+                            log::warn!("failed to control media player: {}", err);
+                        }
+                        message::app(Message::None)
+                    })
+                }
+                None => Command::none(),
+            },
+        }
     }
 
+    /// Only used by --preview, otherwise there is no main window
     fn view(&self) -> cosmic::prelude::Element<Self::Message> {
-        unimplemented!()
+        self.view_window(SurfaceId::MAIN)
     }
 
     fn view_window(&self, surface_id: SurfaceId) -> Element<Self::Message> {
-        let date_time_column = {
-            let mut column = widget::column::with_capacity::<Message>(1).padding(10);
-
-            //TODO: localized format
-            let date = self.now.format("%b %e %-I:%M %p");
-            column = column.push(
-                widget::text::text(format!("{}", date))
-                    .style(style::Text::Default)
-                    .size(18)
-                    .font(FONT_BOLD),
-            );
-
-            column
+        if !self.is_powered(surface_id) {
+            // Nothing to lay out or draw, a lid-closed laptop only renders its external displays
+            return widget::Space::new(iced::Length::Fill, iced::Length::Fill).into();
+        }
+        let portrait =
+            self.output_mode() == OutputMode::Independent && self.is_portrait(surface_id);
+        let output_config_opt = self.output_config(surface_id);
+        let content = output_config_opt.map_or(OutputContent::Full, |config| config.content);
+        let layout = output_config_opt
+            .and_then(|config| config.layout_opt.as_ref())
+            .unwrap_or(&self.flags.config.layout);
+        let widget_fn = |kind| match (content, kind) {
+            (OutputContent::Full, _)
+            | (
+                OutputContent::Clock,
+                WidgetKind::Clock | WidgetKind::AwayFor | WidgetKind::Message,
+            ) => self.view_widget(kind, surface_id),
+            _ => None,
         };
-
-        let centered = cosmic::widget::container(date_time_column)
-            .width(iced::Length::Fill)
-            .height(iced::Length::Shrink)
-            .align_x(iced::alignment::Horizontal::Center)
-            .align_y(iced::alignment::Vertical::Top);
-
-        Element::from(centered)
+        let dim = match self.state {
+            State::Locked(Activity::Dimming(timeline)) => timeline.linear(),
+            // Stays black until the displays have turned on
+            State::Locked(Activity::DisplayOff) => 1.0,
+            State::Unlocking(Some(timeline)) => timeline.progress(),
+            State::Unlocking(None) => 1.0,
+            _ => self
+                .lock_timeline_opt
+                .map_or(0.0, |timeline| 1.0 - timeline.progress()),
+        };
+        let dim = match self.ambient_lux_opt {
+            Some(lux) if self.flags.config.ambient_light => dim.max(ambient_light::dim(lux)),
+            _ => dim,
+        };
+        let mut content = self.wallpaper(surface_id, layout.view(&widget_fn, portrait));
+        if let Some(banner) = self.suspend_banner() {
+            content = widget::column::with_children(vec![banner, content]).into();
+        }
+        if let Some(banner) = self.screencast_banner() {
+            content = widget::column::with_children(vec![banner, content]).into();
+        }
+        if let Some(banner) = self.config_error_banner() {
+            content = widget::column::with_children(vec![banner, content]).into();
+        }
+        if self.flags.config.debug_overlay {
+            content =
+                widget::column::with_children(vec![self.debug_overlay(surface_id), content]).into();
+        }
+        let tint_opt = self
+            .flags
+            .config
+            .night_light_at(self.now.time())
+            .and_then(dimmer::night_light_tint);
+        let mut cursor_area = CursorArea::new(
+            Dimmer::new(content)
+                .tint(tint_opt)
+                .level(dim)
+                .asleep(self.is_asleep()),
+        )
+        .hidden(self.cursor_hidden);
+        // Every pointer move is a message then, only worth it when something listens
+        if self.flags.config.hot_corner_opt.is_some() || self.flags.config.edge_swipe_opt.is_some()
+        {
+            cursor_area = cursor_area.on_contact(Message::Contact);
+        }
+        cursor_area.into()
     }
 
     fn subscription(&self) -> iced::Subscription<Self::Message> {
         let mut subscriptions = Vec::with_capacity(7);
 
-        subscriptions.push(event::listen_with(|event, _| match event {
+        subscriptions.push(event::listen_with(|event, status| match event {
             iced::Event::PlatformSpecific(iced::event::PlatformSpecific::Wayland(
                 wayland_event,
             )) => match wayland_event {
@@ -215,20 +2905,245 @@ impl cosmic::Application for App {
                 WaylandEvent::SessionLock(evt) => Some(Message::SessionLockEvent(evt)),
                 _ => None,
             },
+            iced::Event::Keyboard(iced::keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+                Some(Message::KeyPressed(
+                    key,
+                    modifiers,
+                    status == event::Status::Captured,
+                ))
+            }
+            iced::Event::Mouse(mouse::Event::ButtonPressed(_)) => Some(Message::Pointer),
+            iced::Event::Mouse(mouse::Event::CursorMoved { .. }) => Some(Message::PointerMoved),
+            iced::Event::Touch(touch_event) => Some(Message::Touch(touch_event)),
+            iced::Event::Window(surface_id, iced::window::Event::Resized { width, height }) => {
+                Some(Message::Resized(surface_id, width, height))
+            }
             _ => None,
         }));
 
-        // Unlocks automatically for testing purpose
-        subscriptions.push(time_subscription(10).map(|_| Message::Unlock));
-        subscriptions.push(time_subscription(60).map(|_| Message::Tick));
+        if matches!(self.state, State::Locked(_)) {
+            if self.flags.greeter {
+                subscriptions
+                    .push(greetd::subscription(self.session_tx.subscribe()).map(Message::Auth));
+            } else {
+                subscriptions.push(
+                    auth::subscription(
+                        self.flags.config.pam_service.clone(),
+                        Unlockers {
+                            owner: self.flags.current_user.name.clone(),
+                            users: self.flags.config.unlock_users.clone(),
+                            groups: self.flags.config.unlock_groups.clone(),
+                        },
+                    )
+                    .map(Message::Auth),
+                );
+                // Waits alongside the password, whichever succeeds first unlocks and the other
+                // goes away with the state change
+                if self.flags.config.fingerprint {
+                    subscriptions.push(
+                        fprintd::subscription(self.flags.current_user.name.clone())
+                            .map(Message::Fingerprint),
+                    );
+                }
+            }
+        }
+        subscriptions.push(signals::subscription().map(Message::Signal));
+        if !self.flags.preview && !self.flags.greeter {
+            subscriptions.push(control::subscription().map(Message::Control));
+            subscriptions.push(
+                screensaver::subscription(
+                    self.active_tx.subscribe(),
+                    self.flags.config.gnome_screensaver,
+                )
+                .map(Message::ScreenSaver),
+            );
+        }
+        subscriptions.push(timer::minutes(("tick-sub", self.clock_changes)).map(|_| Message::Tick));
+        subscriptions.push(timer::clock_changes().map(|()| Message::ClockChanged));
+        subscriptions.push(
+            config::subscription(self.flags.config_source.path_opt.clone())
+                .map(|()| Message::ConfigChanged),
+        );
+        if let Some(interval) = self.watchdog_interval_opt {
+            subscriptions
+                .push(timer::every("watchdog-sub", interval / 2).map(|_| Message::Watchdog));
+        }
+        if matches!(self.state, State::Locked(Activity::Active)) {
+            let deadline = self.input_at + Duration::from_secs(self.flags.config.idle_timeout);
+            subscriptions.push(
+                timer::deadline("idle-sub", self.started, deadline).map(|_| Message::IdleTimeout),
+            );
+        }
+        if let Some(timeout) = self.flags.config.display_off_timeout_opt {
+            if matches!(self.state, State::Locked(Activity::Active | Activity::Idle)) {
+                let deadline = self.input_at + dim_after(timeout);
+                subscriptions.push(
+                    timer::deadline("display-off-sub", self.started, deadline)
+                        .map(|_| Message::DisplayOffTimeout),
+                );
+            }
+        }
+        if matches!(self.state, State::Locked(_))
+            && self.active_surface_id_opt.is_none()
+            && !self.grab_failed
+            && self.session_active
+        {
+            subscriptions.push(
+                timer::deadline("grab-sub", self.started, self.unfocused_at + GRAB_TIMEOUT)
+                    .map(|_| Message::GrabTimeout),
+            );
+        }
+        let suspend_timeout_opt = self
+            .flags
+            .config
+            .suspend_timeout_opt
+            .filter(|_| matches!(self.state, State::Locked(_)));
+        if let Some(timeout) = suspend_timeout_opt {
+            if self.suspend_warning {
+                subscriptions.push(
+                    timer::every("suspend-countdown-sub", Duration::from_secs(1))
+                        .map(|_| Message::SuspendCountdown),
+                );
+                subscriptions.push(
+                    timer::deadline("suspend-sub", self.started, self.suspend_at(timeout))
+                        .map(|_| Message::SuspendTimeout),
+                );
+            } else {
+                subscriptions.push(
+                    timer::deadline(
+                        "suspend-warning-sub",
+                        self.started,
+                        self.suspend_warning_at(timeout),
+                    )
+                    .map(|_| Message::SuspendWarning),
+                );
+            }
+        }
+        let any_powered = self
+            .surface_ids
+            .values()
+            .any(|surface_id| self.is_powered(*surface_id));
+        let dimming = matches!(
+            self.state,
+            State::Locked(Activity::Dimming(_)) | State::Unlocking(Some(_))
+        );
+        if dimming
+            || self.lock_timeline_opt.is_some()
+            || (!self.is_idle()
+                && any_powered
+                && (self.cover.animating() || self.typing.animating()))
+        {
+            subscriptions.push(animation::frames().map(|_| Message::Frame));
+        }
+        if self.wallpaper_moving() && any_powered {
+            subscriptions.push(motion::frames().map(Message::WallpaperMotion));
+        }
+        if !self.cursor_hidden {
+            let deadline =
+                self.pointer_moved_at + Duration::from_secs(self.flags.config.cursor_timeout);
+            subscriptions.push(
+                timer::deadline("cursor-sub", self.started, deadline)
+                    .map(|_| Message::CursorTimeout),
+            );
+        }
+        if let Some(deadline) = self.cover.deadline() {
+            subscriptions.push(
+                timer::deadline("cover-sub", self.started, deadline).map(|_| Message::CoverTimeout),
+            );
+        }
+        // Only what the lock screen shows, an unlocked daemon doesn't watch it all day
+        let locked = !matches!(self.state, State::Unlocked);
+        if locked {
+            subscriptions.push(networkmanager::subscription().map(Message::Vpn));
+            subscriptions.push(bluetooth::subscription().map(Message::Bluetooth));
+            subscriptions.push(power_profiles::subscription().map(Message::PowerProfile));
+        }
+        // Polled, so not while idle, they send the current state again on resume
+        if locked && !self.is_idle() {
+            subscriptions.push(upower::peripheral_subscription().map(Message::Peripherals));
+            if !self.flags.greeter {
+                subscriptions.push(pipewire::capture_subscription().map(Message::Capture));
+                subscriptions.push(kclock::subscription().map(Message::ClockTimers));
+            }
+            if self.clock_seconds {
+                subscriptions.push(
+                    timer::every("clock-second-sub", Duration::from_secs(1))
+                        .map(|_| Message::ClockSecond),
+                );
+            }
+            if self.clock_timers.iter().any(|timer| timer.running) {
+                subscriptions.push(
+                    timer::every("timer-countdown-sub", Duration::from_secs(1))
+                        .map(|_| Message::TimerCountdown),
+                );
+            }
+        }
+        if self.flags.config.ambient_light && matches!(self.state, State::Locked(_)) {
+            subscriptions.push(ambient_light::subscription().map(Message::AmbientLight));
+        }
+        // Games get the controllers back once unlocked
+        if !matches!(self.state, State::Unlocked) {
+            // Not paused while idle, the critical action is for when nobody is around
+            subscriptions.push(upower::ups_subscription().map(Message::Ups));
+            subscriptions.push(gamepad::subscription().map(Message::Gamepad));
+            // Counts what arrived since locking, a new monitor forgets the ids of the last lock
+            subscriptions.push(notifications::subscription().map(Message::Notification));
+        }
+        if self.flags.config.pending_updates && locked {
+            subscriptions.push(packagekit::subscription().map(Message::Updates));
+        }
+        subscriptions.push(logind::sleep_subscription().map(Message::Sleep));
+        if !self.flags.preview && !self.flags.greeter {
+            subscriptions.push(logind::session_subscription().map(Message::LogindSession));
+            subscriptions.push(logind::active_subscription().map(Message::SessionActive));
+            if let (true, Some(timeout)) = (self.flags.daemon, self.flags.config.lock_timeout_opt) {
+                subscriptions.push(
+                    idle_notify::subscription(Duration::from_secs(timeout))
+                        .map(Message::IdleNotify),
+                );
+            }
+            if self.flags.config.lock_on_lid != LockOnLid::Never {
+                subscriptions.push(logind::lid_subscription().map(Message::LidClosed));
+            }
+        }
+        subscriptions
+            .push(output_power::subscription().map(|(name, on)| Message::OutputPower(name, on)));
+        // High contrast replaces the desktop theme
+        if !self.flags.config.high_contrast {
+            subscriptions.push(desktop_theme::subscription().map(Message::Theme));
+        }
+        if cfg!(feature = "mpris") && self.flags.config.media_controls && locked && !self.is_idle()
+        {
+            subscriptions.push(mpris::subscription().map(Message::Media));
+        }
 
         Subscription::batch(subscriptions)
     }
 }
 
-fn time_subscription(secs: u64) -> Subscription<()> {
-    subscription::unfold("time-sub", (), move |()| async move {
-        tokio::time::sleep(Duration::from_secs(secs)).await;
-        ((), ())
+fn keyboard_brightness(name: String, brightness: u32) -> Command<Message> {
+    Command::perform(logind::set_brightness("leds", name, brightness), |res| {
+        if let Err(err) = res {
+            log::warn!("failed to set keyboard backlight: {}", err);
+        }
+        message::app(Message::None)
+    })
+}
+
+fn display_power(on: bool) -> Command<Message> {
+    Command::perform(output_power::set_power(on), move |res| {
+        if let Err(err) = res {
+            log::warn!(
+                "failed to turn displays {}: {}",
+                if on { "on" } else { "off" },
+                err
+            );
+        }
+        message::app(Message::None)
     })
 }
+
+// When dimming starts, timeouts shorter than the fade dim right away
+fn dim_after(display_off_timeout: u64) -> Duration {
+    Duration::from_secs(display_off_timeout).saturating_sub(DIM_DURATION)
+}
@@ -0,0 +1,1171 @@
+use cosmic::{
+    font::FONT_BOLD,
+    iced::{self, alignment, ContentFit, Length},
+    iced_runtime::core::window::Id as SurfaceId,
+    style, widget, Element,
+};
+
+use super::{App, Message};
+use crate::{
+    alarms, ambient_light,
+    config::{
+        BackgroundScaling, NotificationPrivacy, OutputMode, Rgb, Status, TypingFeedback,
+        UnreadSource, WidgetStyle,
+    },
+    fl,
+    frosted::Frosted,
+    image_container::ImageContainer,
+    layout::WidgetKind,
+    logind::PowerAction,
+    mpris::MediaAction,
+    networkmanager::VpnState,
+    output, power_profiles,
+    unread::Unread,
+    upower,
+    wallpaper::Wallpaper,
+};
+
+fn uptime(duration: chrono::Duration) -> String {
+    let hours = duration.num_hours() % 24;
+    let minutes = duration.num_minutes() % 60;
+    match duration.num_days() {
+        0 => fl!("uptime-hours", hours = hours, minutes = minutes),
+        days => fl!("uptime-days", days = days, hours = hours),
+    }
+}
+
+fn away_for(duration: chrono::Duration) -> String {
+    let hours = duration.num_hours();
+    let minutes = duration.num_minutes() % 60;
+    match (hours, minutes) {
+        (0, 0) => fl!("away-for-less-than-a-minute"),
+        (0, minutes) => fl!("away-for-minutes", minutes = minutes),
+        (hours, minutes) => fl!("away-for-hours", hours = hours, minutes = minutes),
+    }
+}
+
+// Ctrl++ on the lock screen, about the step from normal to large text in accessibility settings
+const LARGE_TEXT_SCALE: f32 = 1.5;
+
+// The desktop theme's card color without its alpha, so no wallpaper shows through
+fn opaque_card(theme: &cosmic::Theme) -> iced::widget::container::Appearance {
+    let cosmic = theme.cosmic();
+    let mut background: iced::Color = cosmic.background.component.base.into();
+    background.a = 1.0;
+    iced::widget::container::Appearance {
+        text_color: Some(cosmic.background.component.on.into()),
+        background: Some(iced::Background::Color(background)),
+        border: iced::Border {
+            radius: cosmic.corner_radii.radius_m.into(),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+// Light enough over the blur that the wallpaper's colors come through
+const FROSTED_TINT: f32 = 0.35;
+
+// The card color tinting a frosted card. The blur behind it is clipped square, so the corners are
+// only rounded slightly
+fn frosted_card(theme: &cosmic::Theme) -> iced::widget::container::Appearance {
+    let cosmic = theme.cosmic();
+    let mut background: iced::Color = cosmic.background.component.base.into();
+    background.a = FROSTED_TINT;
+    iced::widget::container::Appearance {
+        text_color: Some(cosmic.background.component.on.into()),
+        background: Some(iced::Background::Color(background)),
+        border: iced::Border {
+            radius: cosmic.corner_radii.radius_s.into(),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+fn color(Rgb([r, g, b]): Rgb) -> iced::Color {
+    iced::Color::from_rgb(r, g, b)
+}
+
+fn widget_style(theme: &cosmic::Theme, style: WidgetStyle) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        text_color: style.text_opt.map(color),
+        background: style.background_opt.map(|rgb| {
+            iced::Background::Color(iced::Color {
+                a: style.opacity,
+                ..color(rgb)
+            })
+        }),
+        border: iced::Border {
+            color: style
+                .border_opt
+                .map_or_else(|| theme.cosmic().accent_color().into(), color),
+            width: style.border_width,
+            radius: style.radius.into(),
+        },
+        ..Default::default()
+    }
+}
+
+fn icon_label<'a>(icon: &'static str, label: String) -> widget::Row<'a, Message> {
+    widget::row::with_children(vec![
+        widget::icon::from_name(icon).into(),
+        widget::text(label).into(),
+    ])
+    .spacing(6)
+    .align_items(alignment::Alignment::Center)
+}
+
+impl App {
+    /// The widget in its `[styles]` box, if the config has one for it
+    pub(super) fn view_widget(
+        &self,
+        kind: WidgetKind,
+        surface_id: SurfaceId,
+    ) -> Option<Element<Message>> {
+        let content = self.widget_content(kind, surface_id)?;
+        let Some(style) = self.flags.config.styles.get(&kind).copied() else {
+            return Some(content);
+        };
+        Some(
+            widget::container(content)
+                .padding(style.padding)
+                .style(cosmic::theme::Container::custom(move |theme| {
+                    widget_style(theme, style)
+                }))
+                .into(),
+        )
+    }
+
+    fn widget_content(&self, kind: WidgetKind, surface_id: SurfaceId) -> Option<Element<Message>> {
+        match kind {
+            WidgetKind::Clock => Some(self.backdrop(surface_id, self.clock(surface_id))),
+            WidgetKind::AwayFor => {
+                let locked_at = self.locked_at_opt?;
+                Some(
+                    widget::text(away_for(self.now - locked_at))
+                        .size(14.0 * self.text_scale(surface_id))
+                        .into(),
+                )
+            }
+            WidgetKind::FailedAttempts => {
+                let first = self.failed_attempts.first()?;
+                Some(
+                    widget::text(fl!(
+                        "failed-attempts",
+                        count = self.failed_attempts.len(),
+                        time = first.format("%H:%M").to_string()
+                    ))
+                    .style(self.status_style(Status::Warning))
+                    .size(14.0 * self.text_scale(surface_id))
+                    .into(),
+                )
+            }
+            WidgetKind::Timers => self.clock_timers(surface_id),
+            WidgetKind::Message => {
+                let message = self.flags.message_opt.as_ref()?;
+                Some(
+                    widget::text(message.clone())
+                        .style(style::Text::Accent)
+                        .size(28.0 * self.text_scale(surface_id))
+                        .font(FONT_BOLD)
+                        .into(),
+                )
+            }
+            WidgetKind::Status => Some(self.status()),
+            WidgetKind::Notifications => self.notifications(),
+            WidgetKind::Unread => self.unread(),
+            WidgetKind::Media => self.media(),
+            WidgetKind::Brightness => self.brightness(),
+            WidgetKind::Power => Some(self.power_menu()),
+            WidgetKind::Plugins => self.plugins(),
+            WidgetKind::SystemInfo => self.system_info(surface_id),
+            WidgetKind::Prompt if self.grab_failed => Some(
+                widget::text(fl!("no-keyboard-input"))
+                    .style(self.status_style(Status::Error))
+                    .into(),
+            ),
+            // One prompt on the focused output, the others only show the clock and status
+            WidgetKind::Prompt if !self.is_focused(surface_id) => None,
+            WidgetKind::Prompt => Some(self.backdrop(surface_id, self.prompt(surface_id))),
+            WidgetKind::Keyboard => {
+                if self.osk.visible
+                    && self.cover.revealed()
+                    && self.prompt_opt.is_some()
+                    && self.is_focused(surface_id)
+                {
+                    Some(self.osk.view(Message::Osk))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn icon_button(&self, icon: &'static str) -> widget::Button<Message> {
+        let button =
+            widget::button(widget::icon::from_name(icon)).style(cosmic::theme::Button::Icon);
+        if self.flags.config.high_contrast {
+            // Larger hit target
+            button.padding(16)
+        } else {
+            button
+        }
+    }
+
+    // Finger sized when the power menu was opened by a long press
+    fn touch_sized<'a>(&self, button: widget::Button<'a, Message>) -> widget::Button<'a, Message> {
+        if self.power_menu_touch {
+            button.padding([16, 24])
+        } else {
+            button
+        }
+    }
+
+    fn prompt(&self, surface_id: SurfaceId) -> Element<Message> {
+        if !self.cover.revealed() {
+            return widget::text(fl!("cover-hint")).into();
+        }
+
+        let mut column = widget::column::with_capacity(7)
+            .spacing(8)
+            .align_items(alignment::Alignment::Center);
+
+        // The greeter user is nobody to show, the username is asked for instead
+        if self.flags.greeter {
+            if let Some(user_list) = self.user_list() {
+                column = column.push(user_list);
+            }
+        } else {
+            column = column.push(
+                widget::text(self.flags.current_user.name.clone())
+                    .size(18.0 * self.text_scale(surface_id))
+                    .font(FONT_BOLD),
+            );
+
+            if let Some(secret) = self.secret() {
+                column = column.push(secret);
+            }
+        }
+
+        if let Some(prompt) = &self.prompt_opt {
+            // After the password, "Verification code:" is a question of its own, not a hint
+            let label_opt = (prompt.step > 1).then(|| prompt.text.trim().to_string());
+            if let Some(label) = &label_opt {
+                column = column.push(
+                    widget::text(fl!("prompt-step", step = prompt.step))
+                        .size(12.0 * self.text_scale(surface_id)),
+                );
+                column = column.push(widget::text(label.clone()).font(FONT_BOLD));
+            }
+            let placeholder = if label_opt.is_some() {
+                String::new()
+            } else {
+                prompt.text.clone()
+            };
+            // Answers shown while typed are codes and names, not passwords
+            let icon = if prompt.secret {
+                "system-lock-screen-symbolic"
+            } else {
+                "dialog-question-symbolic"
+            };
+            let input: Element<Message> = if self.segments_input() {
+                // Keys are handled by the app, a text input would show a dot per character
+                let mut segments = widget::column::with_capacity(2);
+                if !placeholder.is_empty() {
+                    segments = segments.push(widget::text(placeholder));
+                }
+                if self.flags.config.typing_feedback == TypingFeedback::Segments {
+                    segments = segments.push(self.typing.view());
+                }
+                segments
+                    .spacing(8)
+                    .align_items(alignment::Alignment::Center)
+                    .into()
+            } else {
+                let mut text_input = widget::text_input(placeholder, prompt.value.clone())
+                    .leading_icon(widget::icon::from_name(icon).into())
+                    .on_input(Message::Input)
+                    .on_submit(Message::Submit)
+                    .width(Length::Fixed(320.0 * self.text_scale(surface_id)));
+                if let Some(text_input_id) = self.text_input_ids.get(&surface_id) {
+                    text_input = text_input.id(text_input_id.clone());
+                }
+                if prompt.secret {
+                    text_input = text_input.password();
+                }
+                text_input.into()
+            };
+
+            let mut input_row = widget::row::with_capacity(3)
+                .push(input)
+                .spacing(8)
+                .align_items(alignment::Alignment::Center);
+            // What the typed password comes out as, switched with a click or Super+Space
+            if let Some(layout) = self.keyboard_layouts.active() {
+                let label = widget::text(layout).size(12.0 * self.text_scale(surface_id));
+                input_row = if self.keyboard_layouts.can_cycle() {
+                    input_row.push(
+                        widget::button(label)
+                            .style(cosmic::theme::Button::Text)
+                            .on_press(Message::CycleLayout),
+                    )
+                } else {
+                    input_row.push(label)
+                };
+            }
+            column = column.push(
+                input_row.push(
+                    self.icon_button("input-keyboard-symbolic")
+                        .on_press(Message::ToggleOsk),
+                ),
+            );
+        }
+
+        // greetd only needs it once authenticated, so it can be changed until then
+        if self.flags.greeter && self.sessions.len() > 1 {
+            column = column.push(widget::dropdown(
+                &self.sessions,
+                Some(self.session_index),
+                Message::Session,
+            ));
+        }
+
+        if let Some(info) = &self.info_opt {
+            column = column.push(widget::text(info.clone()));
+        }
+        if self.fingerprint_ready {
+            column = column.push(widget::text(crate::fl!("fingerprint-hint")));
+        }
+        if let Some(error) = &self.error_opt {
+            column =
+                column.push(widget::text(error.clone()).style(self.status_style(Status::Error)));
+        }
+
+        // Slides in from below while the cover animates away
+        widget::container(column)
+            .padding([self.cover.offset() as u16, 0, 0, 0])
+            .into()
+    }
+
+    /// Users to pick instead of typing a username, hidden accounts can still be typed in
+    fn user_list(&self) -> Option<Element<Message>> {
+        if self.users.is_empty() || self.username_opt.is_some() {
+            return None;
+        }
+        let mut row = widget::row::with_capacity(self.users.len()).spacing(16);
+        for user in self.users.iter() {
+            let avatar: Element<Message> = match &user.icon_opt {
+                Some(icon) => ImageContainer::new(iced::widget::container(widget::Space::new(
+                    Length::Fixed(64.0),
+                    Length::Fixed(64.0),
+                )))
+                .image(icon.clone())
+                .content_fit(ContentFit::Cover)
+                .into(),
+                None => widget::icon::from_name("avatar-default-symbolic")
+                    .size(64)
+                    .into(),
+            };
+            let mut column = widget::column::with_capacity(3)
+                .spacing(4)
+                .align_items(alignment::Alignment::Center)
+                .push(avatar)
+                .push(widget::text(user.real_name.clone()).font(FONT_BOLD));
+            if let Some(login_time) = user.login_time_opt {
+                column = column.push(
+                    widget::text(fl!(
+                        "last-login",
+                        date = login_time.format("%b %-d").to_string()
+                    ))
+                    .size(12),
+                );
+            }
+            row = row.push(
+                widget::button(column)
+                    .style(cosmic::theme::Button::Text)
+                    .on_press(Message::User(user.name.clone())),
+            );
+        }
+        Some(row.into())
+    }
+
+    /// Only the real locker knows the secret, and it only shows it while holding the session lock
+    fn secret(&self) -> Option<Element<Message>> {
+        if self.layer_shell {
+            return None;
+        }
+        let mut row = widget::row::with_capacity(2)
+            .spacing(8)
+            .align_items(alignment::Alignment::Center);
+        if let Some(image) = &self.secret_image_opt {
+            row = row.push(
+                ImageContainer::new(iced::widget::container(widget::Space::new(
+                    Length::Fixed(48.0),
+                    Length::Fixed(48.0),
+                )))
+                .image(image.clone())
+                .content_fit(ContentFit::Cover),
+            );
+        }
+        if let Some(phrase) = &self.flags.config.secret_phrase_opt {
+            row = row.push(widget::text(phrase.clone()).style(style::Text::Accent));
+        }
+        if self.secret_image_opt.is_none() && self.flags.config.secret_phrase_opt.is_none() {
+            return None;
+        }
+        Some(row.into())
+    }
+
+    fn plugins(&self) -> Option<Element<Message>> {
+        let mut row = widget::row::with_capacity(self.plugins.len()).spacing(12);
+        let mut empty = true;
+        for plugin in self.plugins.iter() {
+            let Some(text) = plugin.text() else {
+                continue;
+            };
+            empty = false;
+            match plugin.icon() {
+                Some(icon) => {
+                    row = row.push(
+                        widget::row::with_children(vec![
+                            widget::icon::from_name(icon).into(),
+                            widget::text(text).into(),
+                        ])
+                        .spacing(6)
+                        .align_items(alignment::Alignment::Center),
+                    );
+                }
+                None => row = row.push(widget::text(text)),
+            }
+        }
+        if empty {
+            None
+        } else {
+            Some(row.into())
+        }
+    }
+
+    fn clock(&self, surface_id: SurfaceId) -> Element<Message> {
+        let text_scale = self.text_scale(surface_id);
+        let clock_format = if self.clock_seconds {
+            // "%-I:%M %p" becomes "%-I:%M:%S %p", formats without minutes stay as they are
+            self.flags.config.clock_format.replacen("%M", "%M:%S", 1)
+        } else {
+            self.flags.config.clock_format.clone()
+        };
+        let date = self.now.format(&clock_format);
+        let clock = widget::text::text(format!("{}", date))
+            .style(style::Text::Default)
+            .size(18.0 * text_scale)
+            .font(FONT_BOLD);
+        let Some(next_alarm) = alarms::next(&self.alarms, self.now) else {
+            return clock.into();
+        };
+        // The weekday tells tomorrow morning's alarm apart from one in a week
+        let time = next_alarm.format("%a %-I:%M %p").to_string();
+        widget::column::with_capacity(2)
+            .push(clock)
+            .push(widget::text(fl!("next-alarm", time = time)).size(12.0 * text_scale))
+            .align_items(alignment::Alignment::Center)
+            .into()
+    }
+
+    fn clock_timers(&self, surface_id: SurfaceId) -> Option<Element<Message>> {
+        if self.clock_timers.is_empty() {
+            return None;
+        }
+        let mut column = widget::column::with_capacity(self.clock_timers.len())
+            .spacing(4)
+            .align_items(alignment::Alignment::Center);
+        for clock_timer in self.clock_timers.iter() {
+            let remaining = clock_timer.remaining().as_secs();
+            let label = if clock_timer.label.is_empty() {
+                fl!("timer")
+            } else {
+                clock_timer.label.clone()
+            };
+            let remaining = format!("{}:{:02}", remaining / 60, remaining % 60);
+            let text = if clock_timer.running {
+                fl!("timer-running", label = label, remaining = remaining)
+            } else {
+                fl!("timer-paused", label = label, remaining = remaining)
+            };
+            column = column.push(
+                widget::row::with_children(vec![
+                    widget::icon::from_name("alarm-symbolic").into(),
+                    widget::text(text)
+                        .size(14.0 * self.text_scale(surface_id))
+                        .into(),
+                ])
+                .spacing(6)
+                .align_items(alignment::Alignment::Center),
+            );
+        }
+        Some(column.into())
+    }
+
+    fn system_info(&self, surface_id: SurfaceId) -> Option<Element<Message>> {
+        let system_info = self.system_info_opt.as_ref()?;
+        let size = 12.0 * self.text_scale(surface_id);
+        let mut column = widget::column::with_capacity(3)
+            .push(widget::text(format!("{} · {}", system_info.host, system_info.kernel)).size(size))
+            .push(widget::text(uptime(self.now - system_info.booted_at)).size(size))
+            .align_items(alignment::Alignment::Center);
+        if system_info.reboot_required {
+            column = column.push(
+                widget::text(fl!("reboot-required"))
+                    .style(self.status_style(Status::Warning))
+                    .size(size),
+            );
+        }
+        Some(column.into())
+    }
+
+    /// Grows with the output size when outputs are composed independently, times the configured
+    /// multipliers
+    fn status_style(&self, status: Status) -> style::Text {
+        style::Text::Color(color(self.flags.config.status_color(status)))
+    }
+
+    // Reduced transparency, or sunlight washing out the wallpaper behind the text
+    fn opaque(&self) -> bool {
+        self.flags.config.reduced_transparency
+            || (self.flags.config.ambient_light
+                && self.ambient_lux_opt.map_or(false, ambient_light::sunlight))
+    }
+
+    fn card(&self) -> cosmic::theme::Container {
+        if self.opaque() {
+            cosmic::theme::Container::custom(opaque_card)
+        } else {
+            cosmic::theme::Container::Card
+        }
+    }
+
+    // Opaque or frosted, otherwise the clock and prompt sit on the wallpaper
+    fn backdrop<'a>(
+        &'a self,
+        surface_id: SurfaceId,
+        content: Element<'a, Message>,
+    ) -> Element<'a, Message> {
+        if self.opaque() {
+            return widget::container(content)
+                .padding(16)
+                .style(cosmic::theme::Container::custom(opaque_card))
+                .into();
+        }
+        if !self.flags.config.frosted_card {
+            return content;
+        }
+        let card = iced::widget::container(content)
+            .padding(self.flags.config.frosted_padding)
+            .style(cosmic::theme::Container::custom(frosted_card));
+        match self.frosted(surface_id) {
+            Some((frosted, content_fit)) => self
+                .moving(ImageContainer::new(card))
+                .image(frosted.handle.clone())
+                .content_fit(content_fit)
+                .behind(frosted.scale)
+                .into(),
+            None => card.into(),
+        }
+    }
+
+    // The wallpaper's pan and zoom, the same for its frosted copy so the two line up
+    fn moving<'a>(
+        &self,
+        container: ImageContainer<'a, Message, cosmic::Theme, cosmic::Renderer>,
+    ) -> ImageContainer<'a, Message, cosmic::Theme, cosmic::Renderer> {
+        if self.wallpaper_motion() {
+            container.motion(self.motion.zoom(), self.motion.pan())
+        } else {
+            container
+        }
+    }
+
+    // How an image wallpaper is drawn on this surface's output, its own background_scaling wins
+    fn scaling(
+        &self,
+        surface_id: SurfaceId,
+        content_fit: ContentFit,
+        tile: bool,
+    ) -> (ContentFit, bool) {
+        match self
+            .output_config(surface_id)
+            .and_then(|config| config.background_scaling_opt)
+        {
+            Some(scaling) => (scaling.content_fit(), scaling == BackgroundScaling::Tile),
+            None => (content_fit, tile),
+        }
+    }
+
+    // The blurred wallpaper of this surface's output and how it's fitted, tiled ones aren't
+    fn frosted(&self, surface_id: SurfaceId) -> Option<(&Frosted, ContentFit)> {
+        let output_name = self
+            .output_infos
+            .get(&surface_id)
+            .map(output::name)
+            .unwrap_or_default();
+        let Some(Wallpaper::Image {
+            handle,
+            content_fit,
+            tile,
+            ..
+        }) = self.wallpapers.get(&output_name)
+        else {
+            return None;
+        };
+        let (content_fit, tile) = self.scaling(surface_id, *content_fit, *tile);
+        if tile {
+            return None;
+        }
+        Some((self.frosted.get(&handle.id())?, content_fit))
+    }
+
+    fn text_scale(&self, surface_id: SurfaceId) -> f32 {
+        let mut scale = self.flags.config.text_scale;
+        if self.flags.config.large_text {
+            scale *= LARGE_TEXT_SCALE;
+        }
+        if self.output_mode() == OutputMode::Independent {
+            if let Some(info) = self.output_infos.get(&surface_id) {
+                scale *= output::text_scale(info);
+            }
+        }
+        if let Some(output_config) = self.output_config(surface_id) {
+            scale *= output_config.text_scale;
+        }
+        // Negative or zero sizes from a broken config would hide the text
+        scale.max(0.25)
+    }
+
+    fn status(&self) -> Element<Message> {
+        let mut status_row = widget::row::with_capacity(11 + self.peripherals.len()).spacing(12);
+        // A forgotten call keeps going while the user is away
+        let capture = [
+            (
+                "audio-input-microphone-symbolic",
+                &self.capture.microphone,
+                fl!(
+                    "microphone-in-use",
+                    apps = self.capture.microphone.join(", ")
+                ),
+            ),
+            (
+                "camera-web-symbolic",
+                &self.capture.camera,
+                fl!("camera-in-use", apps = self.capture.camera.join(", ")),
+            ),
+        ];
+        for (icon, apps, label) in capture {
+            if apps.is_empty() {
+                continue;
+            }
+            status_row = status_row.push(
+                widget::row::with_children(vec![
+                    widget::icon::from_name(icon).into(),
+                    widget::text(label)
+                        .style(self.status_style(Status::Error))
+                        .into(),
+                ])
+                .spacing(6)
+                .align_items(alignment::Alignment::Center),
+            );
+        }
+        match &self.vpn_opt {
+            Some(VpnState::Connected(name)) => {
+                status_row = status_row.push(
+                    widget::row::with_children(vec![
+                        widget::icon::from_name("network-vpn-symbolic").into(),
+                        widget::text(fl!("vpn-connected", name = name.clone()))
+                            .style(self.status_style(Status::Success))
+                            .into(),
+                    ])
+                    .spacing(6)
+                    .align_items(alignment::Alignment::Center),
+                );
+            }
+            Some(VpnState::Disconnected) => {
+                status_row = status_row.push(
+                    widget::row::with_children(vec![
+                        widget::icon::from_name("network-vpn-disconnected-symbolic").into(),
+                        widget::text(fl!("vpn-disconnected"))
+                            .style(self.status_style(Status::Warning))
+                            .into(),
+                    ])
+                    .spacing(6),
+                );
+            }
+            None => {}
+        }
+        if let Some(info) = &self.bluetooth_opt {
+            let (icon, label) = if info.powered {
+                let label = match info.connected {
+                    0 => fl!("bluetooth-on"),
+                    count => fl!("bluetooth-devices", count = count),
+                };
+                ("bluetooth-active-symbolic", label)
+            } else {
+                ("bluetooth-disabled-symbolic", fl!("bluetooth-off"))
+            };
+            let bluetooth_row = icon_label(icon, label);
+            if self.flags.config.bluetooth_toggle {
+                status_row = status_row.push(
+                    widget::button(bluetooth_row)
+                        .style(cosmic::theme::Button::Text)
+                        .on_press(Message::BluetoothToggle),
+                );
+            } else {
+                status_row = status_row.push(bluetooth_row);
+            }
+        }
+        // A dying keyboard is better noticed before typing the password
+        for peripheral in self.peripherals.iter() {
+            let percentage = peripheral.percentage.round() as u32;
+            let label = if peripheral.model.is_empty() {
+                format!("{}%", percentage)
+            } else {
+                fl!(
+                    "peripheral-battery",
+                    model = peripheral.model.clone(),
+                    percentage = percentage
+                )
+            };
+            let mut text = widget::text(label);
+            if peripheral.percentage <= upower::LOW_PERCENTAGE {
+                text = text.style(self.status_style(Status::Warning));
+            }
+            status_row = status_row.push(
+                widget::row::with_children(vec![
+                    widget::icon::from_name(peripheral.kind.icon()).into(),
+                    text.into(),
+                ])
+                .spacing(6)
+                .align_items(alignment::Alignment::Center),
+            );
+        }
+        if let Some(ups) = self.ups_opt.as_ref().filter(|ups| ups.on_battery) {
+            let label = match ups.time_to_empty_opt {
+                Some(time_to_empty) => fl!(
+                    "ups-on-battery-minutes",
+                    minutes = time_to_empty.as_secs() / 60
+                ),
+                None => fl!("ups-on-battery", percentage = ups.percentage.round() as u32),
+            };
+            status_row = status_row.push(
+                widget::row::with_children(vec![
+                    widget::icon::from_name("battery-caution-symbolic").into(),
+                    widget::text(label)
+                        .style(self.status_style(Status::Warning))
+                        .into(),
+                ])
+                .spacing(6)
+                .align_items(alignment::Alignment::Center),
+            );
+        }
+        if self.notification_count > 0 {
+            let label = fl!("notifications", count = self.notification_count);
+            status_row = status_row.push(icon_label(
+                "preferences-system-notifications-symbolic",
+                label,
+            ));
+        }
+        if self.updates.count > 0 {
+            let label = fl!("updates-pending", count = self.updates.count);
+            status_row = status_row.push(icon_label("software-update-available-symbolic", label));
+        }
+        if self.updates.reboot_required {
+            status_row = status_row.push(
+                widget::row::with_children(vec![
+                    widget::icon::from_name("system-reboot-symbolic").into(),
+                    widget::text(fl!("updates-reboot-required"))
+                        .style(self.status_style(Status::Warning))
+                        .into(),
+                ])
+                .spacing(6)
+                .align_items(alignment::Alignment::Center),
+            );
+        }
+        if let Some(info) = &self.power_profile_opt {
+            let mut button = widget::button(icon_label(
+                power_profiles::icon(&info.active),
+                power_profiles::label(&info.active),
+            ))
+            .style(cosmic::theme::Button::Text);
+            if let Some(next) = info.next() {
+                button = button.on_press(Message::SetPowerProfile(next.to_string()));
+            }
+            status_row = status_row.push(button);
+        }
+        if let Some(do_not_disturb) = self.do_not_disturb_opt {
+            let (icon, label) = if do_not_disturb {
+                ("notification-disabled-symbolic", fl!("do-not-disturb"))
+            } else {
+                (
+                    "preferences-system-notifications-symbolic",
+                    fl!("notifications-on"),
+                )
+            };
+            status_row = status_row.push(
+                widget::button(icon_label(icon, label))
+                    .style(cosmic::theme::Button::Text)
+                    .on_press(Message::ToggleDoNotDisturb),
+            );
+        }
+        status_row.into()
+    }
+
+    fn notifications(&self) -> Option<Element<Message>> {
+        if self.notifications.is_empty() {
+            return None;
+        }
+
+        let mut notification_column =
+            widget::column::with_capacity(self.notifications.len()).spacing(8);
+        // Newest first
+        for notification in self.notifications.iter().rev() {
+            let mut card = widget::column::with_capacity(2);
+            card = card.push(
+                widget::text(format!(
+                    "{}: {}",
+                    notification.app_name, notification.summary
+                ))
+                .font(FONT_BOLD),
+            );
+            if self
+                .flags
+                .config
+                .notification_privacy(&notification.app_name)
+                == NotificationPrivacy::Show
+                && !notification.body.is_empty()
+            {
+                card = card.push(widget::text(notification.body.clone()));
+            }
+            notification_column = notification_column.push(card);
+        }
+        Some(
+            widget::scrollable(notification_column)
+                .height(Length::Fixed(200.0))
+                .into(),
+        )
+    }
+
+    fn unread(&self) -> Option<Element<Message>> {
+        let mut column = widget::column::with_capacity(self.flags.config.unread.len()).spacing(4);
+        let mut empty = true;
+        for (index, counter) in self.flags.config.unread.iter().enumerate() {
+            let unread_opt = match &counter.source {
+                UnreadSource::Notifications(app_name) => self.unread_notifications.get(app_name),
+                _ => self.unread.get(index).and_then(Option::as_ref),
+            };
+            let Some(Unread { count, latest_opt }) = unread_opt.cloned() else {
+                continue;
+            };
+            if count == 0 {
+                continue;
+            }
+            empty = false;
+            let mut row = widget::row::with_capacity(3)
+                .spacing(6)
+                .align_items(alignment::Alignment::Center)
+                .push(widget::icon::from_name(counter.icon.as_str()))
+                .push(widget::text(fl!(
+                    "unread",
+                    label = counter.label.clone(),
+                    count = count
+                )));
+            if let Some(latest) = latest_opt.filter(|_| counter.preview) {
+                row = row.push(widget::text(latest).size(12.0));
+            }
+            column = column.push(row);
+        }
+        (!empty).then(|| column.into())
+    }
+
+    fn media(&self) -> Option<Element<Message>> {
+        let media = self.media_opt.as_ref()?;
+
+        let mut info_column = widget::column::with_capacity(2);
+        info_column = info_column.push(widget::text(media.title.clone()).font(FONT_BOLD));
+        if let Some(artist) = &media.artist {
+            info_column = info_column.push(widget::text(artist.clone()));
+        }
+
+        let play_pause_icon = if media.playing {
+            "media-playback-pause-symbolic"
+        } else {
+            "media-playback-start-symbolic"
+        };
+        let mut media_row = widget::row::with_capacity(5)
+            .spacing(8)
+            .align_items(alignment::Alignment::Center);
+        let art_opt = media
+            .art_url
+            .as_ref()
+            .and_then(|art_url| self.art_cache.get(art_url))
+            .and_then(|handle_opt| handle_opt.clone());
+        if let Some(art) = art_opt {
+            media_row = media_row.push(
+                ImageContainer::new(iced::widget::container(widget::Space::new(
+                    Length::Fixed(64.0),
+                    Length::Fixed(64.0),
+                )))
+                .image(art)
+                .content_fit(ContentFit::Cover),
+            );
+        }
+        media_row = media_row.push(info_column);
+        for (icon, action) in [
+            ("media-skip-backward-symbolic", MediaAction::Previous),
+            (play_pause_icon, MediaAction::PlayPause),
+            ("media-skip-forward-symbolic", MediaAction::Next),
+        ] {
+            media_row = media_row.push(
+                self.icon_button(icon)
+                    .on_press(Message::MediaControl(action)),
+            );
+        }
+        Some(media_row.into())
+    }
+
+    fn brightness(&self) -> Option<Element<Message>> {
+        self.backlight_opt.as_ref()?;
+        Some(
+            widget::row::with_children(vec![
+                widget::icon::from_name("display-brightness-symbolic").into(),
+                widget::slider(1.0..=100.0, self.brightness, Message::Brightness)
+                    .width(Length::Fixed(200.0))
+                    .into(),
+            ])
+            .spacing(8)
+            .align_items(alignment::Alignment::Center)
+            .into(),
+        )
+    }
+
+    /// The wallpaper of this surface's output behind `content`
+    pub(super) fn wallpaper<'a>(
+        &'a self,
+        surface_id: SurfaceId,
+        mut content: Element<'a, Message>,
+    ) -> Element<'a, Message> {
+        let output_name = self
+            .output_infos
+            .get(&surface_id)
+            .map(output::name)
+            .unwrap_or_default();
+        let (image_opt, color_opt) = match self.wallpapers.get(&output_name) {
+            Some(Wallpaper::Image {
+                handle,
+                content_fit,
+                tile,
+                color_opt,
+            }) => (
+                Some((handle, self.scaling(surface_id, *content_fit, *tile))),
+                *color_opt,
+            ),
+            Some(Wallpaper::Color(color)) => (None, Some(*color)),
+            None => return content,
+        };
+        if let Some((handle, (content_fit, tile))) = image_opt {
+            content = self
+                .moving(ImageContainer::new(
+                    iced::widget::container(content)
+                        .width(Length::Fill)
+                        .height(Length::Fill),
+                ))
+                .image(handle.clone())
+                .content_fit(content_fit)
+                .tile(tile)
+                .into();
+        }
+        // The image container draws its image under its own background, so the color goes around it
+        if let Some([r, g, b]) = color_opt {
+            content = iced::widget::container(content)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .style(cosmic::theme::Container::custom(move |_theme| {
+                    iced::widget::container::Appearance {
+                        background: Some(iced::Background::Color(iced::Color::from_rgb(r, g, b))),
+                        ..Default::default()
+                    }
+                }))
+                .into();
+        }
+        content
+    }
+
+    /// Shown on every output, so nobody nearby is surprised by the suspend
+    pub(super) fn suspend_banner(&self) -> Option<Element<Message>> {
+        let suspend_in = self.suspend_in()?;
+        // Rounded up so the countdown ends at one
+        let secs = suspend_in.as_millis().div_ceil(1000);
+        Some(
+            widget::container(
+                icon_label(
+                    "system-suspend-symbolic",
+                    fl!("suspend-countdown", seconds = secs.to_string()),
+                )
+                .padding(12),
+            )
+            .width(Length::Fill)
+            .center_x()
+            .style(self.card())
+            .into(),
+        )
+    }
+
+    /// Whoever is watching a shared screen sees the lock screen, but the share is still live
+    pub(super) fn screencast_banner(&self) -> Option<Element<Message>> {
+        if self.capture.screen.is_empty() {
+            return None;
+        }
+        let label = fl!("screen-shared", apps = self.capture.screen.join(", "));
+        Some(
+            widget::container(
+                widget::row::with_children(vec![
+                    widget::icon::from_name("video-display-symbolic").into(),
+                    widget::text(label)
+                        .style(self.status_style(Status::Error))
+                        .into(),
+                ])
+                .spacing(6)
+                .align_items(alignment::Alignment::Center)
+                .padding(8),
+            )
+            .width(Length::Fill)
+            .center_x()
+            .style(self.card())
+            .into(),
+        )
+    }
+
+    /// Small, but enough to tell a broken config from a lock screen that looks like the default
+    pub(super) fn config_error_banner(&self) -> Option<Element<Message>> {
+        let error = self.flags.config_error_opt.as_ref()?;
+        Some(
+            widget::container(icon_label("dialog-warning-symbolic", error.clone()).padding(8))
+                .width(Length::Fill)
+                .center_x()
+                .style(self.card())
+                .into(),
+        )
+    }
+
+    /// What is needed to make sense of multi-monitor and focus reports
+    pub(super) fn debug_overlay(&self, surface_id: SurfaceId) -> Element<Message> {
+        let mut lines = Vec::with_capacity(5);
+        let output = match self.output_infos.get(&surface_id) {
+            Some(info) => format!("{} at scale {}", output::name(info), output::scale(info)),
+            None => "unknown output".to_string(),
+        };
+        let size = match self.surface_sizes.get(&surface_id) {
+            Some((width, height)) => format!("{}x{}", width, height),
+            None => "not configured".to_string(),
+        };
+        lines.push(format!("surface {:?}: {}, {}", surface_id, output, size));
+        lines.push(format!(
+            "focus: {:?}, this surface {}",
+            self.active_surface_id_opt,
+            if self.is_focused(surface_id) {
+                "focused"
+            } else {
+                "unfocused"
+            }
+        ));
+        lines.push(format!(
+            "state: {:?}, {} surfaces, {:?} mode",
+            self.state,
+            self.surface_ids.len(),
+            self.output_mode()
+        ));
+        lines.push(format!(
+            "last frame: {:.1}ms, {} wakeups this minute",
+            self.frame_time.as_secs_f32() * 1000.0,
+            self.wakeups
+        ));
+        let auth = match (&self.value_tx_opt, &self.prompt_opt) {
+            (None, _) => "not running",
+            (Some(_), None) => "waiting",
+            (Some(_), Some(prompt)) if prompt.secret => "secret prompt",
+            (Some(_), Some(_)) => "prompt",
+        };
+        lines.push(format!(
+            "auth: {}{}",
+            auth,
+            if self.grab_failed {
+                ", no keyboard grab"
+            } else {
+                ""
+            }
+        ));
+
+        widget::container(widget::text(lines.join("\n")).size(12))
+            .padding(8)
+            .width(Length::Fill)
+            .style(self.card())
+            .into()
+    }
+
+    fn power_menu(&self) -> Element<Message> {
+        // Shared machines aren't free to restart, a discreet line above the buttons says so
+        if self.other_users == 0 {
+            return self.power_buttons();
+        }
+        widget::column::with_capacity(2)
+            .spacing(4)
+            .align_items(alignment::Alignment::End)
+            .push(widget::text(fl!("other-users", count = self.other_users)).size(12))
+            .push(self.power_buttons())
+            .into()
+    }
+
+    fn power_buttons(&self) -> Element<Message> {
+        if let Some(action) = self.power_confirm_opt {
+            return widget::row::with_children(vec![
+                widget::text(fl!("power-confirm", action = action.label())).into(),
+                self.touch_sized(widget::button(widget::text(action.label())))
+                    .style(cosmic::theme::Button::Destructive)
+                    .on_press(Message::PowerAction(action))
+                    .into(),
+                self.touch_sized(widget::button(widget::text(fl!("cancel"))))
+                    .style(cosmic::theme::Button::Standard)
+                    .on_press(Message::PowerConfirm(None))
+                    .into(),
+            ])
+            .spacing(12)
+            .align_items(alignment::Alignment::Center)
+            .into();
+        }
+
+        let mut row = widget::row::with_capacity(PowerAction::ALL.len() + 2).spacing(12);
+        if self.power_menu {
+            if self.switch_user_available {
+                row = row.push(
+                    self.touch_sized(widget::button(icon_label(
+                        "system-switch-user-symbolic",
+                        fl!("switch-user"),
+                    )))
+                    .style(cosmic::theme::Button::Standard)
+                    .on_press(Message::SwitchUser),
+                );
+            }
+            for action in PowerAction::ALL {
+                if self.flags.config.hidden_power_actions.contains(&action) {
+                    continue;
+                }
+                row = row.push(
+                    self.touch_sized(widget::button(icon_label(action.icon(), action.label())))
+                        .style(cosmic::theme::Button::Standard)
+                        .on_press(Message::PowerConfirm(Some(action))),
+                );
+            }
+        }
+        row = row.push(
+            self.icon_button("system-shutdown-symbolic")
+                .on_press(Message::PowerMenu(!self.power_menu)),
+        );
+        row.into()
+    }
+}
@@ -0,0 +1,35 @@
+//! Logs go to the journal when running under systemd, otherwise to stderr
+//!
+//! The locker usually has no terminal, so without the journal its logs are easily lost. `log`
+//! records from the rest of the code and libraries are forwarded to tracing. Spans name the phase
+//! a line was logged in, like `locked` or `auth{attempt=2}`.
+
+use std::env;
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+pub fn init() {
+    // RUST_LOG as before, warnings and errors by default
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn"));
+
+    // Set by systemd when stderr is connected to the journal
+    let journald_opt = if env::var_os("JOURNAL_STREAM").is_some() {
+        match tracing_journald::layer() {
+            Ok(layer) => Some(layer.with_syslog_identifier("zero-lock".to_string())),
+            Err(err) => {
+                eprintln!("failed to connect to the journal: {}", err);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let fmt_opt = journald_opt
+        .is_none()
+        .then(|| fmt::layer().with_writer(std::io::stderr).boxed());
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(journald_opt)
+        .with(fmt_opt)
+        .init();
+}
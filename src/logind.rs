@@ -0,0 +1,368 @@
+use cosmic::iced::{
+    futures::{channel::mpsc, stream, SinkExt, StreamExt},
+    subscription, Subscription,
+};
+use serde::Deserialize;
+use std::{any::TypeId, time::Duration};
+use tokio::time;
+use zbus::{
+    dbus_proxy,
+    zvariant::{OwnedFd, OwnedObjectPath},
+    Connection, Result,
+};
+
+use crate::fl;
+
+// What, who, why, mode, UID and PID
+type Inhibitor = (String, String, String, String, u32, u32);
+// Session ID, UID, user name, seat ID and object path
+type SessionEntry = (String, u32, String, String, OwnedObjectPath);
+
+#[dbus_proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Manager {
+    fn suspend(&self, interactive: bool) -> Result<()>;
+
+    fn hibernate(&self, interactive: bool) -> Result<()>;
+
+    fn reboot(&self, interactive: bool) -> Result<()>;
+
+    fn power_off(&self, interactive: bool) -> Result<()>;
+
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> Result<OwnedFd>;
+
+    fn list_inhibitors(&self) -> Result<Vec<Inhibitor>>;
+
+    fn list_sessions(&self) -> Result<Vec<SessionEntry>>;
+
+    #[dbus_proxy(property)]
+    fn block_inhibited(&self) -> Result<String>;
+
+    #[dbus_proxy(property)]
+    fn lid_closed(&self) -> Result<bool>;
+
+    #[dbus_proxy(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> Result<()>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait Session {
+    fn set_brightness(&self, subsystem: &str, name: &str, brightness: u32) -> Result<()>;
+
+    fn set_locked_hint(&self, locked: bool) -> Result<()>;
+
+    #[dbus_proxy(property)]
+    fn id(&self) -> Result<String>;
+
+    #[dbus_proxy(property)]
+    fn locked_hint(&self) -> Result<bool>;
+
+    // False while another VT or session is in the foreground of the seat
+    #[dbus_proxy(property)]
+    fn active(&self) -> Result<bool>;
+
+    // Sent for loginctl lock-session and unlock-session, or lock-sessions for all of them
+    #[dbus_proxy(signal)]
+    fn lock(&self) -> Result<()>;
+
+    #[dbus_proxy(signal)]
+    fn unlock(&self) -> Result<()>;
+
+    #[dbus_proxy(property)]
+    fn seat(&self) -> Result<(String, OwnedObjectPath)>;
+
+    // "user", "greeter", "lock-screen", or "manager" for user@.service
+    #[dbus_proxy(property)]
+    fn class(&self) -> Result<String>;
+
+    // "online", "active" or "closing"
+    #[dbus_proxy(property)]
+    fn state(&self) -> Result<String>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerAction {
+    Suspend,
+    Hibernate,
+    Restart,
+    ShutDown,
+}
+
+impl PowerAction {
+    pub const ALL: [Self; 4] = [
+        Self::Suspend,
+        Self::Hibernate,
+        Self::Restart,
+        Self::ShutDown,
+    ];
+
+    pub fn icon(self) -> &'static str {
+        match self {
+            Self::Suspend => "system-suspend-symbolic",
+            Self::Hibernate => "system-hibernate-symbolic",
+            Self::Restart => "system-reboot-symbolic",
+            Self::ShutDown => "system-shutdown-symbolic",
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Self::Suspend => fl!("suspend"),
+            Self::Hibernate => fl!("hibernate"),
+            Self::Restart => fl!("restart"),
+            Self::ShutDown => fl!("shut-down"),
+        }
+    }
+}
+
+pub async fn power_action(action: PowerAction) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let manager = ManagerProxy::new(&zbus).await?;
+    // Let polkit decide, the call fails if the action is not allowed
+    match action {
+        PowerAction::Suspend => manager.suspend(true).await,
+        PowerAction::Hibernate => manager.hibernate(true).await,
+        PowerAction::Restart => manager.reboot(true).await,
+        PowerAction::ShutDown => manager.power_off(true).await,
+    }
+}
+
+/// Suspends without asking unless something holds a sleep inhibitor, returns false if it did
+pub async fn auto_suspend() -> Result<bool> {
+    let zbus = Connection::system().await?;
+    let manager = ManagerProxy::new(&zbus).await?;
+    // Colon separated, like "sleep:idle:handle-lid-switch"
+    let block_inhibited = manager.block_inhibited().await?;
+    if block_inhibited.split(':').any(|what| what == "sleep") {
+        return Ok(false);
+    }
+    manager.suspend(false).await?;
+    Ok(true)
+}
+
+/// Delays sleep until the returned file descriptor is closed, or logind's InhibitDelayMaxSec
+pub async fn inhibit_sleep() -> Result<OwnedFd> {
+    let zbus = Connection::system().await?;
+    let manager = ManagerProxy::new(&zbus).await?;
+    manager
+        .inhibit(
+            "sleep",
+            "zero-lock",
+            "Drawing the lock screen before sleep",
+            "delay",
+        )
+        .await
+}
+
+/// Who holds off idle actions and why, like "Firefox: Playing video"
+pub async fn idle_inhibitors() -> Result<Vec<String>> {
+    let zbus = Connection::system().await?;
+    let manager = ManagerProxy::new(&zbus).await?;
+    Ok(manager
+        .list_inhibitors()
+        .await?
+        .into_iter()
+        // Colon separated like block_inhibited, delay inhibitors only apply to sleep
+        .filter(|(what, _, _, mode, _, _)| {
+            mode == "block" && what.split(':').any(|what| what == "idle")
+        })
+        .map(|(_, who, why, _, _, _)| format!("{}: {}", who, why))
+        .collect())
+}
+
+pub async fn set_brightness(subsystem: &'static str, name: String, brightness: u32) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let session = SessionProxy::new(&zbus).await?;
+    session.set_brightness(subsystem, &name, brightness).await
+}
+
+/// Session and seat IDs of the session being locked, "2" and "seat0"
+pub async fn session_seat() -> Result<(String, String)> {
+    let zbus = Connection::system().await?;
+    let session = SessionProxy::new(&zbus).await?;
+    let (seat, _seat_path) = session.seat().await?;
+    Ok((session.id().await?, seat))
+}
+
+/// How many other users have a session open, on any seat or over SSH
+pub async fn other_users(username: String) -> Result<usize> {
+    let zbus = Connection::system().await?;
+    let manager = ManagerProxy::new(&zbus).await?;
+    let mut users: Vec<String> = Vec::new();
+    for (_id, _uid, user, _seat, path) in manager.list_sessions().await? {
+        if user == username || users.contains(&user) {
+            continue;
+        }
+        let session = SessionProxy::builder(&zbus).path(path)?.build().await?;
+        // Lingering user managers and sessions on their way out don't hold the machine
+        if session.class().await? == "user" && session.state().await? != "closing" {
+            users.push(user);
+        }
+    }
+    Ok(users.len())
+}
+
+/// Marks only this session as locked, other seats have their own sessions and lockers
+pub async fn set_locked_hint(locked: bool) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let session = SessionProxy::new(&zbus).await?;
+    session.set_locked_hint(locked).await
+}
+
+/// True before the system sleeps and false after it resumed
+pub fn sleep_subscription() -> Subscription<bool> {
+    struct SleepSubscription;
+
+    subscription::channel(
+        TypeId::of::<SleepSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            if let Err(err) = sleep_handler(&mut msg_tx).await {
+                log::warn!("logind sleep signal error: {}", err);
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+async fn sleep_handler(msg_tx: &mut mpsc::Sender<bool>) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let manager = ManagerProxy::new(&zbus).await?;
+    let mut signals = manager.receive_prepare_for_sleep().await?;
+    while let Some(signal) = signals.next().await {
+        let args = signal.args()?;
+        if msg_tx.send(args.start).await.is_err() {
+            // The locker stopped listening
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Whether the session is marked locked, still true after a locker died holding the lock
+pub async fn locked_hint() -> Result<bool> {
+    let zbus = Connection::system().await?;
+    let session = SessionProxy::new(&zbus).await?;
+    session.locked_hint().await
+}
+
+/// True when logind asks this session to lock and false when it asks to unlock
+pub fn session_subscription() -> Subscription<bool> {
+    struct SessionSubscription;
+
+    subscription::channel(
+        TypeId::of::<SessionSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            if let Err(err) = session_handler(&mut msg_tx).await {
+                log::warn!("logind session signal error: {}", err);
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+async fn session_handler(msg_tx: &mut mpsc::Sender<bool>) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let session = SessionProxy::new(&zbus).await?;
+    let locks = session.receive_lock().await?.map(|_| true);
+    let unlocks = session.receive_unlock().await?.map(|_| false);
+    let mut signals = stream::select(locks, unlocks);
+    while let Some(lock) = signals.next().await {
+        if msg_tx.send(lock).await.is_err() {
+            // The locker stopped listening
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Sent whenever this session is switched away from or back to, with a VT switch or another
+/// session taking over the seat
+pub fn active_subscription() -> Subscription<bool> {
+    struct ActiveSubscription;
+
+    subscription::channel(
+        TypeId::of::<ActiveSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            if let Err(err) = active_handler(&mut msg_tx).await {
+                log::warn!("logind session activity error: {}", err);
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+async fn active_handler(msg_tx: &mut mpsc::Sender<bool>) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let session = SessionProxy::new(&zbus).await?;
+    let mut active = session.active().await?;
+    let mut changes = session.receive_active_changed().await;
+    while let Some(change) = changes.next().await {
+        let changed = change.get().await?;
+        if changed != active {
+            active = changed;
+            if msg_tx.send(active).await.is_err() {
+                // The locker stopped listening
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Sent whenever the lid opens or closes
+pub fn lid_subscription() -> Subscription<bool> {
+    struct LidSubscription;
+
+    subscription::channel(
+        TypeId::of::<LidSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            if let Err(err) = lid_handler(&mut msg_tx).await {
+                log::warn!("logind lid switch error: {}", err);
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+async fn lid_handler(msg_tx: &mut mpsc::Sender<bool>) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let manager = ManagerProxy::new(&zbus).await?;
+    // Streams may start with the current state, which is not a change
+    let mut lid_closed = manager.lid_closed().await?;
+    let mut changes = manager.receive_lid_closed_changed().await;
+    while let Some(change) = changes.next().await {
+        let changed = change.get().await?;
+        if changed != lid_closed {
+            lid_closed = changed;
+            if msg_tx.send(lid_closed).await.is_err() {
+                // The locker stopped listening
+                break;
+            }
+        }
+    }
+    Ok(())
+}
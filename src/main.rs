@@ -1,13 +1,131 @@
+mod accounts;
+mod alarms;
+mod ambient_light;
+mod animation;
+mod auth;
+mod background_dir;
+mod backlight;
+mod bluetooth;
+mod clipboard;
+mod config;
+mod control;
+mod cover;
+mod crash_report;
+mod cursor_area;
+mod desktop_theme;
+mod detach;
+mod dimmer;
+mod display_manager;
+mod fprintd;
+mod frosted;
+mod gamepad;
+mod gesture;
+mod grace;
+mod greetd;
+mod hooks;
+mod idle_notify;
 mod image_container;
+mod kclock;
+mod keyboard_layout;
+mod keymap;
+mod layout;
+mod localize;
 mod locker;
+mod logging;
+mod logind;
+mod motion;
+mod mpris;
+mod networkmanager;
+mod notifications;
+mod osk;
+mod output;
+mod output_power;
+mod packagekit;
+mod panic_hook;
+mod pipewire;
+mod plugin;
+mod power_profiles;
+mod power_supply;
+mod render;
+mod resolved;
+mod retry;
+mod screensaver;
+mod sessions;
+mod signals;
+mod stats;
+mod supervisor;
+mod swaylock;
+mod system_info;
+mod systemd;
+mod timer;
+mod typing;
+mod unread;
+mod upower;
+mod wallpaper;
+
+include!("cli.rs");
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    logging::init();
+    localize::init();
+
+    // Ring colors and sizes from swaylock invocations would be rejected as unknown
+    let args = Args::parse_from(swaylock::strip_args(std::env::args_os()));
+    let command_opt = match args.action_opt {
+        Some(Action::Status) => Some("status"),
+        Some(Action::Stats) => Some("stats"),
+        Some(Action::Unlock) => Some("unlock"),
+        Some(Action::Lock | Action::Render { .. }) | None => None,
+    };
+    if let Some(command) = command_opt {
+        let reply = control::request(command)
+            .map_err(|err| format!("no running locker to ask: {}", err))?;
+        print!("{}", reply);
+        if reply.starts_with("error") {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    // One locker per session, a second one would fail to lock anyway
+    let render = matches!(args.action_opt, Some(Action::Render { .. }));
+    if !args.preview && !render {
+        let lock_command = match &args.profile_opt {
+            Some(profile) => format!("lock {}", profile),
+            None => "lock".to_string(),
+        };
+        if args.daemon {
+            if control::request("status").is_ok() {
+                return Err("a locker is already running".into());
+            }
+        } else if control::request(&lock_command).is_ok() {
+            log::info!("asked the running locker to lock");
+            return Ok(());
+        }
+    }
+    if args.daemonize {
+        // The detached locker takes everything else, including --supervised
+        // Not UTF-8 either, clap took paths as they are
+        let locker_args = std::env::args_os()
+            .skip(1)
+            .filter(|arg| arg != "-f" && arg != "--daemonize")
+            .collect();
+        return detach::main(locker_args);
+    }
+    if args.supervised {
+        // Everything else is for the supervised locker
+        let locker_args = std::env::args_os()
+            .skip(1)
+            .filter(|arg| arg != "--supervised")
+            .collect();
+        return supervisor::main(locker_args);
+    }
+    panic_hook::install();
 
     match pwd::Passwd::current_user() {
         Some(current_user) => match current_user.name.as_str() {
-            "greeter" => locker::main(),
-            _ => locker::main(),
+            // Started by greetd to log someone in, instead of locking this user's session
+            "greeter" => locker::main(current_user, args, true),
+            _ => locker::main(current_user, args, false),
         },
         _ => Err("failed to determine current user".into()),
     }
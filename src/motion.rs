@@ -0,0 +1,62 @@
+//! A slow pan and zoom over the wallpaper, Ken Burns style
+//!
+//! The phase only advances while frames come in, so the wallpaper holds still while idle and
+//! carries on from there instead of jumping.
+
+use cosmic::iced::{time, Subscription, Vector};
+use std::{
+    f32::consts::TAU,
+    time::{Duration, Instant},
+};
+
+// Moving a few pixels a second doesn't need the animation frame rate
+const FRAME_INTERVAL: Duration = Duration::from_millis(50);
+// Longer gaps are a pause, not a slow frame
+const MAX_STEP: Duration = Duration::from_millis(200);
+// Never fully zoomed out, so there is always some room to pan
+const MIN_ZOOM: f32 = 1.04;
+const MAX_ZOOM: f32 = 1.12;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Motion {
+    period: Duration,
+    // Cycles so far, from 0.0 to 1.0
+    phase: f32,
+    last_opt: Option<Instant>,
+}
+
+impl Motion {
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            phase: 0.0,
+            last_opt: None,
+        }
+    }
+
+    pub fn frame(&mut self, now: Instant) {
+        if let Some(last) = self.last_opt {
+            let step = now.saturating_duration_since(last).min(MAX_STEP);
+            self.phase = (self.phase + step.as_secs_f32() / self.period.as_secs_f32()).fract();
+        }
+        self.last_opt = Some(now);
+    }
+
+    /// How much larger the wallpaper is drawn than fitted
+    pub fn zoom(&self) -> f32 {
+        let t = 0.5 - 0.5 * (self.phase * TAU).cos();
+        MIN_ZOOM + (MAX_ZOOM - MIN_ZOOM) * t
+    }
+
+    /// Where the zoomed wallpaper is, from -1.0 to 1.0 of the room it has on each axis
+    pub fn pan(&self) -> Vector {
+        Vector::new(
+            (self.phase * TAU).sin(),
+            (self.phase * 2.0 * TAU).sin() * 0.5,
+        )
+    }
+}
+
+pub fn frames() -> Subscription<Instant> {
+    time::every(FRAME_INTERVAL)
+}
@@ -0,0 +1,290 @@
+use cosmic::iced::{
+    futures::{channel::mpsc, future, stream, SinkExt, StreamExt},
+    widget::image::Handle,
+    Subscription,
+};
+use std::{any::TypeId, collections::HashMap};
+#[cfg(feature = "mpris")]
+use tokio::fs;
+#[cfg(feature = "mpris")]
+use url::Url;
+use zbus::{
+    dbus_proxy,
+    fdo::DBusProxy,
+    zvariant::{OwnedValue, Value},
+    Connection, MatchRule, MessageStream, MessageType, Result,
+};
+
+use crate::retry;
+
+const BUS_NAME_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const PLAYER_PATH: &str = "/org/mpris/MediaPlayer2";
+
+#[dbus_proxy(
+    interface = "org.mpris.MediaPlayer2.Player",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+trait Player {
+    fn play_pause(&self) -> Result<()>;
+
+    fn pause(&self) -> Result<()>;
+
+    fn play(&self) -> Result<()>;
+
+    fn next(&self) -> Result<()>;
+
+    fn previous(&self) -> Result<()>;
+
+    #[dbus_proxy(property)]
+    fn playback_status(&self) -> Result<String>;
+
+    #[dbus_proxy(property)]
+    fn metadata(&self) -> Result<HashMap<String, OwnedValue>>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MediaInfo {
+    pub bus_name: String,
+    pub title: String,
+    pub artist: Option<String>,
+    pub art_url: Option<String>,
+    pub playing: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum MediaAction {
+    PlayPause,
+    Next,
+    Previous,
+}
+
+pub fn subscription() -> Subscription<Option<MediaInfo>> {
+    struct MprisSubscription;
+
+    retry::subscription(
+        TypeId::of::<MprisSubscription>(),
+        "mpris",
+        Some(None),
+        handler,
+    )
+}
+
+async fn handler(mut msg_tx: mpsc::Sender<Option<MediaInfo>>) -> Result<()> {
+    let zbus = Connection::session().await?;
+    // Players changing what or whether they play, and players starting and quitting
+    let rule = MatchRule::builder()
+        .msg_type(MessageType::Signal)
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .path(PLAYER_PATH)?
+        .build();
+    let properties = MessageStream::for_match_rule(rule, &zbus, None)
+        .await?
+        .map(|_| ());
+    let owners = DBusProxy::new(&zbus)
+        .await?
+        .receive_name_owner_changed()
+        .await?
+        .filter(|signal| {
+            future::ready(signal.args().map_or(false, |args| {
+                args.name().as_str().starts_with(BUS_NAME_PREFIX)
+            }))
+        })
+        .map(|_| ());
+    let mut changes = stream::select(properties, owners);
+
+    let mut last_opt = None;
+    loop {
+        let info_opt = current(&zbus).await?;
+        if info_opt != last_opt {
+            if msg_tx.send(info_opt.clone()).await.is_err() {
+                // The locker stopped listening
+                return Ok(());
+            }
+            last_opt = info_opt;
+        }
+
+        if changes.next().await.is_none() {
+            return Ok(());
+        }
+    }
+}
+
+async fn current(zbus: &Connection) -> Result<Option<MediaInfo>> {
+    let mut paused_opt = None;
+    for bus_name in player_names(zbus).await? {
+        let player = match PlayerProxy::builder(zbus)
+            .destination(bus_name.clone())?
+            .build()
+            .await
+        {
+            Ok(ok) => ok,
+            Err(err) => {
+                log::info!("failed to connect to player {}: {}", bus_name, err);
+                continue;
+            }
+        };
+
+        // Players may vanish between listing and querying
+        let Ok(status) = player.playback_status().await else {
+            continue;
+        };
+        let playing = match status.as_str() {
+            "Playing" => true,
+            "Paused" => false,
+            _ => continue,
+        };
+        let metadata = player.metadata().await.unwrap_or_default();
+        let Some(title) = metadata_str(&metadata, "xesam:title") else {
+            continue;
+        };
+        let info = MediaInfo {
+            bus_name,
+            title,
+            artist: metadata_artist(&metadata),
+            art_url: metadata_str(&metadata, "mpris:artUrl"),
+            playing,
+        };
+
+        // Prefer whatever is actually playing
+        if playing {
+            return Ok(Some(info));
+        } else if paused_opt.is_none() {
+            paused_opt = Some(info);
+        }
+    }
+    Ok(paused_opt)
+}
+
+pub async fn player_names(zbus: &Connection) -> Result<Vec<String>> {
+    let dbus = DBusProxy::new(zbus).await?;
+    Ok(dbus
+        .list_names()
+        .await?
+        .into_iter()
+        .map(|name| name.to_string())
+        .filter(|name| name.starts_with(BUS_NAME_PREFIX))
+        .collect())
+}
+
+fn metadata_str(metadata: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    metadata
+        .get(key)
+        .and_then(|value| value.downcast_ref::<str>())
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_string())
+}
+
+fn metadata_artist(metadata: &HashMap<String, OwnedValue>) -> Option<String> {
+    match &**metadata.get("xesam:artist")? {
+        Value::Array(array) => {
+            let artists: Vec<&str> = array
+                .get()
+                .iter()
+                .filter_map(|value| value.downcast_ref::<str>())
+                .collect();
+            if artists.is_empty() {
+                None
+            } else {
+                Some(artists.join(", "))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Reads the cover image for `art_url`, only local files are supported. Remote art isn't fetched,
+/// a locked machine shouldn't make requests to wherever a player points it
+#[cfg(feature = "mpris")]
+pub async fn load_art(art_url: String) -> Option<Handle> {
+    let url = match Url::parse(&art_url) {
+        Ok(ok) => ok,
+        Err(err) => {
+            log::info!("invalid art url {:?}: {}", art_url, err);
+            return None;
+        }
+    };
+    let Ok(path) = url.to_file_path() else {
+        log::debug!("not fetching remote art {:?}", art_url);
+        return None;
+    };
+    match fs::read(&path).await {
+        Ok(bytes) => Some(Handle::from_memory(bytes)),
+        Err(err) => {
+            log::info!("failed to read art {:?}: {}", path, err);
+            None
+        }
+    }
+}
+
+#[cfg(not(feature = "mpris"))]
+pub async fn load_art(_art_url: String) -> Option<Handle> {
+    None
+}
+
+pub async fn control(bus_name: String, action: MediaAction) -> Result<()> {
+    let zbus = Connection::session().await?;
+    let player = PlayerProxy::builder(&zbus)
+        .destination(bus_name)?
+        .build()
+        .await?;
+    match action {
+        MediaAction::PlayPause => player.play_pause().await,
+        MediaAction::Next => player.next().await,
+        MediaAction::Previous => player.previous().await,
+    }
+}
+
+/// Pauses every player that is playing, returning their bus names to resume them later
+pub async fn pause_all() -> Result<Vec<String>> {
+    let zbus = Connection::session().await?;
+    let mut paused = Vec::new();
+    for bus_name in player_names(&zbus).await? {
+        let Ok(player) = player(&zbus, &bus_name).await else {
+            continue;
+        };
+        // Players may vanish between listing and querying
+        if player.playback_status().await.ok().as_deref() != Some("Playing") {
+            continue;
+        }
+        match player.pause().await {
+            Ok(()) => {
+                log::info!("paused {}", bus_name);
+                paused.push(bus_name);
+            }
+            Err(err) => log::info!("failed to pause {}: {}", bus_name, err),
+        }
+    }
+    Ok(paused)
+}
+
+/// Resumes the players [`pause_all`] paused, those that went away are skipped
+pub async fn resume(bus_names: Vec<String>) -> Result<()> {
+    let zbus = Connection::session().await?;
+    for bus_name in bus_names {
+        let res = match player(&zbus, &bus_name).await {
+            Ok(player) => player.play().await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = res {
+            log::info!("failed to resume {}: {}", bus_name, err);
+        }
+    }
+    Ok(())
+}
+
+async fn player<'a>(zbus: &Connection, bus_name: &str) -> Result<PlayerProxy<'a>> {
+    PlayerProxy::builder(zbus)
+        .destination(bus_name.to_string())?
+        .build()
+        .await
+}
+
+/// Controls the player that would be shown on the lock screen, used for media keys
+pub async fn control_current(action: MediaAction) -> Result<()> {
+    let zbus = Connection::session().await?;
+    match current(&zbus).await? {
+        Some(info) => control(info.bus_name, action).await,
+        None => Ok(()),
+    }
+}
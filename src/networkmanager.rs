@@ -0,0 +1,126 @@
+use cosmic::iced::{
+    futures::{channel::mpsc, SinkExt, StreamExt},
+    Subscription,
+};
+use std::{any::TypeId, time::Duration};
+use tokio::time;
+use zbus::{
+    dbus_proxy, fdo::DBusProxy, names::BusName, zvariant::OwnedObjectPath, Connection, Result,
+};
+
+use crate::{resolved, retry};
+
+const SERVICE: &str = "org.freedesktop.NetworkManager";
+// NM_ACTIVE_CONNECTION_STATE_ACTIVATED
+const STATE_ACTIVATED: u32 = 2;
+// Connections can change state without the list changing, so poll as well
+const POLL_INTERVAL: Duration = Duration::new(10, 0);
+
+#[dbus_proxy(
+    interface = "org.freedesktop.NetworkManager",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager"
+)]
+trait NetworkManager {
+    #[dbus_proxy(property)]
+    fn active_connections(&self) -> Result<Vec<OwnedObjectPath>>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.NetworkManager.Connection.Active",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait ActiveConnection {
+    #[dbus_proxy(property)]
+    fn id(&self) -> Result<String>;
+
+    #[dbus_proxy(property, name = "Type")]
+    fn connection_type(&self) -> Result<String>;
+
+    #[dbus_proxy(property)]
+    fn vpn(&self) -> Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn state(&self) -> Result<u32>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VpnState {
+    Connected(String),
+    // A VPN was connected earlier but is gone now
+    Disconnected,
+}
+
+pub fn subscription() -> Subscription<Option<VpnState>> {
+    struct VpnSubscription;
+
+    retry::subscription(
+        TypeId::of::<VpnSubscription>(),
+        "networkmanager",
+        Some(None),
+        handler,
+    )
+}
+
+async fn handler(mut msg_tx: mpsc::Sender<Option<VpnState>>) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let dbus = DBusProxy::new(&zbus).await?;
+    let nm_opt = if dbus.name_has_owner(BusName::try_from(SERVICE)?).await? {
+        Some(NetworkManagerProxy::new(&zbus).await?)
+    } else {
+        log::info!("no NetworkManager, looking for VPN links in systemd-resolved");
+        None
+    };
+    let mut changes_opt = match &nm_opt {
+        Some(nm) => Some(nm.receive_active_connections_changed().await),
+        None => None,
+    };
+
+    let mut seen_vpn = false;
+    let mut last_opt = None;
+    loop {
+        let name_opt = match &nm_opt {
+            Some(nm) => active_vpn(&zbus, nm).await?,
+            None => resolved::active_vpn(&zbus).await?,
+        };
+        let state_opt = match name_opt {
+            Some(name) => {
+                seen_vpn = true;
+                Some(VpnState::Connected(name))
+            }
+            None if seen_vpn => Some(VpnState::Disconnected),
+            None => None,
+        };
+
+        if state_opt != last_opt {
+            log::info!("vpn state: {:?}", state_opt);
+            if msg_tx.send(state_opt.clone()).await.is_err() {
+                // The locker stopped listening
+                return Ok(());
+            }
+            last_opt = state_opt;
+        }
+
+        match &mut changes_opt {
+            Some(changes) => {
+                let _ = time::timeout(POLL_INTERVAL, changes.next()).await;
+            }
+            None => time::sleep(POLL_INTERVAL).await,
+        }
+    }
+}
+
+async fn active_vpn(zbus: &Connection, nm: &NetworkManagerProxy<'_>) -> Result<Option<String>> {
+    for path in nm.active_connections().await? {
+        let conn = ActiveConnectionProxy::builder(zbus)
+            .path(path)?
+            .build()
+            .await?;
+
+        let is_vpn = conn.vpn().await? || conn.connection_type().await? == "wireguard";
+        if is_vpn && conn.state().await? == STATE_ACTIVATED {
+            return Ok(Some(conn.id().await?));
+        }
+    }
+    Ok(None)
+}
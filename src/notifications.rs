@@ -0,0 +1,195 @@
+use cosmic::{
+    cosmic_config::{self, ConfigGet, ConfigSet},
+    iced::{
+        futures::{channel::mpsc, SinkExt, StreamExt},
+        Subscription,
+    },
+};
+use std::{
+    any::TypeId,
+    collections::{HashMap, HashSet},
+};
+use zbus::{
+    fdo::{DBusProxy, MonitoringProxy},
+    names::{BusName, WellKnownName},
+    zvariant::OwnedValue,
+    Connection, MatchRule, Message, MessageStream, MessageType, Result,
+};
+
+use crate::retry;
+
+const INTERFACE: &str = "org.freedesktop.Notifications";
+// NotificationClosed reason, expired ones are still unread in the notification center
+const CLOSED_EXPIRED: u32 = 1;
+const DAEMON_CONFIG_ID: &str = "com.system76.CosmicNotifications";
+const DAEMON_CONFIG_VERSION: u64 = 1;
+
+type NotifyArgs = (
+    String,
+    u32,
+    String,
+    String,
+    String,
+    Vec<String>,
+    HashMap<String, OwnedValue>,
+    i32,
+);
+
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub app_name: String,
+    pub summary: String,
+    pub body: String,
+}
+
+#[derive(Clone, Debug)]
+pub enum NotificationEvent {
+    Received(Notification),
+    Closed,
+}
+
+pub fn subscription() -> Subscription<NotificationEvent> {
+    struct NotificationSubscription;
+
+    retry::subscription(
+        TypeId::of::<NotificationSubscription>(),
+        "notifications",
+        None,
+        handler,
+    )
+}
+
+async fn handler(mut msg_tx: mpsc::Sender<NotificationEvent>) -> Result<()> {
+    // A monitor connection cannot be used for anything else, so it gets its own
+    let zbus = Connection::session().await?;
+    // Replies are only watched from the daemon, started now if the first Notify would have
+    let dbus = DBusProxy::new(&zbus).await?;
+    dbus.start_service_by_name(WellKnownName::try_from(INTERFACE)?, 0)
+        .await?;
+    let daemon = dbus.get_name_owner(BusName::try_from(INTERFACE)?).await?;
+    let rules = [
+        MatchRule::builder()
+            .msg_type(MessageType::MethodCall)
+            .interface(INTERFACE)?
+            .member("Notify")?
+            .build(),
+        MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .sender(daemon.as_str())?
+            .interface(INTERFACE)?
+            .member("NotificationClosed")?
+            .build(),
+        // The ids of new notifications come back in Notify's reply
+        MatchRule::builder()
+            .msg_type(MessageType::MethodReturn)
+            .sender(daemon.as_str())?
+            .build(),
+        // A restarted daemon has another unique name, the rules are made again for it
+        MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .sender("org.freedesktop.DBus")?
+            .interface("org.freedesktop.DBus")?
+            .member("NameOwnerChanged")?
+            .arg(0, INTERFACE)?
+            .build(),
+    ];
+    MonitoringProxy::new(&zbus)
+        .await?
+        .become_monitor(&rules, 0)
+        .await?;
+
+    // Notify calls waiting for their reply, and the ids of the notifications counted so far, only
+    // their closing takes one off the count
+    let mut pending: HashSet<(String, u32)> = HashSet::new();
+    let mut counted: HashSet<u32> = HashSet::new();
+    let mut stream = MessageStream::from(&zbus);
+    while let Some(msg_res) = stream.next().await {
+        let msg = msg_res?;
+        let member_opt = msg.member();
+        let event = match (msg.message_type(), member_opt.as_deref()) {
+            (MessageType::MethodCall, Some("Notify")) => match msg.body::<NotifyArgs>() {
+                // Updates to an existing notification are not new notifications
+                Ok((app_name, 0, _, summary, body, ..)) => {
+                    if let Some(call) = call_key(&msg) {
+                        pending.insert(call);
+                    }
+                    NotificationEvent::Received(Notification {
+                        app_name,
+                        summary,
+                        body,
+                    })
+                }
+                Ok(_) => continue,
+                Err(err) => {
+                    log::info!("failed to parse notification: {}", err);
+                    continue;
+                }
+            },
+            (MessageType::MethodReturn, _) => {
+                if reply_key(&msg).is_some_and(|call| pending.remove(&call)) {
+                    match msg.body::<u32>() {
+                        Ok(id) => {
+                            counted.insert(id);
+                        }
+                        Err(err) => log::info!("failed to parse notification id: {}", err),
+                    }
+                }
+                continue;
+            }
+            (MessageType::Signal, Some("NameOwnerChanged")) => {
+                log::info!("notification daemon changed, watching the new one");
+                return Ok(());
+            }
+            (MessageType::Signal, Some("NotificationClosed")) => match msg.body::<(u32, u32)>() {
+                Ok((id, reason)) if reason != CLOSED_EXPIRED && counted.remove(&id) => {
+                    NotificationEvent::Closed
+                }
+                Ok(_) => continue,
+                Err(err) => {
+                    log::info!("failed to parse closed notification: {}", err);
+                    continue;
+                }
+            },
+            _ => continue,
+        };
+        if msg_tx.send(event).await.is_err() {
+            // The locker stopped listening
+            return Ok(());
+        }
+    }
+
+    Ok(())
+}
+
+// The caller and serial of a method call, which its reply is addressed with
+fn call_key(msg: &Message) -> Option<(String, u32)> {
+    let header = msg.header().ok()?;
+    let sender = header.sender().ok()??.to_string();
+    Some((sender, *msg.primary_header().serial_num()?))
+}
+
+fn reply_key(msg: &Message) -> Option<(String, u32)> {
+    let header = msg.header().ok()?;
+    let destination = header.destination().ok()??.to_string();
+    Some((destination, header.reply_serial().ok()??))
+}
+
+/// Do Not Disturb state of cosmic-notifications, None if it can't be read
+pub fn do_not_disturb() -> Option<bool> {
+    cosmic_config::Config::new(DAEMON_CONFIG_ID, DAEMON_CONFIG_VERSION)
+        .and_then(|config| config.get::<bool>("do_not_disturb"))
+        .map_err(|err| log::info!("failed to read do_not_disturb: {:?}", err))
+        .ok()
+}
+
+pub fn set_do_not_disturb(do_not_disturb: bool) -> bool {
+    match cosmic_config::Config::new(DAEMON_CONFIG_ID, DAEMON_CONFIG_VERSION)
+        .and_then(|config| config.set("do_not_disturb", do_not_disturb))
+    {
+        Ok(()) => true,
+        Err(err) => {
+            log::warn!("failed to set do_not_disturb: {:?}", err);
+            false
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use cosmic::{
+    iced::{alignment::Alignment, Length},
+    widget, Element,
+};
+
+const LETTER_ROWS: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+const SYMBOL_ROWS: [&str; 3] = ["1234567890", "@#$%&*-+()", "!\"':;/?"];
+const KEY_SIZE: f32 = 48.0;
+// For high contrast mode, easier to hit
+const LARGE_KEY_SIZE: f32 = 64.0;
+// Shift, symbols, space, backspace and enter
+const BOTTOM_ROW_KEYS: [OskKey; 5] = [
+    OskKey::Shift,
+    OskKey::Symbols,
+    OskKey::Space,
+    OskKey::Backspace,
+    OskKey::Enter,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OskKey {
+    Char(char),
+    Backspace,
+    Shift,
+    Symbols,
+    Space,
+    Enter,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct VirtualKeyboard {
+    pub visible: bool,
+    large: bool,
+    shift: bool,
+    symbols: bool,
+    // Row and column of the key a game controller is on, the bottom row is the fourth
+    selected_opt: Option<(usize, usize)>,
+}
+
+impl VirtualKeyboard {
+    pub fn new(visible: bool, large: bool) -> Self {
+        Self {
+            visible,
+            large,
+            ..Default::default()
+        }
+    }
+
+    /// Handles layout keys, returns the key when it affects the input
+    pub fn press(&mut self, key: OskKey) -> Option<OskKey> {
+        match key {
+            OskKey::Shift => {
+                self.shift = !self.shift;
+                None
+            }
+            OskKey::Symbols => {
+                self.symbols = !self.symbols;
+                None
+            }
+            OskKey::Char(c) => {
+                // Shift only applies to the next letter, like on phones
+                let c = if self.shift {
+                    self.shift = false;
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                };
+                Some(OskKey::Char(c))
+            }
+            _ => Some(key),
+        }
+    }
+
+    /// Shows the keyboard with a key highlighted, the first one to start with
+    pub fn show_selection(&mut self) {
+        self.visible = true;
+        self.selected_opt.get_or_insert((0, 0));
+    }
+
+    /// Moves the highlighted key, the first move only shows it
+    pub fn select(&mut self, direction: Direction) {
+        let (row, column) = match self.selected_opt {
+            Some(selected) if self.visible => selected,
+            _ => return self.show_selection(),
+        };
+        let row = match direction {
+            Direction::Up => row.saturating_sub(1),
+            Direction::Down => (row + 1).min(LETTER_ROWS.len()),
+            _ => row,
+        };
+        let last = self.row_len(row) - 1;
+        // Moving between rows of different lengths keeps to the last key
+        let column = match direction {
+            Direction::Left => column.min(last).saturating_sub(1),
+            Direction::Right => (column + 1).min(last),
+            _ => column.min(last),
+        };
+        self.selected_opt = Some((row, column));
+    }
+
+    /// The highlighted key, if a game controller has moved over the keyboard
+    pub fn selected(&self) -> Option<OskKey> {
+        let (row, column) = self.selected_opt?;
+        // Symbols and letters rows differ in length
+        let column = column.min(self.row_len(row) - 1);
+        match self.rows().get(row) {
+            Some(keys) => keys.chars().nth(column).map(OskKey::Char),
+            None => BOTTOM_ROW_KEYS.get(column).copied(),
+        }
+    }
+
+    fn rows(&self) -> [&'static str; 3] {
+        if self.symbols {
+            SYMBOL_ROWS
+        } else {
+            LETTER_ROWS
+        }
+    }
+
+    fn row_len(&self, row: usize) -> usize {
+        match self.rows().get(row) {
+            Some(keys) => keys.chars().count(),
+            None => BOTTOM_ROW_KEYS.len(),
+        }
+    }
+
+    pub fn view<'a, M: Clone + 'a>(&self, on_key: fn(OskKey) -> M) -> Element<'a, M> {
+        let size = if self.large { LARGE_KEY_SIZE } else { KEY_SIZE };
+        let rows = self.rows();
+        let selected_opt = self.selected();
+
+        let mut column = widget::column::with_capacity(rows.len() + 1)
+            .spacing(6)
+            .align_items(Alignment::Center);
+        for row in rows {
+            let mut keys = widget::row::with_capacity(row.len()).spacing(6);
+            for c in row.chars() {
+                let label = if self.shift {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                };
+                let osk_key = OskKey::Char(c);
+                let selected = selected_opt == Some(osk_key);
+                keys = keys.push(key(
+                    label.to_string(),
+                    size,
+                    size,
+                    selected,
+                    on_key(osk_key),
+                ));
+            }
+            column = column.push(keys);
+        }
+
+        let shift_label = if self.shift { "⇧ on" } else { "⇧" };
+        let symbols_label = if self.symbols { "abc" } else { "123" };
+        let labels = [
+            (shift_label.to_string(), 1.5),
+            (symbols_label.to_string(), 1.5),
+            (" ".to_string(), 4.0),
+            ("⌫".to_string(), 1.5),
+            ("⏎".to_string(), 1.5),
+        ];
+        let mut bottom_row = widget::row::with_capacity(labels.len()).spacing(6);
+        for ((label, width), osk_key) in labels.into_iter().zip(BOTTOM_ROW_KEYS) {
+            let selected = selected_opt == Some(osk_key);
+            bottom_row = bottom_row.push(key(label, size * width, size, selected, on_key(osk_key)));
+        }
+        column = column.push(bottom_row);
+
+        column.into()
+    }
+}
+
+fn key<'a, M: Clone + 'a>(
+    label: String,
+    width: f32,
+    height: f32,
+    selected: bool,
+    message: M,
+) -> Element<'a, M> {
+    // Highlighted for game controllers
+    let style = if selected {
+        cosmic::theme::Button::Suggested
+    } else {
+        cosmic::theme::Button::Standard
+    };
+    widget::button(
+        widget::container(widget::text(label))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x()
+            .center_y(),
+    )
+    .width(Length::Fixed(width))
+    .height(Length::Fixed(height))
+    .style(style)
+    .on_press(message)
+    .into()
+}
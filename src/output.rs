@@ -0,0 +1,73 @@
+use cosmic::cctk::sctk::output::OutputInfo;
+use wayland_client::protocol::wl_output::Transform;
+
+/// Name for logs and config, "DP-1"
+pub fn name(info: &OutputInfo) -> String {
+    info.name
+        .clone()
+        .unwrap_or_else(|| format!("{} {}", info.make, info.model))
+}
+
+/// Scale the compositor renders at, including fractional scales like 1.5
+///
+/// wl_output only reports whole scales, so the real one comes from the logical size against the
+/// current mode. Lock surfaces are rendered at this scale through wp-fractional-scale and
+/// viewporter when the compositor supports them, otherwise at the whole scale and downscaled.
+pub fn scale(info: &OutputInfo) -> f32 {
+    let whole = info.scale_factor.max(1) as f32;
+    let Some((logical_width, logical_height)) = info.logical_size else {
+        return whole;
+    };
+    let Some(mode) = info.modes.iter().find(|mode| mode.current) else {
+        return whole;
+    };
+    // Compare the long sides, the logical size is already rotated but the mode is not
+    let logical = logical_width.max(logical_height);
+    let physical = mode.dimensions.0.max(mode.dimensions.1);
+    if logical <= 0 || physical <= 0 {
+        return whole;
+    }
+    physical as f32 / logical as f32
+}
+
+/// Text size multiplier for how far away the output is probably watched from, at least 1.0
+///
+/// Sized for a 1080p desktop monitor, the short side of a 24" one is about 300mm. Outputs with
+/// more logical pixels or larger panels, like a 4K TV at scale 1, get bigger text.
+pub fn text_scale(info: &OutputInfo) -> f32 {
+    let logical = size(info)
+        .map(|(width, height)| width.min(height) as f32 / 1080.0)
+        .unwrap_or(1.0);
+    let (physical_width, physical_height) = info.physical_size;
+    let physical = physical_width.min(physical_height) as f32 / 300.0;
+    logical.max(physical).max(1.0)
+}
+
+/// Logical size, or the current mode without xdg-output
+pub fn size(info: &OutputInfo) -> Option<(i32, i32)> {
+    info.logical_size.or_else(|| {
+        let mode = info.modes.iter().find(|mode| mode.current)?;
+        Some(mode.dimensions)
+    })
+}
+
+/// Taller than wide after the output transform
+pub fn is_portrait(info: &OutputInfo) -> bool {
+    if let Some((width, height)) = info.logical_size {
+        return height > width;
+    }
+    // Without xdg-output, rotate the current mode by the transform
+    let Some(mode) = info.modes.iter().find(|mode| mode.current) else {
+        return false;
+    };
+    let (width, height) = mode.dimensions;
+    let rotated = matches!(
+        info.transform,
+        Transform::_90 | Transform::_270 | Transform::Flipped90 | Transform::Flipped270
+    );
+    if rotated {
+        width > height
+    } else {
+        height > width
+    }
+}
@@ -0,0 +1,224 @@
+//! Display power through wlr-output-power-management
+//!
+//! iced does not expose the protocol, so a short-lived connection of its own sets the mode, and
+//! a long-lived one watches for outputs that were turned off by anyone.
+
+use cosmic::iced::{futures::SinkExt, subscription, Subscription};
+use std::{any::TypeId, collections::HashMap, error::Error, time::Duration};
+use tokio::{sync::mpsc, task, time};
+use wayland_client::{
+    backend::ObjectId,
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::{
+        wl_output::{self, WlOutput},
+        wl_registry::{self, WlRegistry},
+    },
+    Connection, Dispatch, Proxy, QueueHandle,
+};
+use wayland_protocols_wlr::output_power_management::v1::client::{
+    zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1,
+    zwlr_output_power_v1::{self, ZwlrOutputPowerV1},
+};
+
+#[derive(Default)]
+struct State {
+    // Set while watching, output names and modes are sent here
+    mode_tx_opt: Option<mpsc::UnboundedSender<(String, bool)>>,
+    manager_opt: Option<ZwlrOutputPowerManagerV1>,
+    names: HashMap<ObjectId, String>,
+}
+
+impl State {
+    fn watch_output(&self, output: &WlOutput, qh: &QueueHandle<Self>) {
+        if let Some(manager) = &self.manager_opt {
+            manager.get_output_power(output, qh, output.id());
+        }
+    }
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        state: &mut Self,
+        registry: &WlRegistry,
+        event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        // Hotplugged outputs
+        if let wl_registry::Event::Global {
+            name,
+            interface,
+            version,
+        } = event
+        {
+            if state.mode_tx_opt.is_some() && interface == WlOutput::interface().name {
+                let output: WlOutput = registry.bind(name, version.min(4), qh, ());
+                state.watch_output(&output, qh);
+            }
+        }
+    }
+}
+
+impl Dispatch<WlOutput, ()> for State {
+    fn event(
+        state: &mut Self,
+        output: &WlOutput,
+        event: wl_output::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        // Sent before the power mode, which is only sent once the power object exists
+        if let wl_output::Event::Name { name } = event {
+            state.names.insert(output.id(), name);
+        }
+    }
+}
+
+impl Dispatch<ZwlrOutputPowerManagerV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _manager: &ZwlrOutputPowerManagerV1,
+        _event: <ZwlrOutputPowerManagerV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ZwlrOutputPowerV1, ObjectId> for State {
+    fn event(
+        state: &mut Self,
+        power: &ZwlrOutputPowerV1,
+        event: zwlr_output_power_v1::Event,
+        output_id: &ObjectId,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_output_power_v1::Event::Mode { mode } => {
+                let (Some(mode_tx), Some(name)) = (&state.mode_tx_opt, state.names.get(output_id))
+                else {
+                    return;
+                };
+                let on = matches!(mode.into_result(), Ok(zwlr_output_power_v1::Mode::On));
+                let _ = mode_tx.send((name.clone(), on));
+            }
+            zwlr_output_power_v1::Event::Failed if state.mode_tx_opt.is_some() => {
+                // Sent when the output goes away, the object is useless from then on
+                log::debug!("output power {}: no longer watched", power.id());
+                power.destroy();
+            }
+            zwlr_output_power_v1::Event::Failed => {
+                log::warn!("output power {}: failed to set mode", power.id());
+            }
+            _ => {}
+        }
+    }
+}
+
+fn set_power_blocking(on: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, mut queue) = registry_queue_init::<State>(&conn)?;
+    let qh = queue.handle();
+    let manager: ZwlrOutputPowerManagerV1 = globals.bind(&qh, 1..=1, ())?;
+    let mut state = State::default();
+
+    let mode = if on {
+        zwlr_output_power_v1::Mode::On
+    } else {
+        zwlr_output_power_v1::Mode::Off
+    };
+    let mut powers = Vec::new();
+    for global in globals.contents().clone_list() {
+        if global.interface != WlOutput::interface().name {
+            continue;
+        }
+        let output: WlOutput = globals
+            .registry()
+            .bind(global.name, global.version.min(4), &qh, ());
+        let power = manager.get_output_power(&output, &qh, output.id());
+        power.set_mode(mode);
+        powers.push(power);
+    }
+    // Wait for failed events before tearing down, the mode stays set afterwards
+    queue.roundtrip(&mut state)?;
+
+    for power in powers {
+        power.destroy();
+    }
+    manager.destroy();
+    conn.flush()?;
+    Ok(())
+}
+
+/// Powers all outputs on or off
+pub async fn set_power(on: bool) -> Result<(), String> {
+    task::spawn_blocking(move || set_power_blocking(on).map_err(|err| err.to_string()))
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+fn watch_blocking(
+    mode_tx: mpsc::UnboundedSender<(String, bool)>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, mut queue) = registry_queue_init::<State>(&conn)?;
+    let qh = queue.handle();
+    let mut state = State {
+        mode_tx_opt: Some(mode_tx),
+        manager_opt: Some(globals.bind(&qh, 1..=1, ())?),
+        names: HashMap::new(),
+    };
+    for global in globals.contents().clone_list() {
+        if global.interface == WlOutput::interface().name {
+            let output: WlOutput =
+                globals
+                    .registry()
+                    .bind(global.name, global.version.min(4), &qh, ());
+            state.watch_output(&output, &qh);
+        }
+    }
+    // Until the subscription stops listening
+    while !state
+        .mode_tx_opt
+        .as_ref()
+        .map_or(false, |mode_tx| mode_tx.is_closed())
+    {
+        queue.blocking_dispatch(&mut state)?;
+    }
+    Ok(())
+}
+
+/// Output names with whether they are powered on, sent for every output and on every change
+pub fn subscription() -> Subscription<(String, bool)> {
+    struct OutputPowerSubscription;
+
+    subscription::channel(
+        TypeId::of::<OutputPowerSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            let (mode_tx, mut mode_rx) = mpsc::unbounded_channel();
+            let watcher = task::spawn_blocking(move || {
+                if let Err(err) = watch_blocking(mode_tx) {
+                    log::info!("not watching output power: {}", err);
+                }
+            });
+            while let Some(mode) = mode_rx.recv().await {
+                if msg_tx.send(mode).await.is_err() {
+                    // The locker stopped listening
+                    break;
+                }
+            }
+            // The watcher ends with the next event after this
+            drop(mode_rx);
+            let _ = watcher.await;
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
@@ -0,0 +1,108 @@
+//! Pending updates from PackageKit, for machines that sit locked for days
+//!
+//! Only the package cache is read, refreshing it is left to the desktop's software center or a
+//! timer. An offline update prepared for the next boot waits on a restart.
+
+use cosmic::iced::{
+    futures::{channel::mpsc, stream, FutureExt, SinkExt, StreamExt},
+    subscription, Subscription,
+};
+use std::{any::TypeId, path::Path, time::Duration};
+use tokio::time;
+use zbus::{dbus_proxy, zvariant::OwnedObjectPath, Connection, Result};
+
+// Any filter, as a PackageKit bitfield with only the "none" bit set
+const FILTER_NONE: u64 = 1 << 1;
+// Link systemd's system-update.target looks for, made when an offline update is prepared
+const OFFLINE_UPDATE: &str = "/system-update";
+// Cache refreshes without an UpdatesChanged still show up eventually
+const POLL_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[dbus_proxy(
+    interface = "org.freedesktop.PackageKit",
+    default_service = "org.freedesktop.PackageKit",
+    default_path = "/org/freedesktop/PackageKit"
+)]
+trait PackageKit {
+    fn create_transaction(&self) -> Result<OwnedObjectPath>;
+
+    #[dbus_proxy(signal)]
+    fn updates_changed(&self) -> Result<()>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.PackageKit.Transaction",
+    default_service = "org.freedesktop.PackageKit"
+)]
+trait Transaction {
+    fn get_updates(&self, filter: u64) -> Result<()>;
+
+    #[dbus_proxy(signal)]
+    fn package(&self, info: u32, package_id: &str, summary: &str) -> Result<()>;
+
+    #[dbus_proxy(signal)]
+    fn finished(&self, exit: u32, runtime: u32) -> Result<()>;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UpdatesInfo {
+    pub count: usize,
+    pub reboot_required: bool,
+}
+
+pub fn subscription() -> Subscription<UpdatesInfo> {
+    struct UpdatesSubscription;
+
+    subscription::channel(
+        TypeId::of::<UpdatesSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            if let Err(err) = handler(&mut msg_tx).await {
+                log::warn!("PackageKit error: {}", err);
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+async fn handler(msg_tx: &mut mpsc::Sender<UpdatesInfo>) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let packagekit = PackageKitProxy::new(&zbus).await?;
+    let mut changes = packagekit.receive_updates_changed().await?;
+    loop {
+        let info = UpdatesInfo {
+            count: count_updates(&zbus, &packagekit).await?,
+            reboot_required: Path::new(OFFLINE_UPDATE).exists(),
+        };
+        if msg_tx.send(info).await.is_err() {
+            // The locker stopped listening
+            return Ok(());
+        }
+
+        if let Ok(None) = time::timeout(POLL_INTERVAL, changes.next()).await {
+            return Ok(());
+        }
+    }
+}
+
+async fn count_updates(zbus: &Connection, packagekit: &PackageKitProxy<'_>) -> Result<usize> {
+    let path = packagekit.create_transaction().await?;
+    let transaction = TransactionProxy::builder(zbus).path(path)?.build().await?;
+    // Subscribed before starting, the transaction may finish right away
+    let packages = transaction.receive_package().await?.map(|_| true);
+    let finished = transaction.receive_finished().await?.map(|_| false);
+    let mut signals = stream::select(packages, finished);
+    transaction.get_updates(FILTER_NONE).await?;
+    let mut count = 0;
+    while let Some(true) = signals.next().await {
+        count += 1;
+    }
+    // Packages sent just before Finished may still be queued
+    while let Some(Some(true)) = signals.next().now_or_never() {
+        count += 1;
+    }
+    Ok(count)
+}
@@ -0,0 +1,145 @@
+//! Keeps the session locked when the locker panics
+//!
+//! Unwinding out of the iced runtime would end the process with whatever state the outputs were
+//! left in. Instead the panicking process replaces itself with a fresh locker: its Wayland
+//! connection closes, the compositor keeps the outputs blanked as a locked session without a
+//! client, and the new process takes over the lock.
+//!
+//! A panic before anything was shown is most likely a broken GPU driver, so the new locker uses
+//! the software renderer.
+//!
+//! A crash report is written first, see `crash_report`.
+//!
+//! The locked state is also kept in a file in the runtime dir while locked, so a daemon restarted
+//! by its service manager, or with the whole session, locks again instead of coming up unlocked.
+//! The runtime dir goes away on logout, a file left behind by a kill only locks once too often.
+
+use crate::crash_report;
+use std::{
+    env, fs, io,
+    os::unix::process::CommandExt,
+    panic,
+    path::PathBuf,
+    process,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+const RESTARTS_VAR: &str = "ZERO_LOCK_PANIC_RESTARTS";
+// Set for a relaunched daemon that has to take over the lock right away
+pub const RELOCK_VAR: &str = "ZERO_LOCK_RELOCK";
+// Next to the control socket
+const LOCKED_FILE_NAME: &str = "zero-lock.locked";
+// Renderer selection read by iced, tried in order
+const BACKEND_VAR: &str = "ICED_BACKEND";
+const SOFTWARE_BACKEND: &str = "tiny-skia";
+// Stop replacing a locker that panics on startup, the session stays locked either way
+const MAX_RESTARTS: u32 = 5;
+// A locker that ran this long was not crash looping, its panic starts the count over
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+static RENDERED: AtomicBool = AtomicBool::new(false);
+static LOCKED: AtomicBool = AtomicBool::new(false);
+
+/// A lock surface was drawn, so the renderer works
+pub fn rendered() {
+    RENDERED.store(true, Ordering::Relaxed);
+}
+
+/// Whether the session is locked, a daemon relaunched while locked locks again on startup
+pub fn locked(locked: bool) {
+    LOCKED.store(locked, Ordering::Relaxed);
+    let Some(path) = locked_file_path() else {
+        return;
+    };
+    let res = if locked {
+        fs::write(&path, b"")
+    } else {
+        fs::remove_file(&path).or_else(|err| match err.kind() {
+            io::ErrorKind::NotFound => Ok(()),
+            _ => Err(err),
+        })
+    };
+    if let Err(err) = res {
+        log::warn!("failed to update {}: {}", path.display(), err);
+    }
+}
+
+/// Takes the relock request of a relaunch, so this process' own children don't inherit it, or
+/// finds the session still marked locked by a locker that didn't get to unlock
+pub fn relock() -> bool {
+    let relock = env::var_os(RELOCK_VAR).is_some();
+    env::remove_var(RELOCK_VAR);
+    if !relock && locked_file_path().is_some_and(|path| path.exists()) {
+        log::info!("the last locker exited while locked, locking again");
+        return true;
+    }
+    relock
+}
+
+fn locked_file_path() -> Option<PathBuf> {
+    Some(PathBuf::from(env::var_os("XDG_RUNTIME_DIR")?).join(LOCKED_FILE_NAME))
+}
+
+/// Uses the software renderer from now on, before the first surface is created
+pub fn software_rendering() {
+    env::set_var(BACKEND_VAR, SOFTWARE_BACKEND);
+}
+
+pub fn install() {
+    let default_hook = panic::take_hook();
+    let started = Instant::now();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+
+        let restarts = if started.elapsed() >= STABLE_RUN {
+            0
+        } else {
+            env::var(RESTARTS_VAR)
+                .ok()
+                .and_then(|restarts| restarts.parse::<u32>().ok())
+                .unwrap_or(0)
+        };
+        let details = [
+            (
+                "renderer",
+                env::var(BACKEND_VAR).unwrap_or_else(|_| "default".to_string()),
+            ),
+            ("rendered", RENDERED.load(Ordering::Relaxed).to_string()),
+            ("locked", LOCKED.load(Ordering::Relaxed).to_string()),
+            ("restarts", restarts.to_string()),
+        ];
+        if let Some(path) = crash_report::write(info, &details) {
+            log::error!("crash report written to {}", path.display());
+        }
+        if restarts >= MAX_RESTARTS {
+            log::error!("locker panicked {} times, giving up", restarts);
+            process::abort();
+        }
+
+        log::error!("locker panicked, relaunching to keep the session locked");
+        log::logger().flush();
+        let exe = match env::current_exe() {
+            Ok(exe) => exe,
+            Err(err) => {
+                log::error!("failed to find the locker executable: {}", err);
+                process::abort();
+            }
+        };
+        let mut command = process::Command::new(exe);
+        command
+            .args(env::args_os().skip(1))
+            .env(RESTARTS_VAR, (restarts + 1).to_string());
+        if LOCKED.load(Ordering::Relaxed) {
+            command.env(RELOCK_VAR, "1");
+        }
+        if !RENDERED.load(Ordering::Relaxed) {
+            log::warn!("nothing was drawn yet, falling back to software rendering");
+            command.env(BACKEND_VAR, SOFTWARE_BACKEND);
+        }
+        // Only returns on failure
+        let err = command.exec();
+        log::error!("failed to relaunch the locker: {}", err);
+        process::abort();
+    }));
+}
@@ -0,0 +1,210 @@
+use cosmic::iced::{futures::SinkExt, subscription, Subscription};
+use serde_json::Value;
+use std::{any::TypeId, collections::HashMap, io, time::Duration};
+use tokio::{process::Command, time};
+
+const DEFAULT_SINK: &str = "@DEFAULT_AUDIO_SINK@";
+// Microphones and other capture devices, including virtual ones
+const SOURCE_CLASS_PREFIX: &str = "Audio/Source";
+// Cameras, and the screencasts of xdg-desktop-portal which have no device behind them
+const VIDEO_SOURCE_CLASS: &str = "Video/Source";
+// How often capture streams are looked for, pw-dump has no cheaper way to watch links
+const CAPTURE_POLL: Duration = Duration::from_secs(5);
+const VOLUME_STEP: &str = "5%";
+// Same limit as the COSMIC volume applet
+const VOLUME_LIMIT: &str = "1.5";
+
+#[derive(Clone, Copy, Debug)]
+pub enum VolumeAction {
+    Raise,
+    Lower,
+    ToggleMute,
+}
+
+/// Apps recording from a microphone, camera or the screen, by name
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CaptureInfo {
+    pub microphone: Vec<String>,
+    pub camera: Vec<String>,
+    // Screen sharing goes on behind the lock screen
+    pub screen: Vec<String>,
+}
+
+impl CaptureInfo {
+    pub fn is_empty(&self) -> bool {
+        self.microphone.is_empty() && self.camera.is_empty() && self.screen.is_empty()
+    }
+}
+
+/// The apps capturing audio, video or the screen, sent when they change
+pub fn capture_subscription() -> Subscription<CaptureInfo> {
+    struct CaptureSubscription;
+
+    subscription::channel(
+        TypeId::of::<CaptureSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            let mut last_opt = None;
+            loop {
+                match capture_info().await {
+                    Ok(info) => {
+                        if last_opt.as_ref() != Some(&info) {
+                            if msg_tx.send(info.clone()).await.is_err() {
+                                // The locker stopped listening
+                                break;
+                            }
+                            last_opt = Some(info);
+                        }
+                    }
+                    Err(err) => {
+                        log::info!("not watching capture streams: {}", err);
+                        break;
+                    }
+                }
+                time::sleep(CAPTURE_POLL).await;
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+// Active links from capture sources, named after the app on the other end
+async fn capture_info() -> io::Result<CaptureInfo> {
+    let dump = output(Command::new("pw-dump")).await?;
+    let objects: Vec<Value> = serde_json::from_str(&dump)?;
+    let nodes: HashMap<u64, &Value> = objects
+        .iter()
+        .filter(|object| object["type"] == "PipeWire:Interface:Node")
+        .filter_map(|object| Some((object["id"].as_u64()?, &object["info"]["props"])))
+        .collect();
+
+    let mut info = CaptureInfo::default();
+    for link in objects
+        .iter()
+        .filter(|object| object["type"] == "PipeWire:Interface:Link")
+        .map(|object| &object["info"])
+        .filter(|link| link["state"] == "active")
+    {
+        let (Some(source), Some(sink)) = (
+            link["output-node-id"]
+                .as_u64()
+                .and_then(|id| nodes.get(&id)),
+            link["input-node-id"].as_u64().and_then(|id| nodes.get(&id)),
+        ) else {
+            continue;
+        };
+        let class = source["media.class"].as_str().unwrap_or_default();
+        let apps = if class.starts_with(SOURCE_CLASS_PREFIX) {
+            &mut info.microphone
+        } else if class == VIDEO_SOURCE_CLASS && source["device.api"].is_string() {
+            &mut info.camera
+        } else if class == VIDEO_SOURCE_CLASS {
+            &mut info.screen
+        } else {
+            continue;
+        };
+        let Some(app) = sink["application.name"]
+            .as_str()
+            .or_else(|| sink["node.name"].as_str())
+        else {
+            continue;
+        };
+        if !apps.iter().any(|known| known == app) {
+            apps.push(app.to_string());
+        }
+    }
+    info.microphone.sort();
+    info.camera.sort();
+    info.screen.sort();
+    Ok(info)
+}
+
+/// Changes the default sink volume through WirePlumber
+pub async fn volume(action: VolumeAction) -> io::Result<()> {
+    let mut command = Command::new("wpctl");
+    match action {
+        VolumeAction::Raise => command
+            .arg("set-volume")
+            .arg("--limit")
+            .arg(VOLUME_LIMIT)
+            .arg(DEFAULT_SINK)
+            .arg(format!("{}+", VOLUME_STEP)),
+        VolumeAction::Lower => command
+            .arg("set-volume")
+            .arg(DEFAULT_SINK)
+            .arg(format!("{}-", VOLUME_STEP)),
+        VolumeAction::ToggleMute => command.arg("set-mute").arg(DEFAULT_SINK).arg("toggle"),
+    };
+
+    output(command).await.map(|_| ())
+}
+
+/// Mutes every capture source that isn't muted already, returning their node ids
+pub async fn mute_sources() -> io::Result<Vec<u32>> {
+    let mut muted = Vec::new();
+    for id in source_ids().await? {
+        let mut command = Command::new("wpctl");
+        command.arg("get-volume").arg(id.to_string());
+        // "Volume: 0.40 [MUTED]"
+        if output(command).await?.contains("[MUTED]") {
+            continue;
+        }
+        set_mute(id, true).await?;
+        muted.push(id);
+    }
+    Ok(muted)
+}
+
+/// Unmutes the sources [`mute_sources`] muted, those that went away are skipped
+pub async fn unmute_sources(ids: Vec<u32>) {
+    for id in ids {
+        if let Err(err) = set_mute(id, false).await {
+            log::info!("failed to unmute source {}: {}", id, err);
+        }
+    }
+}
+
+async fn set_mute(id: u32, mute: bool) -> io::Result<()> {
+    let mut command = Command::new("wpctl");
+    command
+        .arg("set-mute")
+        .arg(id.to_string())
+        .arg(if mute { "1" } else { "0" });
+    output(command).await.map(|_| ())
+}
+
+// Node ids of the capture sources in pw-dump's list of every object
+async fn source_ids() -> io::Result<Vec<u32>> {
+    let dump = output(Command::new("pw-dump")).await?;
+    let objects: Vec<Value> = serde_json::from_str(&dump)?;
+    Ok(objects
+        .iter()
+        .filter(|object| object["type"] == "PipeWire:Interface:Node")
+        .filter(|object| {
+            object["info"]["props"]["media.class"]
+                .as_str()
+                .map_or(false, |class| class.starts_with(SOURCE_CLASS_PREFIX))
+        })
+        .filter_map(|object| object["id"].as_u64())
+        .filter_map(|id| u32::try_from(id).ok())
+        .collect())
+}
+
+async fn output(mut command: Command) -> io::Result<String> {
+    let output = command.output().await?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "{:?} exited with {}",
+                command.as_std().get_program(),
+                output.status
+            ),
+        ))
+    }
+}
@@ -0,0 +1,169 @@
+//! Third-party widgets loaded with dlopen
+//!
+//! iced elements cannot cross a shared library boundary, so a plugin exports a C vtable and
+//! describes its widget as an icon name and a line of text which zero-lock renders itself. A
+//! plugin is a cdylib exporting:
+//!
+//! ```c
+//! const struct zero_lock_plugin_v1 *zero_lock_plugin_v1(void);
+//! ```
+//!
+//! All strings are NUL-terminated UTF-8 owned by the plugin and must stay valid until the next
+//! call into the same instance. Every call happens on the UI thread.
+//!
+//! Without the `plugins` feature nothing is loaded and configured plugins are reported missing.
+
+#[cfg(feature = "plugins")]
+use libloading::Library;
+use std::{
+    env,
+    ffi::{c_char, c_void, CStr},
+    path::PathBuf,
+};
+
+#[cfg_attr(not(feature = "plugins"), allow(dead_code))]
+pub const ABI_VERSION: u32 = 1;
+#[cfg_attr(not(feature = "plugins"), allow(dead_code))]
+const ENTRY_SYMBOL: &[u8] = b"zero_lock_plugin_v1\0";
+const SYSTEM_DIR: &str = "/usr/lib/zero-lock/plugins";
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug)]
+pub enum PluginEvent {
+    Tick = 0,
+    Locked = 1,
+    Unlocking = 2,
+}
+
+#[repr(C)]
+pub struct PluginVTable {
+    pub abi_version: u32,
+    pub name: unsafe extern "C" fn() -> *const c_char,
+    pub create: unsafe extern "C" fn() -> *mut c_void,
+    pub event: unsafe extern "C" fn(instance: *mut c_void, event: u32),
+    // May return null to hide the widget
+    pub text: unsafe extern "C" fn(instance: *mut c_void) -> *const c_char,
+    // May return null for no icon
+    pub icon: unsafe extern "C" fn(instance: *mut c_void) -> *const c_char,
+    pub destroy: unsafe extern "C" fn(instance: *mut c_void),
+}
+
+#[cfg_attr(not(feature = "plugins"), allow(dead_code))]
+pub struct Plugin {
+    name: String,
+    vtable: &'static PluginVTable,
+    instance: *mut c_void,
+    // Must outlive the vtable and instance, dropped last
+    #[cfg(feature = "plugins")]
+    _library: Library,
+}
+
+impl Plugin {
+    #[cfg(not(feature = "plugins"))]
+    fn load(_path: PathBuf) -> Result<Self, String> {
+        Err("built without plugin support".to_string())
+    }
+
+    #[cfg(feature = "plugins")]
+    fn load(path: PathBuf) -> Result<Self, String> {
+        // Safety: loading runs the library constructors, only configured plugins are loaded
+        let library = unsafe { Library::new(&path) }.map_err(|err| err.to_string())?;
+        let vtable: &'static PluginVTable = unsafe {
+            let entry = library
+                .get::<unsafe extern "C" fn() -> *const PluginVTable>(ENTRY_SYMBOL)
+                .map_err(|err| err.to_string())?;
+            // The vtable lives in the library, which is kept loaded as long as the plugin
+            entry().as_ref().ok_or("plugin returned no vtable")?
+        };
+        if vtable.abi_version != ABI_VERSION {
+            return Err(format!(
+                "unsupported ABI version {} (expected {})",
+                vtable.abi_version, ABI_VERSION
+            ));
+        }
+
+        let name = unsafe { c_string((vtable.name)()) }.unwrap_or_default();
+        let instance = unsafe { (vtable.create)() };
+        if instance.is_null() {
+            return Err("plugin failed to create an instance".to_string());
+        }
+
+        Ok(Self {
+            name,
+            vtable,
+            instance,
+            _library: library,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn event(&self, event: PluginEvent) {
+        unsafe { (self.vtable.event)(self.instance, event as u32) }
+    }
+
+    pub fn text(&self) -> Option<String> {
+        unsafe { c_string((self.vtable.text)(self.instance)) }
+    }
+
+    pub fn icon(&self) -> Option<String> {
+        unsafe { c_string((self.vtable.icon)(self.instance)) }
+    }
+}
+
+impl Drop for Plugin {
+    fn drop(&mut self) {
+        unsafe { (self.vtable.destroy)(self.instance) }
+    }
+}
+
+unsafe fn c_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+}
+
+fn plugin_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::with_capacity(2);
+    match env::var_os("XDG_DATA_HOME") {
+        Some(data_home) => dirs.push(PathBuf::from(data_home).join("zero-lock/plugins")),
+        None => {
+            if let Some(home) = env::var_os("HOME") {
+                dirs.push(PathBuf::from(home).join(".local/share/zero-lock/plugins"));
+            }
+        }
+    }
+    dirs.push(PathBuf::from(SYSTEM_DIR));
+    dirs
+}
+
+/// Loads the named plugins, `clock` is looked up as `libclock.so` in the plugin directories
+pub fn load(names: &[String]) -> Vec<Plugin> {
+    let dirs = plugin_dirs();
+    let mut plugins = Vec::with_capacity(names.len());
+    for name in names {
+        let file_name = format!("lib{}.so", name);
+        let Some(path) = dirs
+            .iter()
+            .map(|dir| dir.join(&file_name))
+            .find(|path| path.is_file())
+        else {
+            log::warn!("plugin {:?} not found in {:?}", name, dirs);
+            continue;
+        };
+
+        match Plugin::load(path.clone()) {
+            Ok(plugin) => {
+                log::info!("loaded plugin {:?} from {:?}", plugin.name(), path);
+                plugins.push(plugin);
+            }
+            Err(err) => {
+                log::warn!("failed to load plugin {:?}: {}", path, err);
+            }
+        }
+    }
+    plugins
+}
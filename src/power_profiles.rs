@@ -0,0 +1,104 @@
+use cosmic::iced::{
+    futures::{channel::mpsc, SinkExt, StreamExt},
+    Subscription,
+};
+use std::{any::TypeId, collections::HashMap};
+use zbus::{dbus_proxy, zvariant::OwnedValue, Connection, Result};
+
+use crate::{fl, retry};
+
+#[dbus_proxy(
+    interface = "net.hadess.PowerProfiles",
+    default_service = "net.hadess.PowerProfiles",
+    default_path = "/net/hadess/PowerProfiles"
+)]
+trait PowerProfiles {
+    #[dbus_proxy(property)]
+    fn active_profile(&self) -> Result<String>;
+
+    #[dbus_proxy(property)]
+    fn set_active_profile(&self, profile: &str) -> Result<()>;
+
+    #[dbus_proxy(property)]
+    fn profiles(&self) -> Result<Vec<HashMap<String, OwnedValue>>>;
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PowerProfileInfo {
+    pub active: String,
+    pub available: Vec<String>,
+}
+
+impl PowerProfileInfo {
+    pub fn next(&self) -> Option<&str> {
+        let index = self
+            .available
+            .iter()
+            .position(|profile| profile == &self.active)?;
+        let next = self.available.get((index + 1) % self.available.len())?;
+        Some(next.as_str())
+    }
+}
+
+pub fn icon(profile: &str) -> &'static str {
+    match profile {
+        "performance" => "power-profile-performance-symbolic",
+        "power-saver" => "power-profile-power-saver-symbolic",
+        _ => "power-profile-balanced-symbolic",
+    }
+}
+
+pub fn label(profile: &str) -> String {
+    match profile {
+        "performance" => fl!("power-profile-performance"),
+        "power-saver" => fl!("power-profile-power-saver"),
+        _ => fl!("power-profile-balanced"),
+    }
+}
+
+pub fn subscription() -> Subscription<Option<PowerProfileInfo>> {
+    struct PowerProfilesSubscription;
+
+    retry::subscription(
+        TypeId::of::<PowerProfilesSubscription>(),
+        "power-profiles-daemon",
+        Some(None),
+        handler,
+    )
+}
+
+async fn handler(mut msg_tx: mpsc::Sender<Option<PowerProfileInfo>>) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let power_profiles = PowerProfilesProxy::new(&zbus).await?;
+
+    let available = power_profiles
+        .profiles()
+        .await?
+        .iter()
+        .filter_map(|profile| profile.get("Profile")?.downcast_ref::<str>())
+        .map(|profile| profile.to_string())
+        .collect::<Vec<_>>();
+
+    let mut changes = power_profiles.receive_active_profile_changed().await;
+    loop {
+        let active = power_profiles.active_profile().await?;
+        let info = PowerProfileInfo {
+            active,
+            available: available.clone(),
+        };
+        if msg_tx.send(Some(info)).await.is_err() {
+            // The locker stopped listening
+            return Ok(());
+        }
+
+        if changes.next().await.is_none() {
+            return Ok(());
+        }
+    }
+}
+
+pub async fn set_active_profile(profile: String) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let power_profiles = PowerProfilesProxy::new(&zbus).await?;
+    power_profiles.set_active_profile(&profile).await
+}
@@ -0,0 +1,33 @@
+use std::{fs, path::Path};
+
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// Whether the machine runs on external power, desktops without a battery always do
+pub fn on_ac() -> bool {
+    let Ok(entries) = fs::read_dir(POWER_SUPPLY_DIR) else {
+        return true;
+    };
+    let mut battery = false;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        match read_trimmed(&path.join("type")).as_deref() {
+            Some("Mains" | "USB") => {
+                if read_trimmed(&path.join("online")).as_deref() == Some("1") {
+                    return true;
+                }
+            }
+            // Peripherals like mice report their batteries here too
+            Some("Battery") => {
+                if read_trimmed(&path.join("scope")).as_deref() != Some("Device") {
+                    battery = true;
+                }
+            }
+            _ => {}
+        }
+    }
+    !battery
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    Some(fs::read_to_string(path).ok()?.trim().to_string())
+}
@@ -0,0 +1,23 @@
+//! `zero-lock render`, the preview window saved to a PNG file for screenshots and theme previews
+//!
+//! The toolkit has no offscreen target, so the lock screen is drawn in a preview window of the
+//! requested size, read back once it has settled, and the window closes again.
+
+use cosmic::iced::window::Screenshot;
+use cosmic::iced_renderer::graphics::image::image_rs::{self, ColorType};
+use std::{path::Path, time::Duration};
+
+// Images decode and wallpapers blur in the background, the first frames are missing them
+pub const SETTLE_DELAY: Duration = Duration::from_secs(2);
+
+pub fn save(path: &Path, screenshot: &Screenshot) -> Result<(), String> {
+    image_rs::save_buffer_with_format(
+        path,
+        screenshot.as_ref(),
+        screenshot.size.width,
+        screenshot.size.height,
+        ColorType::Rgba8,
+        image_rs::ImageFormat::Png,
+    )
+    .map_err(|err| err.to_string())
+}
@@ -0,0 +1,54 @@
+//! VPN links as systemd-resolved knows them, for systems without NetworkManager
+//!
+//! wg-quick and OpenVPN's resolved scripts give the tunnel DNS servers of its own, resolved has no
+//! notion of a VPN, so the link's device type tells.
+
+use std::{fs, path::Path};
+use zbus::{dbus_proxy, Connection, Result};
+
+#[dbus_proxy(
+    interface = "org.freedesktop.resolve1.Manager",
+    default_service = "org.freedesktop.resolve1",
+    default_path = "/org/freedesktop/resolve1"
+)]
+trait Manager {
+    // Interface index, address family and address of each DNS server, index 0 for global ones
+    #[dbus_proxy(property, name = "DNS")]
+    fn dns(&self) -> Result<Vec<(i32, i32, Vec<u8>)>>;
+}
+
+/// The interface name of the first WireGuard or tun link with DNS servers
+pub async fn active_vpn(zbus: &Connection) -> Result<Option<String>> {
+    let manager = ManagerProxy::new(zbus).await?;
+    let mut ifindexes: Vec<i32> = manager
+        .dns()
+        .await?
+        .into_iter()
+        .map(|(ifindex, ..)| ifindex)
+        .filter(|ifindex| *ifindex > 0)
+        .collect();
+    ifindexes.dedup();
+    Ok(ifindexes.into_iter().find_map(vpn_link))
+}
+
+fn vpn_link(ifindex: i32) -> Option<String> {
+    for entry in fs::read_dir("/sys/class/net").ok()?.flatten() {
+        let path = entry.path();
+        let Ok(index) = fs::read_to_string(path.join("ifindex")) else {
+            continue;
+        };
+        if index.trim().parse::<i32>().ok() != Some(ifindex) {
+            continue;
+        }
+        return is_tunnel(&path).then(|| entry.file_name().to_string_lossy().into_owned());
+    }
+    None
+}
+
+// WireGuard, or a tun device as OpenVPN and most other clients use
+fn is_tunnel(path: &Path) -> bool {
+    let wireguard = fs::read_to_string(path.join("uevent")).map_or(false, |uevent| {
+        uevent.lines().any(|line| line == "DEVTYPE=wireguard")
+    });
+    wireguard || path.join("tun_flags").exists()
+}
@@ -0,0 +1,72 @@
+//! Subscriptions to D-Bus services that may fail or restart while locked
+//!
+//! The handler runs again after an error or when its service went away, with the delay doubling
+//! up to a few minutes. A handler that can't send returns, the locker stopped listening then, and
+//! the subscription waits to be dropped instead of panicking.
+
+use cosmic::iced::{futures::channel::mpsc, subscription, Subscription};
+use std::{
+    any::TypeId,
+    future::Future,
+    time::{Duration, Instant},
+};
+use tokio::time;
+
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+// Also how long a handler has to run for the delay to start over
+const RETRY_DELAY_MAX: Duration = Duration::from_secs(300);
+
+/// Runs `handler` until the locker stops listening, `reset_opt` is sent after an error so stale
+/// state isn't shown meanwhile
+pub fn subscription<T, F, Fut>(
+    id: TypeId,
+    name: &'static str,
+    reset_opt: Option<T>,
+    mut handler: F,
+) -> Subscription<T>
+where
+    T: Clone + Send + 'static,
+    F: FnMut(mpsc::Sender<T>) -> Fut + Send + 'static,
+    Fut: Future<Output = zbus::Result<()>> + Send,
+{
+    subscription::channel(id, 16, move |mut msg_tx| async move {
+        let mut delay = RETRY_DELAY;
+        let mut failed = false;
+        loop {
+            let started = Instant::now();
+            let res = handler(msg_tx.clone()).await;
+            if msg_tx.is_closed() {
+                break;
+            }
+            if started.elapsed() >= RETRY_DELAY_MAX {
+                delay = RETRY_DELAY;
+                failed = false;
+            }
+            match res {
+                Ok(()) => log::info!("{} went away, retrying in {:?}", name, delay),
+                Err(err) => {
+                    // Only the first of a row is a warning, a service that isn't installed fails
+                    // every time
+                    if failed {
+                        log::info!("{} error: {}, retrying in {:?}", name, err, delay);
+                    } else {
+                        log::warn!("{} error: {}, retrying in {:?}", name, err, delay);
+                    }
+                    failed = true;
+                    if let Some(reset) = &reset_opt {
+                        if msg_tx.try_send(reset.clone()).is_err() && msg_tx.is_closed() {
+                            break;
+                        }
+                    }
+                }
+            }
+            time::sleep(delay).await;
+            delay = (delay * 2).min(RETRY_DELAY_MAX);
+        }
+
+        // Nothing listens anymore, the subscription is about to be dropped
+        loop {
+            time::sleep(Duration::new(60, 0)).await;
+        }
+    })
+}
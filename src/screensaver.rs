@@ -0,0 +1,355 @@
+//! org.freedesktop.ScreenSaver on the session bus, for xdg-screensaver and apps that ask whether
+//! the screen is locked, and optionally org.gnome.ScreenSaver for apps that only know GNOME's
+//!
+//! zero-lock's own interface has the lock state as properties, for status bars and scripts.
+
+use chrono::{DateTime, Local};
+use cosmic::iced::{
+    futures::{channel::mpsc, SinkExt, StreamExt},
+    subscription, Subscription,
+};
+use std::{
+    any::TypeId,
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{sync::watch, time};
+use zbus::{
+    dbus_interface, fdo, fdo::DBusProxy, Connection, ConnectionBuilder, MessageHeader, Result,
+    SignalContext,
+};
+
+const BUS_NAME: &str = "org.freedesktop.ScreenSaver";
+// KDE's path, where most callers look, and the one in the specification
+const PATHS: [&str; 2] = ["/ScreenSaver", "/org/freedesktop/ScreenSaver"];
+const GNOME_BUS_NAME: &str = "org.gnome.ScreenSaver";
+const GNOME_PATH: &str = "/org/gnome/ScreenSaver";
+const ZERO_LOCK_BUS_NAME: &str = "io.github.jilv220.ZeroLock";
+const ZERO_LOCK_PATH: &str = "/io/github/jilv220/ZeroLock";
+
+#[derive(Clone, Debug)]
+pub enum ScreenSaverEvent {
+    Lock,
+    // Turn the displays back on, as if someone touched a key
+    WakeUp,
+    // Apps holding off idle locking, "Firefox: video-playing"
+    Inhibitors(Vec<String>),
+}
+
+struct Inhibitor {
+    // Unique bus name of the caller, its inhibitors go away with it
+    sender: String,
+    app_name: String,
+    reason: String,
+}
+
+#[derive(Default)]
+struct Inhibitors {
+    next_cookie: u32,
+    by_cookie: BTreeMap<u32, Inhibitor>,
+}
+
+impl Inhibitors {
+    fn list(&self) -> Vec<String> {
+        self.by_cookie
+            .values()
+            .map(|inhibitor| format!("{}: {}", inhibitor.app_name, inhibitor.reason))
+            .collect()
+    }
+}
+
+// What both interfaces answer from
+#[derive(Clone)]
+struct State {
+    event_tx: mpsc::Sender<ScreenSaverEvent>,
+    // Locked since, None while not locked
+    active_rx: watch::Receiver<Option<DateTime<Local>>>,
+    inhibitors: Arc<Mutex<Inhibitors>>,
+}
+
+impl State {
+    async fn send(&mut self, event: ScreenSaverEvent) {
+        let _ = self.event_tx.send(event).await;
+    }
+
+    async fn send_inhibitors(&mut self) {
+        let list = self.inhibitors.lock().unwrap().list();
+        self.send(ScreenSaverEvent::Inhibitors(list)).await;
+    }
+
+    fn active(&self) -> bool {
+        self.active_rx.borrow().is_some()
+    }
+
+    // Unix time, 0 while not locked
+    fn locked_since(&self) -> i64 {
+        self.active_rx
+            .borrow()
+            .map_or(0, |locked_at| locked_at.timestamp())
+    }
+
+    fn active_time(&self) -> u32 {
+        self.active_rx.borrow().map_or(0, |locked_at| {
+            (Local::now() - locked_at)
+                .num_seconds()
+                .clamp(0, u32::MAX.into()) as u32
+        })
+    }
+
+    // Activating locks, deactivating needs authentication and is refused
+    async fn set_active(&mut self, active: bool) -> bool {
+        if !active {
+            return self.active();
+        }
+        self.send(ScreenSaverEvent::Lock).await;
+        true
+    }
+}
+
+struct ScreenSaver(State);
+
+#[dbus_interface(name = "org.freedesktop.ScreenSaver")]
+impl ScreenSaver {
+    async fn lock(&mut self) {
+        self.0.send(ScreenSaverEvent::Lock).await;
+    }
+
+    fn get_active(&self) -> bool {
+        self.0.active()
+    }
+
+    /// Seconds the screen has been locked for
+    fn get_active_time(&self) -> u32 {
+        self.0.active_time()
+    }
+
+    async fn set_active(&mut self, active: bool) -> bool {
+        self.0.set_active(active).await
+    }
+
+    /// Holds off idle locking until UnInhibit is called with the returned cookie
+    async fn inhibit(
+        &mut self,
+        #[zbus(header)] header: MessageHeader<'_>,
+        application_name: String,
+        reason_for_inhibit: String,
+    ) -> fdo::Result<u32> {
+        let sender = header
+            .sender()?
+            .ok_or_else(|| fdo::Error::Failed("no sender".to_string()))?
+            .to_string();
+        let cookie = {
+            let mut inhibitors = self.0.inhibitors.lock().unwrap();
+            // Zero is never handed out, some callers treat it as no cookie
+            inhibitors.next_cookie = inhibitors.next_cookie.wrapping_add(1).max(1);
+            let cookie = inhibitors.next_cookie;
+            log::info!(
+                "idle inhibited by {} ({}): {}",
+                application_name,
+                sender,
+                reason_for_inhibit
+            );
+            inhibitors.by_cookie.insert(
+                cookie,
+                Inhibitor {
+                    sender,
+                    app_name: application_name,
+                    reason: reason_for_inhibit,
+                },
+            );
+            cookie
+        };
+        self.0.send_inhibitors().await;
+        Ok(cookie)
+    }
+
+    async fn un_inhibit(&mut self, cookie: u32) {
+        let removed = self.0.inhibitors.lock().unwrap().by_cookie.remove(&cookie);
+        if let Some(inhibitor) = removed {
+            log::info!("idle no longer inhibited by {}", inhibitor.app_name);
+            self.0.send_inhibitors().await;
+        }
+    }
+
+    #[dbus_interface(signal)]
+    async fn active_changed(ctxt: &SignalContext<'_>, active: bool) -> Result<()>;
+}
+
+// Drops the inhibitors of callers that left the bus without uninhibiting, like crashed browsers
+async fn watch_senders(zbus: Connection, mut state: State) -> Result<()> {
+    let dbus = DBusProxy::new(&zbus).await?;
+    let mut changes = dbus.receive_name_owner_changed().await?;
+    while let Some(change) = changes.next().await {
+        let args = change.args()?;
+        if args.new_owner().is_some() {
+            continue;
+        }
+        let name = args.name().to_string();
+        let removed = {
+            let mut inhibitors = state.inhibitors.lock().unwrap();
+            let before = inhibitors.by_cookie.len();
+            inhibitors
+                .by_cookie
+                .retain(|_, inhibitor| inhibitor.sender != name);
+            before != inhibitors.by_cookie.len()
+        };
+        if removed {
+            log::info!("{} left the bus, dropping its idle inhibitors", name);
+            state.send_inhibitors().await;
+        }
+    }
+    Ok(())
+}
+
+struct GnomeScreenSaver(State);
+
+#[dbus_interface(name = "org.gnome.ScreenSaver")]
+impl GnomeScreenSaver {
+    async fn lock(&mut self) {
+        self.0.send(ScreenSaverEvent::Lock).await;
+    }
+
+    fn get_active(&self) -> bool {
+        self.0.active()
+    }
+
+    fn get_active_time(&self) -> u32 {
+        self.0.active_time()
+    }
+
+    async fn set_active(&mut self, active: bool) -> bool {
+        self.0.set_active(active).await
+    }
+
+    async fn wake_up_screen(&mut self) {
+        self.0.send(ScreenSaverEvent::WakeUp).await;
+    }
+
+    #[dbus_interface(signal)]
+    async fn active_changed(ctxt: &SignalContext<'_>, active: bool) -> Result<()>;
+}
+
+struct ZeroLock(State);
+
+#[dbus_interface(name = "io.github.jilv220.ZeroLock")]
+impl ZeroLock {
+    #[dbus_interface(property)]
+    fn is_locked(&self) -> bool {
+        self.0.active()
+    }
+
+    /// Unix time the session was locked at, 0 while unlocked
+    #[dbus_interface(property)]
+    fn locked_since(&self) -> i64 {
+        self.0.locked_since()
+    }
+
+    #[dbus_interface(signal)]
+    async fn state_changed(
+        ctxt: &SignalContext<'_>,
+        is_locked: bool,
+        locked_since: i64,
+    ) -> Result<()>;
+}
+
+/// Serves the interfaces, `active_rx` has the time the session was locked at
+pub fn subscription(
+    active_rx: watch::Receiver<Option<DateTime<Local>>>,
+    gnome: bool,
+) -> Subscription<ScreenSaverEvent> {
+    struct ScreenSaverSubscription;
+
+    subscription::channel(
+        TypeId::of::<ScreenSaverSubscription>(),
+        16,
+        move |event_tx| async move {
+            if let Err(err) = handler(
+                State {
+                    event_tx,
+                    active_rx,
+                    inhibitors: Arc::default(),
+                },
+                gnome,
+            )
+            .await
+            {
+                log::info!("not serving the screensaver interface: {}", err);
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+// Another screensaver may hold either name, the other one is still worth serving
+async fn request_name(zbus: &Connection, name: &'static str) -> bool {
+    match zbus.request_name(name).await {
+        Ok(()) => {
+            log::info!("serving {}", name);
+            true
+        }
+        Err(err) => {
+            log::info!("not serving {}: {}", name, err);
+            false
+        }
+    }
+}
+
+async fn handler(mut state: State, gnome: bool) -> Result<()> {
+    let mut builder = ConnectionBuilder::session()?;
+    for path in PATHS {
+        builder = builder.serve_at(path, ScreenSaver(state.clone()))?;
+    }
+    if gnome {
+        builder = builder.serve_at(GNOME_PATH, GnomeScreenSaver(state.clone()))?;
+    }
+    builder = builder.serve_at(ZERO_LOCK_PATH, ZeroLock(state.clone()))?;
+    let zbus = builder.build().await?;
+    let freedesktop = request_name(&zbus, BUS_NAME).await;
+    let gnome = gnome && request_name(&zbus, GNOME_BUS_NAME).await;
+    let zero_lock = request_name(&zbus, ZERO_LOCK_BUS_NAME).await;
+    if !freedesktop && !gnome && !zero_lock {
+        return Ok(());
+    }
+    if freedesktop {
+        let (zbus, state) = (zbus.clone(), state.clone());
+        tokio::spawn(async move {
+            if let Err(err) = watch_senders(zbus, state).await {
+                log::warn!("not watching idle inhibitor callers: {}", err);
+            }
+        });
+    }
+
+    let mut active = state.active_rx.borrow_and_update().is_some();
+    while state.active_rx.changed().await.is_ok() {
+        let changed = state.active_rx.borrow_and_update().is_some();
+        if changed == active {
+            continue;
+        }
+        active = changed;
+        if freedesktop {
+            for path in PATHS {
+                ScreenSaver::active_changed(&SignalContext::new(&zbus, path)?, active).await?;
+            }
+        }
+        if gnome {
+            GnomeScreenSaver::active_changed(&SignalContext::new(&zbus, GNOME_PATH)?, active)
+                .await?;
+        }
+        if zero_lock {
+            let iface = zbus
+                .object_server()
+                .interface::<_, ZeroLock>(ZERO_LOCK_PATH)
+                .await?;
+            let ctxt = iface.signal_context();
+            let zero_lock = iface.get().await;
+            zero_lock.is_locked_changed(ctxt).await?;
+            zero_lock.locked_since_changed(ctxt).await?;
+            ZeroLock::state_changed(ctxt, active, state.locked_since()).await?;
+        }
+    }
+    Ok(())
+}
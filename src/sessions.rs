@@ -0,0 +1,148 @@
+//! Desktop sessions to start from greeter mode, read from XDG session files
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const WAYLAND_SESSIONS: &str = "/usr/share/wayland-sessions";
+const X_SESSIONS: &str = "/usr/share/xsessions";
+// Last session of each user, must be writable by the greeter user
+const LAST_SESSION_DIR: &str = "/var/cache/zero-lock/sessions";
+// X sessions need a server, greetd only runs the command
+const X_WRAPPER: [&str; 2] = ["startx", "/usr/bin/env"];
+
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub name: String,
+    pub cmd: Vec<String>,
+    // Passed on to the session, XDG_SESSION_TYPE and XDG_SESSION_DESKTOP
+    pub env: Vec<String>,
+}
+
+// Dropdown labels
+impl AsRef<str> for Session {
+    fn as_ref(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Session {
+    /// A configured command, for systems without session files
+    pub fn command(cmd: Vec<String>) -> Self {
+        Self {
+            name: cmd.join(" "),
+            cmd,
+            env: Vec::new(),
+        }
+    }
+}
+
+fn parse(path: &Path, wayland: bool) -> Option<Session> {
+    let contents = fs::read_to_string(path).ok()?;
+    let mut name_opt = None;
+    let mut exec_opt = None;
+    let mut desktop_names_opt = None;
+    let mut in_entry = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_entry {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        match key.trim() {
+            "Name" => name_opt = Some(value.trim().to_string()),
+            "Exec" => exec_opt = Some(value.trim().to_string()),
+            "DesktopNames" => desktop_names_opt = Some(value.trim().replace(';', ":")),
+            "Hidden" | "NoDisplay" if value.trim() == "true" => return None,
+            _ => {}
+        }
+    }
+
+    // Field codes like %f have nothing to expand to
+    let exec: Vec<String> = exec_opt?
+        .split_whitespace()
+        .filter(|arg| !arg.starts_with('%'))
+        .map(str::to_string)
+        .collect();
+    if exec.is_empty() {
+        return None;
+    }
+    let cmd = if wayland {
+        exec
+    } else {
+        X_WRAPPER
+            .iter()
+            .map(|arg| arg.to_string())
+            .chain(exec)
+            .collect()
+    };
+    let mut env = vec![format!(
+        "XDG_SESSION_TYPE={}",
+        if wayland { "wayland" } else { "x11" }
+    )];
+    if let Some(desktop_names) = desktop_names_opt {
+        env.push(format!(
+            "XDG_SESSION_DESKTOP={}",
+            desktop_names.trim_end_matches(':')
+        ));
+    }
+    Some(Session {
+        name: name_opt.unwrap_or_else(|| cmd.join(" ")),
+        cmd,
+        env,
+    })
+}
+
+/// Wayland sessions first, then X sessions, each sorted by name
+pub fn load() -> Vec<Session> {
+    let mut sessions = Vec::new();
+    for (dir, wayland) in [(WAYLAND_SESSIONS, true), (X_SESSIONS, false)] {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map_or(false, |ext| ext == "desktop"))
+            .collect();
+        paths.sort();
+        let mut dir_sessions: Vec<_> = paths
+            .iter()
+            .filter_map(|path| parse(path, wayland))
+            .collect();
+        dir_sessions.sort_by(|a, b| a.name.cmp(&b.name));
+        sessions.extend(dir_sessions);
+    }
+    log::info!("found {} sessions", sessions.len());
+    sessions
+}
+
+// Usernames are chosen by root, but never trust one with a path
+fn last_session_path(username: &str) -> Option<PathBuf> {
+    if username.is_empty() || username.contains('/') || username.starts_with('.') {
+        return None;
+    }
+    Some(Path::new(LAST_SESSION_DIR).join(username))
+}
+
+/// Name of the session the user last started
+pub fn last(username: &str) -> Option<String> {
+    let name = fs::read_to_string(last_session_path(username)?).ok()?;
+    Some(name.trim().to_string())
+}
+
+pub fn remember(username: &str, name: &str) {
+    let Some(path) = last_session_path(username) else {
+        return;
+    };
+    if let Err(err) = fs::create_dir_all(LAST_SESSION_DIR).and_then(|()| fs::write(&path, name)) {
+        log::warn!("failed to remember session in {:?}: {}", path, err);
+    }
+}
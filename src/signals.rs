@@ -0,0 +1,66 @@
+//! SIGTERM, SIGUSR1 and SIGUSR2 as events, instead of the default of terminating the locker
+//!
+//! A locker killed by SIGTERM while locked leaves the session locked with nothing to unlock it,
+//! so the locker decides what to do with it.
+
+use cosmic::iced::{
+    futures::{channel::mpsc, stream, SinkExt, Stream, StreamExt},
+    subscription, Subscription,
+};
+use std::{any::TypeId, io, time::Duration};
+use tokio::{
+    signal::unix::{signal, SignalKind},
+    time,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignalEvent {
+    // SIGTERM
+    Terminate,
+    // SIGUSR1
+    ToggleDebugOverlay,
+    // SIGUSR2
+    Redraw,
+}
+
+pub fn subscription() -> Subscription<SignalEvent> {
+    struct SignalSubscription;
+
+    subscription::channel(
+        TypeId::of::<SignalSubscription>(),
+        16,
+        |event_tx| async move {
+            if let Err(err) = handler(event_tx).await {
+                log::warn!("not handling signals: {}", err);
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+// Each signal as a stream of its event
+fn events(kind: SignalKind, event: SignalEvent) -> io::Result<impl Stream<Item = SignalEvent>> {
+    let signal = signal(kind)?;
+    Ok(stream::unfold(signal, move |mut signal| async move {
+        signal.recv().await?;
+        Some((event, signal))
+    }))
+}
+
+async fn handler(mut event_tx: mpsc::Sender<SignalEvent>) -> io::Result<()> {
+    let mut events = stream::select(
+        events(SignalKind::terminate(), SignalEvent::Terminate)?,
+        stream::select(
+            events(SignalKind::user_defined1(), SignalEvent::ToggleDebugOverlay)?,
+            events(SignalKind::user_defined2(), SignalEvent::Redraw)?,
+        ),
+    );
+    while let Some(event) = events.next().await {
+        log::info!("received {:?}", event);
+        let _ = event_tx.send(event).await;
+    }
+    Ok(())
+}
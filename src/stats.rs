@@ -0,0 +1,107 @@
+//! Lock, unlock and failed attempt counts for `zero-lock stats`, optionally kept across logins
+//! in the state dir
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::{env, fs, path::PathBuf, time::Duration};
+
+const FILE_NAME: &str = "zero-lock/stats.json";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Stats {
+    // Counting since
+    since: DateTime<Local>,
+    locks: u64,
+    unlocks: u64,
+    failures: u64,
+    // Summed over every unlock, for the average
+    locked_secs: u64,
+    #[serde(skip)]
+    persist: bool,
+}
+
+impl Stats {
+    /// Empty, or what was saved last time if persisted
+    pub fn load(persist: bool) -> Self {
+        let saved_opt = persist
+            .then(path)
+            .flatten()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|data| {
+                serde_json::from_str::<Self>(&data)
+                    .map_err(|err| log::warn!("ignoring saved stats: {}", err))
+                    .ok()
+            });
+        let mut stats = saved_opt.unwrap_or_else(|| Self {
+            since: Local::now(),
+            locks: 0,
+            unlocks: 0,
+            failures: 0,
+            locked_secs: 0,
+            persist: false,
+        });
+        stats.persist = persist;
+        stats
+    }
+
+    pub fn locked(&mut self) {
+        self.locks += 1;
+        self.save();
+    }
+
+    pub fn unlocked(&mut self, locked_for: Duration) {
+        self.unlocks += 1;
+        self.locked_secs += locked_for.as_secs();
+        self.save();
+    }
+
+    pub fn failed(&mut self) {
+        self.failures += 1;
+        self.save();
+    }
+
+    /// Reply to `zero-lock stats`
+    pub fn report(&self) -> String {
+        let mut report = format!("since: {}\n", self.since.format("%F %T"));
+        report.push_str(&format!("locks: {}\n", self.locks));
+        report.push_str(&format!("unlocks: {}\n", self.unlocks));
+        report.push_str(&format!("failed attempts: {}\n", self.failures));
+        if let Some(average) = self.locked_secs.checked_div(self.unlocks) {
+            report.push_str(&format!(
+                "average locked: {}m {}s\n",
+                average / 60,
+                average % 60
+            ));
+        }
+        report
+    }
+
+    // Small enough to write on every change, the locker may be killed at any time
+    fn save(&self) {
+        if !self.persist {
+            return;
+        }
+        let Some(path) = path() else {
+            return;
+        };
+        let res = serde_json::to_string(self)
+            .map_err(|err| err.to_string())
+            .and_then(|data| {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent).map_err(|err| err.to_string())?;
+                }
+                fs::write(&path, data).map_err(|err| err.to_string())
+            });
+        if let Err(err) = res {
+            log::warn!("failed to save stats to {:?}: {}", path, err);
+        }
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    let state_home = match env::var_os("XDG_STATE_HOME") {
+        Some(state_home) => PathBuf::from(state_home),
+        None => PathBuf::from(env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(state_home.join(FILE_NAME))
+}
@@ -0,0 +1,49 @@
+//! Relaunches the locker if it crashes
+//!
+//! ext-session-lock keeps the session locked when the locking client dies, so the outputs stay
+//! blank and a new locker can take over the lock. Without a supervisor nothing would, and the
+//! only way back in would be another VT.
+
+use crate::panic_hook;
+use std::{
+    env,
+    error::Error,
+    ffi::OsString,
+    process::Command,
+    thread,
+    time::{Duration, Instant},
+};
+
+const RESTART_DELAY: Duration = Duration::from_millis(250);
+const RESTART_DELAY_MAX: Duration = Duration::from_secs(5);
+// A locker that ran this long was not crash looping
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+pub fn main(args: Vec<OsString>) -> Result<(), Box<dyn Error>> {
+    let exe = env::current_exe()?;
+    let mut delay = RESTART_DELAY;
+    let mut relaunch = false;
+    loop {
+        let started = Instant::now();
+        let mut command = Command::new(&exe);
+        command.args(&args);
+        // A daemon may have crashed while locked, so it can't know whether to lock again
+        if relaunch {
+            command.env(panic_hook::RELOCK_VAR, "1");
+        }
+        let status = command.status()?;
+        if status.success() {
+            // Unlocked normally
+            return Ok(());
+        }
+
+        log::error!("locker exited with {}, relaunching", status);
+        relaunch = true;
+        if started.elapsed() >= STABLE_RUN {
+            delay = RESTART_DELAY;
+        }
+        thread::sleep(delay);
+        // Never give up, that would leave the session locked with no way to unlock it
+        delay = (delay * 2).min(RESTART_DELAY_MAX);
+    }
+}
@@ -0,0 +1,172 @@
+//! swaylock's config file and options, so a setup migrated from sway keeps its look
+//!
+//! Every line is a long option without the dashes, `color=1e1e2e` or `daemonize`. Options that
+//! only style swaylock's ring are accepted and ignored, zero-lock draws its own prompt.
+
+use std::{env, ffi::OsString, fs, path::PathBuf};
+
+use crate::config::{BackgroundScaling, Config, Rgb, TypingFeedback};
+
+// Colors, sizes and text of the ring indicator
+const RING_OPTIONS: [&str; 12] = [
+    "bs-hl-color",
+    "caps-lock-",
+    "font",
+    "indicator",
+    "inside",
+    "key-hl-color",
+    "layout-",
+    "line",
+    "ring",
+    "separator-color",
+    "text",
+    "disable-caps-lock-text",
+];
+// Ring options that take no value
+const RING_FLAGS: [&str; 4] = [
+    "indicator",
+    "indicator-idle-visible",
+    "indicator-caps-lock",
+    "disable-caps-lock-text",
+];
+// Behaviour that zero-lock has no equivalent for or always does
+const IGNORED_OPTIONS: [&str; 6] = [
+    "ignore-empty-password",
+    "show-failed-attempts",
+    "show-keyboard-layout",
+    "hide-keyboard-layout",
+    "debug",
+    // Swaylock's -f in its config, only the command line option detaches
+    "daemonize",
+];
+// Short forms of the ignored options and ring flags, -n is i3lock's --nofork
+const IGNORED_SHORT: [&str; 8] = ["-e", "-F", "-k", "-K", "-L", "-l", "-n", "-d"];
+
+fn config_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::with_capacity(3);
+    let home_opt = env::var_os("HOME").map(PathBuf::from);
+    if let Some(home) = &home_opt {
+        paths.push(home.join(".swaylock/config"));
+    }
+    match env::var_os("XDG_CONFIG_HOME") {
+        Some(config_home) => paths.push(PathBuf::from(config_home).join("swaylock/config")),
+        None => {
+            if let Some(home) = &home_opt {
+                paths.push(home.join(".config/swaylock/config"));
+            }
+        }
+    }
+    paths.push(PathBuf::from("/etc/swaylock/config"));
+    paths
+}
+
+/// The first swaylock config found, in the order swaylock looks for one
+pub fn load() -> Option<Config> {
+    let (data, path) = config_paths()
+        .into_iter()
+        .find_map(|path| Some((fs::read_to_string(&path).ok()?, path)))?;
+    log::info!("no zero-lock config, using {}", path.display());
+
+    // swaylock's indicator doesn't show the password length either
+    let mut config = Config {
+        typing_feedback: TypingFeedback::Segments,
+        ..Config::default()
+    };
+    for line in data.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, value_opt) = match line.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (line, None),
+        };
+        if !apply(&mut config, name, value_opt) {
+            log::info!("ignoring swaylock option {:?}", line);
+        }
+    }
+    Some(config)
+}
+
+/// Applies one swaylock option by its long name, false if it is unknown or its value is invalid
+pub fn apply(config: &mut Config, name: &str, value_opt: Option<&str>) -> bool {
+    match (name, value_opt) {
+        // "[[output]:]path", an image for one output is drawn on all of them
+        ("image", Some(value)) => {
+            let path = match value.split_once(':') {
+                Some((output, path)) if !output.contains('/') => path,
+                _ => value,
+            };
+            config.background_opt = Some(PathBuf::from(path));
+        }
+        ("color", Some(value)) => match Rgb::parse(value) {
+            Some(color) => config.background_color_opt = Some(color),
+            None => return false,
+        },
+        ("scaling", Some(value)) => {
+            config.background_scaling = match value {
+                "fill" => BackgroundScaling::Fill,
+                "fit" => BackgroundScaling::Fit,
+                "stretch" => BackgroundScaling::Stretch,
+                "center" => BackgroundScaling::Center,
+                "tile" => BackgroundScaling::Tile,
+                "solid_color" => {
+                    config.background_opt = None;
+                    config.desktop_wallpaper = false;
+                    return true;
+                }
+                _ => return false,
+            };
+        }
+        ("no-unlock-indicator", None) => config.typing_feedback = TypingFeedback::Hidden,
+        // swaylock-effects
+        ("grace", Some(value)) => match value.parse::<f32>() {
+            Ok(grace) if grace >= 0.0 => config.grace_period = grace.ceil() as u64,
+            _ => return false,
+        },
+        ("timestr", Some(value)) => config.clock_format = value.to_string(),
+        ("tiling", None) => config.background_scaling = BackgroundScaling::Tile,
+        _ => return is_ring_option(name) || IGNORED_OPTIONS.contains(&name),
+    }
+    true
+}
+
+fn is_ring_option(name: &str) -> bool {
+    RING_OPTIONS.iter().any(|option| name.starts_with(option))
+}
+
+/// Drops the command line options that only style swaylock's ring or change nothing, so
+/// swayidle and xss-lock invocations written for swaylock or i3lock keep working
+pub fn strip_args(args: impl IntoIterator<Item = OsString>) -> Vec<OsString> {
+    let mut stripped = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let Some(arg_str) = arg.to_str() else {
+            stripped.push(arg);
+            continue;
+        };
+        if IGNORED_SHORT.contains(&arg_str) {
+            continue;
+        }
+        let Some(option) = arg_str.strip_prefix("--") else {
+            stripped.push(arg);
+            continue;
+        };
+        let (name, has_value) = match option.split_once('=') {
+            Some((name, _)) => (name, true),
+            None => (option, false),
+        };
+        if is_ring_option(name) {
+            log::info!("ignoring swaylock option {}", arg_str);
+            // "--ring-color 1e1e2e", the value is the next argument
+            if !has_value && !RING_FLAGS.contains(&name) {
+                args.next();
+            }
+        } else if IGNORED_OPTIONS.contains(&name) && name != "daemonize" {
+            log::info!("ignoring swaylock option {}", arg_str);
+        } else {
+            stripped.push(arg);
+        }
+    }
+    stripped
+}
@@ -0,0 +1,48 @@
+//! Host name, kernel, uptime and whether a reboot is pending, for the `system_info` widget
+
+use chrono::{DateTime, Duration, Local};
+use std::{fs, path::Path};
+
+// Debian and Ubuntu's update-notifier, Fedora's needs-restarting has no file to check
+const REBOOT_REQUIRED: &str = "/run/reboot-required";
+
+#[derive(Clone, Debug)]
+pub struct SystemInfo {
+    pub host: String,
+    pub kernel: String,
+    pub booted_at: DateTime<Local>,
+    pub reboot_required: bool,
+}
+
+impl SystemInfo {
+    pub fn load() -> Option<Self> {
+        let host = read_trimmed("/proc/sys/kernel/hostname")?;
+        let kernel = read_trimmed("/proc/sys/kernel/osrelease")?;
+        // Seconds since boot, then seconds idle
+        let uptime = read_trimmed("/proc/uptime")?
+            .split_whitespace()
+            .next()?
+            .parse::<f64>()
+            .ok()?;
+        // Distributions that replace the modules of the running kernel on upgrade, like Arch
+        let modules_gone = !["/usr/lib/modules", "/lib/modules"]
+            .iter()
+            .any(|dir| Path::new(dir).join(&kernel).exists());
+        Some(Self {
+            host,
+            booted_at: Local::now() - Duration::seconds(uptime as i64),
+            reboot_required: Path::new(REBOOT_REQUIRED).exists() || modules_gone,
+            kernel,
+        })
+    }
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(data) => Some(data.trim().to_string()),
+        Err(err) => {
+            log::info!("failed to read {}: {}", path, err);
+            None
+        }
+    }
+}
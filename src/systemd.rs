@@ -0,0 +1,59 @@
+//! sd_notify and socket activation without libsystemd
+
+use std::{
+    env,
+    os::{
+        fd::{FromRawFd, RawFd},
+        linux::net::SocketAddrExt,
+        unix::net::{SocketAddr, UnixDatagram, UnixListener},
+    },
+    process,
+    time::Duration,
+};
+
+const LISTEN_FDS_START: RawFd = 3;
+
+/// Sends a state like "READY=1" to the service manager, does nothing outside a service
+pub fn notify(state: &str) {
+    let Some(path) = env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    let path = path.to_string_lossy().into_owned();
+
+    let res = (|| {
+        let socket = UnixDatagram::unbound()?;
+        // A leading @ is an abstract socket
+        let addr = match path.strip_prefix('@') {
+            Some(name) => SocketAddr::from_abstract_name(name)?,
+            None => SocketAddr::from_pathname(&path)?,
+        };
+        socket.send_to_addr(state.as_bytes(), &addr)
+    })();
+    if let Err(err) = res {
+        log::warn!("failed to notify service manager of {:?}: {}", state, err);
+    }
+}
+
+/// How often the service manager expects WATCHDOG=1, if the watchdog is enabled
+pub fn watchdog_interval() -> Option<Duration> {
+    let usec = env::var("WATCHDOG_USEC").ok()?.parse::<u64>().ok()?;
+    if usec == 0 {
+        return None;
+    }
+    Some(Duration::from_micros(usec))
+}
+
+/// The socket passed by socket activation, see sd_listen_fds(3)
+pub fn listener() -> Option<UnixListener> {
+    // Meant for this process only, not for children or a relaunched locker
+    let pid = env::var("LISTEN_PID").ok()?.parse::<u32>().ok();
+    let fds = env::var("LISTEN_FDS").ok()?.parse::<u32>().ok();
+    env::remove_var("LISTEN_PID");
+    env::remove_var("LISTEN_FDS");
+    env::remove_var("LISTEN_FDNAMES");
+    if pid != Some(process::id()) || fds.unwrap_or(0) == 0 {
+        return None;
+    }
+    // Passed fds start after stdio, only the first one is used
+    Some(unsafe { UnixListener::from_raw_fd(LISTEN_FDS_START) })
+}
@@ -0,0 +1,112 @@
+//! Timers that only wake the locker when something is due
+//!
+//! Every message redraws all surfaces, so polling "has the timeout passed yet" every second is
+//! what keeps a locked laptop from idling.
+
+use chrono::{Local, Timelike};
+use cosmic::iced::{
+    futures::{channel::mpsc, future, SinkExt, StreamExt},
+    subscription, Subscription,
+};
+use inotify::{Inotify, WatchMask};
+use std::{
+    any::TypeId,
+    ffi::OsStr,
+    hash::Hash,
+    io,
+    time::{Duration, Instant, SystemTime},
+};
+
+// How often the wall clock is compared with the monotonic one, in the background without waking
+// the locker, and how far they may drift apart before it counts as a jump
+const CLOCK_CHECK: Duration = Duration::from_secs(5);
+const CLOCK_JUMP: Duration = Duration::from_secs(2);
+// timedated and `ln -sf` both replace the symlink
+const LOCALTIME_DIR: &str = "/etc";
+const LOCALTIME_NAME: &str = "localtime";
+
+pub fn every<I: Hash + 'static>(id: I, interval: Duration) -> Subscription<()> {
+    subscription::unfold(id, (), move |()| async move {
+        tokio::time::sleep(interval).await;
+        ((), ())
+    })
+}
+
+/// Fires at the start of every minute, so the clock changes with the wall clock
+///
+/// The sleep doesn't follow the wall clock, a new ID after [`clock_changes`] restarts it.
+pub fn minutes<I: Hash + 'static>(id: I) -> Subscription<()> {
+    subscription::unfold(id, (), |()| async {
+        let now = Local::now();
+        let into_minute = Duration::new(now.second() as u64, now.nanosecond() % 1_000_000_000);
+        tokio::time::sleep(Duration::from_secs(60).saturating_sub(into_minute)).await;
+        ((), ())
+    })
+}
+
+/// Fires once at the deadline
+///
+/// The deadline is part of the subscription ID, in whole seconds after epoch so that a deadline
+/// pushed back by continuous input restarts the timer at most once a second.
+pub fn deadline(id: &'static str, epoch: Instant, deadline: Instant) -> Subscription<()> {
+    // Rounded up so the timeout has passed when it fires
+    let secs = deadline.saturating_duration_since(epoch).as_secs() + 1;
+    subscription::unfold((id, secs), false, move |fired| async move {
+        if fired {
+            future::pending::<()>().await;
+        }
+        tokio::time::sleep_until((epoch + Duration::from_secs(secs)).into()).await;
+        ((), true)
+    })
+}
+
+/// Fires when the wall clock jumps, after an NTP sync, a resume or someone setting it, and when
+/// /etc/localtime points to another timezone
+pub fn clock_changes() -> Subscription<()> {
+    struct ClockSubscription;
+
+    subscription::channel(
+        TypeId::of::<ClockSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            let timezone_tx = msg_tx.clone();
+            tokio::spawn(async move {
+                if let Err(err) = watch_timezone(timezone_tx).await {
+                    log::info!("not watching the timezone: {}", err);
+                }
+            });
+
+            let mut wall = SystemTime::now();
+            let mut instant = Instant::now();
+            loop {
+                tokio::time::sleep(CLOCK_CHECK).await;
+                let (now_wall, now_instant) = (SystemTime::now(), Instant::now());
+                // The monotonic clock stops during suspend, so resuming shows up as a jump too
+                let expected = wall + now_instant.duration_since(instant);
+                let drift = match now_wall.duration_since(expected) {
+                    Ok(ahead) => ahead,
+                    Err(err) => err.duration(),
+                };
+                if drift > CLOCK_JUMP {
+                    log::info!("wall clock jumped by {}s", drift.as_secs());
+                    let _ = msg_tx.send(()).await;
+                }
+                (wall, instant) = (now_wall, now_instant);
+            }
+        },
+    )
+}
+
+async fn watch_timezone(mut msg_tx: mpsc::Sender<()>) -> io::Result<()> {
+    let inotify = Inotify::init()?;
+    let mask = WatchMask::CREATE | WatchMask::MOVED_TO | WatchMask::CLOSE_WRITE;
+    inotify.watches().add(LOCALTIME_DIR, mask)?;
+    let mut events = inotify.into_event_stream([0; 1024])?;
+    while let Some(event) = events.next().await {
+        if event?.name.as_deref() == Some(OsStr::new(LOCALTIME_NAME)) {
+            log::info!("timezone changed");
+            let _ = msg_tx.send(()).await;
+        }
+    }
+    Ok(())
+}
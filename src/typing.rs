@@ -0,0 +1,127 @@
+use cosmic::{
+    iced::{self, widget::container, Background, Border, Length},
+    theme, widget, Element,
+};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::animation::Timeline;
+
+const SEGMENTS: usize = 8;
+const FLASH_DURATION: Duration = Duration::from_millis(300);
+
+#[derive(Clone, Copy, Debug)]
+enum Flash {
+    Key(usize),
+    Erase,
+}
+
+/// Swaylock style feedback, a random segment lights up per key so the length stays hidden
+#[derive(Clone, Debug)]
+pub struct TypingIndicator {
+    flash_opt: Option<(Flash, Timeline)>,
+    // Without fading the segment just stays lit for the flash duration
+    fade: bool,
+    seed: u32,
+}
+
+impl TypingIndicator {
+    pub fn new(fade: bool) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(1, |duration| duration.subsec_nanos());
+        Self {
+            flash_opt: None,
+            fade,
+            // xorshift gets stuck on zero
+            seed: seed | 1,
+        }
+    }
+
+    pub fn animating(&self) -> bool {
+        self.flash_opt.is_some()
+    }
+
+    pub fn keystroke(&mut self) {
+        let last_opt = match self.flash_opt {
+            Some((Flash::Key(last), _)) => Some(last),
+            _ => None,
+        };
+        // Never the same segment twice so repeated keys still visibly register
+        let mut segment = self.next_random() as usize % SEGMENTS;
+        if Some(segment) == last_opt {
+            segment = (segment + 1) % SEGMENTS;
+        }
+        self.flash_opt = Some((Flash::Key(segment), Timeline::new(FLASH_DURATION)));
+    }
+
+    pub fn erase(&mut self) {
+        self.flash_opt = Some((Flash::Erase, Timeline::new(FLASH_DURATION)));
+    }
+
+    pub fn frame(&mut self) {
+        if self
+            .flash_opt
+            .map_or(false, |(_, timeline)| timeline.is_done())
+        {
+            self.flash_opt = None;
+        }
+    }
+
+    fn next_random(&mut self) -> u32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed
+    }
+
+    pub fn view<'a, M: 'a>(&self) -> Element<'a, M> {
+        let mut row = widget::row::with_capacity(SEGMENTS).spacing(4);
+        let lit = |timeline: Timeline| {
+            if self.fade {
+                1.0 - timeline.linear()
+            } else {
+                1.0
+            }
+        };
+        for i in 0..SEGMENTS {
+            let (lit, erase) = match self.flash_opt {
+                Some((Flash::Key(segment), timeline)) if segment == i => (lit(timeline), false),
+                Some((Flash::Erase, timeline)) => (lit(timeline), true),
+                _ => (0.0, false),
+            };
+            row = row.push(segment(lit, erase));
+        }
+        row.into()
+    }
+}
+
+fn segment<'a, M: 'a>(lit: f32, erase: bool) -> Element<'a, M> {
+    widget::container(widget::Space::new(Length::Fixed(32.0), Length::Fixed(6.0)))
+        .style(theme::Container::custom(move |theme| {
+            let cosmic = theme.cosmic();
+            let idle: iced::Color = cosmic.palette.neutral_5.into();
+            let flash: iced::Color = if erase {
+                cosmic.destructive_color().into()
+            } else {
+                cosmic.accent_color().into()
+            };
+            container::Appearance {
+                background: Some(Background::Color(mix(idle, flash, lit))),
+                border: Border {
+                    radius: 3.0.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        }))
+        .into()
+}
+
+fn mix(a: iced::Color, b: iced::Color, t: f32) -> iced::Color {
+    iced::Color {
+        r: a.r + (b.r - a.r) * t,
+        g: a.g + (b.g - a.g) * t,
+        b: a.b + (b.b - a.b) * t,
+        a: a.a + (b.a - a.a) * t,
+    }
+}
@@ -0,0 +1,136 @@
+//! Unread counts for the `unread` widget, from a maildir or a command
+//!
+//! Counters fed by notifications are counted by the locker from the ones it received while
+//! locked, they need nothing read here.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::{Duration, SystemTime},
+};
+use tokio::{process::Command, task, time};
+
+use crate::config::UnreadSource;
+
+// A count that takes longer is skipped until the next minute
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Unread {
+    pub count: usize,
+    // Sender and subject of the newest message, or the second line of the command's output
+    pub latest_opt: Option<String>,
+}
+
+/// Counts every source, None for notification sources and for ones that failed
+pub async fn load(sources: Vec<UnreadSource>) -> Vec<Option<Unread>> {
+    let mut unread = Vec::with_capacity(sources.len());
+    for source in sources {
+        unread.push(match source {
+            UnreadSource::Maildir(path) => task::spawn_blocking(move || maildir(&path))
+                .await
+                .ok()
+                .flatten(),
+            UnreadSource::Command(command) => run(&command).await,
+            UnreadSource::Notifications(_) => None,
+        });
+    }
+    unread
+}
+
+// Everything in new/, and messages in cur/ without the Seen flag
+fn maildir(path: &Path) -> Option<Unread> {
+    let mut unseen: Vec<(SystemTime, PathBuf)> = Vec::new();
+    for dir in ["new", "cur"] {
+        let entries = match fs::read_dir(path.join(dir)) {
+            Ok(entries) => entries,
+            Err(err) => {
+                log::info!("failed to read maildir {}: {}", path.display(), err);
+                return None;
+            }
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            // "unique:2,FRS", flags after the colon
+            let seen = name
+                .rsplit_once(":2,")
+                .is_some_and(|(_, flags)| flags.contains('S'));
+            if dir == "cur" && seen {
+                continue;
+            }
+            let modified = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            unseen.push((modified, entry.path()));
+        }
+    }
+    let latest_opt = unseen
+        .iter()
+        .max_by_key(|(modified, _)| *modified)
+        .and_then(|(_, path)| headers(path));
+    Some(Unread {
+        count: unseen.len(),
+        latest_opt,
+    })
+}
+
+// "Sender — Subject", folded headers and encoded words are left as they are
+fn headers(path: &Path) -> Option<String> {
+    let data = fs::read(path).ok()?;
+    let data = String::from_utf8_lossy(&data);
+    let mut from_opt = None;
+    let mut subject_opt = None;
+    for line in data.lines().take_while(|line| !line.is_empty()) {
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("from") {
+                // The display name without the address
+                let value = value.split('<').next().unwrap_or(value);
+                from_opt = Some(value.trim().trim_matches('"').to_string());
+            } else if name.eq_ignore_ascii_case("subject") {
+                subject_opt = Some(value.trim().to_string());
+            }
+        }
+    }
+    match (from_opt, subject_opt) {
+        (Some(from), Some(subject)) => Some(format!("{} — {}", from, subject)),
+        (from_opt, subject_opt) => from_opt.or(subject_opt),
+    }
+}
+
+// The count on the first line of the output, an optional preview on the second
+async fn run(command: &str) -> Option<Unread> {
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| log::warn!("failed to run unread command {:?}: {}", command, err))
+        .ok()?;
+    let output = match time::timeout(COMMAND_TIMEOUT, child.wait_with_output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(err)) => {
+            log::warn!("failed to wait for unread command {:?}: {}", command, err);
+            return None;
+        }
+        Err(_) => {
+            log::warn!("unread command {:?} timed out, killed it", command);
+            return None;
+        }
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    let Some(count) = lines.next().and_then(|line| line.trim().parse().ok()) else {
+        log::warn!("unread command {:?} didn't print a count", command);
+        return None;
+    };
+    Some(Unread {
+        count,
+        latest_opt: lines.next().map(|line| line.trim().to_string()),
+    })
+}
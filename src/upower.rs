@@ -0,0 +1,215 @@
+//! Batteries of wireless keyboards, mice and headsets, and UPSes, from UPower
+//!
+//! Levels change slowly, so devices are polled instead of following every property change. UPSes
+//! are the ones UPower finds itself, mostly over USB, a UPS only known to NUT isn't seen.
+
+use cosmic::iced::{
+    futures::{channel::mpsc, SinkExt},
+    subscription, Subscription,
+};
+use std::{any::TypeId, time::Duration};
+use tokio::time;
+use zbus::{dbus_proxy, zvariant::OwnedObjectPath, Connection, Result};
+
+// At or below this, the level is shown as a warning
+pub const LOW_PERCENTAGE: f64 = 20.0;
+// UP_DEVICE_KIND_UPS
+const UPS_TYPE: u32 = 3;
+// Discharging, empty and pending discharge in UP_DEVICE_STATE
+const ON_BATTERY_STATES: [u32; 3] = [2, 3, 6];
+
+#[dbus_proxy(
+    interface = "org.freedesktop.UPower",
+    default_service = "org.freedesktop.UPower",
+    default_path = "/org/freedesktop/UPower"
+)]
+trait UPower {
+    fn enumerate_devices(&self) -> Result<Vec<OwnedObjectPath>>;
+}
+
+#[dbus_proxy(
+    interface = "org.freedesktop.UPower.Device",
+    default_service = "org.freedesktop.UPower"
+)]
+trait Device {
+    // UP_DEVICE_KIND, see `PeripheralKind::from_type`
+    #[dbus_proxy(property, name = "Type")]
+    fn type_(&self) -> Result<u32>;
+
+    // False for peripherals, true for the batteries powering the machine
+    #[dbus_proxy(property)]
+    fn power_supply(&self) -> Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn is_present(&self) -> Result<bool>;
+
+    #[dbus_proxy(property)]
+    fn model(&self) -> Result<String>;
+
+    #[dbus_proxy(property)]
+    fn percentage(&self) -> Result<f64>;
+
+    #[dbus_proxy(property)]
+    fn state(&self) -> Result<u32>;
+
+    // Seconds, 0 while unknown
+    #[dbus_proxy(property)]
+    fn time_to_empty(&self) -> Result<i64>;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeripheralKind {
+    Keyboard,
+    Mouse,
+    Headset,
+    Controller,
+    Other,
+}
+
+impl PeripheralKind {
+    fn from_type(kind: u32) -> Option<Self> {
+        match kind {
+            5 | 14 => Some(Self::Mouse),
+            6 => Some(Self::Keyboard),
+            12 => Some(Self::Controller),
+            17 | 19 => Some(Self::Headset),
+            // Pens, speakers, remotes, wearables and unnamed Bluetooth devices. Phones and
+            // tablets show their own batteries
+            13 | 18 | 21 | 22 | 26 | 28 => Some(Self::Other),
+            _ => None,
+        }
+    }
+
+    pub fn icon(self) -> &'static str {
+        match self {
+            Self::Keyboard => "input-keyboard-symbolic",
+            Self::Mouse => "input-mouse-symbolic",
+            Self::Headset => "audio-headphones-symbolic",
+            Self::Controller => "input-gaming-symbolic",
+            Self::Other => "battery-symbolic",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Peripheral {
+    pub kind: PeripheralKind,
+    pub model: String,
+    pub percentage: f64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UpsInfo {
+    pub on_battery: bool,
+    pub percentage: f64,
+    // UPower's estimate while on battery
+    pub time_to_empty_opt: Option<Duration>,
+}
+
+pub fn peripheral_subscription() -> Subscription<Vec<Peripheral>> {
+    struct PeripheralSubscription;
+
+    subscription::channel(
+        TypeId::of::<PeripheralSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            if let Err(err) = peripheral_handler(&mut msg_tx).await {
+                log::info!("no peripheral batteries: {}", err);
+                let _ = msg_tx.send(Vec::new()).await;
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+async fn peripheral_handler(msg_tx: &mut mpsc::Sender<Vec<Peripheral>>) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let upower = UPowerProxy::new(&zbus).await?;
+
+    let mut last_opt = None;
+    loop {
+        let mut peripherals = Vec::new();
+        for path in upower.enumerate_devices().await? {
+            let device = DeviceProxy::builder(&zbus).path(path)?.build().await?;
+            // Disconnected ones linger with their last level
+            if device.power_supply().await? || !device.is_present().await? {
+                continue;
+            }
+            let Some(kind) = PeripheralKind::from_type(device.type_().await?) else {
+                continue;
+            };
+            peripherals.push(Peripheral {
+                kind,
+                model: device.model().await?,
+                percentage: device.percentage().await?,
+            });
+        }
+        if last_opt.as_ref() != Some(&peripherals) {
+            if msg_tx.send(peripherals.clone()).await.is_err() {
+                // The locker stopped listening
+                return Ok(());
+            }
+            last_opt = Some(peripherals);
+        }
+
+        time::sleep(Duration::new(30, 0)).await;
+    }
+}
+
+/// The first UPS, None without one
+pub fn ups_subscription() -> Subscription<Option<UpsInfo>> {
+    struct UpsSubscription;
+
+    subscription::channel(
+        TypeId::of::<UpsSubscription>(),
+        16,
+        |mut msg_tx| async move {
+            if let Err(err) = ups_handler(&mut msg_tx).await {
+                log::info!("no UPS: {}", err);
+                let _ = msg_tx.send(None).await;
+            }
+
+            loop {
+                time::sleep(Duration::new(60, 0)).await;
+            }
+        },
+    )
+}
+
+async fn ups_handler(msg_tx: &mut mpsc::Sender<Option<UpsInfo>>) -> Result<()> {
+    let zbus = Connection::system().await?;
+    let upower = UPowerProxy::new(&zbus).await?;
+
+    let mut last_opt = None;
+    loop {
+        let mut info_opt = None;
+        for path in upower.enumerate_devices().await? {
+            let device = DeviceProxy::builder(&zbus).path(path)?.build().await?;
+            if device.type_().await? != UPS_TYPE || !device.is_present().await? {
+                continue;
+            }
+            let on_battery = ON_BATTERY_STATES.contains(&device.state().await?);
+            let time_to_empty = device.time_to_empty().await?;
+            info_opt = Some(UpsInfo {
+                on_battery,
+                percentage: device.percentage().await?,
+                time_to_empty_opt: (on_battery && time_to_empty > 0)
+                    .then(|| Duration::from_secs(time_to_empty as u64)),
+            });
+            break;
+        }
+        if last_opt.as_ref() != Some(&info_opt) {
+            if msg_tx.send(info_opt.clone()).await.is_err() {
+                // The locker stopped listening
+                return Ok(());
+            }
+            last_opt = Some(info_opt);
+        }
+
+        // Runtime on battery can be short, the locker may have to act on it
+        time::sleep(Duration::new(10, 0)).await;
+    }
+}
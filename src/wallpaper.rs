@@ -0,0 +1,189 @@
+//! The desktop wallpaper from cosmic-bg's config, so the lock screen matches the desktop
+
+use chrono::Local;
+use cosmic::{
+    cosmic_config::{self, ConfigGet},
+    iced::{widget::image, ContentFit},
+};
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+const CONFIG_ID: &str = "com.system76.CosmicBackground";
+const CONFIG_VERSION: u64 = 1;
+// Entry for outputs without their own, and for all of them with same-on-all
+const ALL: &str = "all";
+const IMAGE_EXTENSIONS: [&str; 5] = ["jpg", "jpeg", "png", "webp", "jxl"];
+
+// The parts of cosmic-bg's entries the lock screen draws, the rest is ignored
+
+#[derive(Deserialize)]
+struct Entry {
+    output: String,
+    source: Source,
+    // Seconds between images of a slideshow
+    #[serde(default)]
+    rotation_frequency: u64,
+    #[serde(default)]
+    scaling_mode: ScalingMode,
+}
+
+#[derive(Deserialize)]
+enum Source {
+    // An image, or a directory for a slideshow
+    Path(PathBuf),
+    Color(Color),
+}
+
+#[derive(Deserialize)]
+enum Color {
+    Single([f32; 3]),
+    Gradient(Gradient),
+}
+
+#[derive(Deserialize)]
+struct Gradient {
+    colors: Vec<[f32; 3]>,
+}
+
+#[derive(Default, Deserialize)]
+enum ScalingMode {
+    // Letterboxed with this color
+    Fit([f32; 3]),
+    Stretch,
+    #[default]
+    Zoom,
+}
+
+#[derive(Clone, Debug)]
+pub enum Wallpaper {
+    Image {
+        handle: image::Handle,
+        content_fit: ContentFit,
+        // Repeated at its own size, content_fit is unused then
+        tile: bool,
+        // Around an image that doesn't fill the output
+        color_opt: Option<[f32; 3]>,
+    },
+    Color([f32; 3]),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Wallpapers {
+    all_opt: Option<Wallpaper>,
+    // Keyed by output name like "DP-1"
+    outputs: HashMap<String, Wallpaper>,
+}
+
+impl Wallpapers {
+    /// The same wallpaper on every output
+    pub fn all(wallpaper: Wallpaper) -> Self {
+        Self {
+            all_opt: Some(wallpaper),
+            outputs: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, output_name: &str) -> Option<&Wallpaper> {
+        self.outputs.get(output_name).or(self.all_opt.as_ref())
+    }
+
+    /// Every image shown on some output
+    pub fn images(&self) -> impl Iterator<Item = &image::Handle> {
+        self.all_opt
+            .iter()
+            .chain(self.outputs.values())
+            .filter_map(|wallpaper| match wallpaper {
+                Wallpaper::Image { handle, .. } => Some(handle),
+                Wallpaper::Color(_) => None,
+            })
+    }
+}
+
+/// The wallpapers set in COSMIC Settings, empty without cosmic-bg
+pub fn load() -> Wallpapers {
+    let mut wallpapers = Wallpapers::default();
+    let config = match cosmic_config::Config::new(CONFIG_ID, CONFIG_VERSION) {
+        Ok(config) => config,
+        Err(err) => {
+            log::info!("failed to open cosmic-bg config: {:?}", err);
+            return wallpapers;
+        }
+    };
+
+    wallpapers.all_opt = entry(&config, ALL).and_then(|entry| wallpaper(&entry));
+    if config.get::<bool>("same-on-all").unwrap_or(true) {
+        return wallpapers;
+    }
+    // Keys of the per-output entries, each names its output
+    let keys = config.get::<Vec<String>>("backgrounds").unwrap_or_default();
+    for key in keys {
+        let Some(entry) = entry(&config, &key) else {
+            continue;
+        };
+        if let Some(wallpaper) = wallpaper(&entry) {
+            wallpapers.outputs.insert(entry.output.clone(), wallpaper);
+        }
+    }
+    wallpapers
+}
+
+fn entry(config: &cosmic_config::Config, key: &str) -> Option<Entry> {
+    config
+        .get::<Entry>(key)
+        .map_err(|err| log::info!("failed to read cosmic-bg {}: {:?}", key, err))
+        .ok()
+}
+
+fn wallpaper(entry: &Entry) -> Option<Wallpaper> {
+    match &entry.source {
+        Source::Path(path) => {
+            let path = if path.is_dir() {
+                slideshow_image(path, entry.rotation_frequency)?
+            } else {
+                path.clone()
+            };
+            let (content_fit, color_opt) = match entry.scaling_mode {
+                ScalingMode::Fit(color) => (ContentFit::Contain, Some(color)),
+                ScalingMode::Stretch => (ContentFit::Fill, None),
+                ScalingMode::Zoom => (ContentFit::Cover, None),
+            };
+            Some(Wallpaper::Image {
+                handle: image::Handle::from_path(path),
+                content_fit,
+                tile: false,
+                color_opt,
+            })
+        }
+        Source::Color(Color::Single(color)) => Some(Wallpaper::Color(*color)),
+        // No gradients in iced containers, the first color is close enough behind the lock
+        Source::Color(Color::Gradient(gradient)) => {
+            gradient.colors.first().copied().map(Wallpaper::Color)
+        }
+    }
+}
+
+// cosmic-bg shuffles, so this is only the image it would be on if it went in order
+fn slideshow_image(dir: &Path, rotation_frequency: u64) -> Option<PathBuf> {
+    let mut images: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|err| log::info!("failed to read slideshow {}: {}", dir.display(), err))
+        .ok()?
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.extension()
+                .and_then(|extension| extension.to_str())
+                .map_or(false, |extension| {
+                    IMAGE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+                })
+        })
+        .collect();
+    if images.is_empty() {
+        return None;
+    }
+    images.sort();
+    let slot = Local::now().timestamp() as u64 / rotation_frequency.max(1);
+    Some(images.swap_remove(slot as usize % images.len()))
+}